@@ -16,18 +16,19 @@
     (fn [idxAv]
       (do
         (def idxA idxAv)
-        (map
-          (fn [idxBv]
-            (do
-              (def idxB idxBv)
-              (reduce
-                +
-                (map
-                  #(*
-                    (nth (nth matrixA idxA) %)
-                    (nth (nth matrixB %) idxB))
-                  (range dA2)))))
-          (range dB2))))
+        (doall
+          (map
+            (fn [idxBv]
+              (do
+                (def idxB idxBv)
+                (reduce
+                  +
+                  (map
+                    #(*
+                      (nth (nth matrixA idxA) %)
+                      (nth (nth matrixB %) idxB))
+                    (range dA2)))))
+            (range dB2)))))
     (range dA1)))
 
 (println "Matrix A:" matrixA)
@@ -26,7 +26,7 @@
         (if (= idxB dB2)
           (recur (inc idxA) 0 (conj result row) [])
           (recur idxA (inc idxB) result
-            (conj row (pos_matrix_mult A B idxA idxB dA2))))))))
+                 (conj row (pos_matrix_mult A B idxA idxB dA2))))))))
 
 (println "Matrix A:" matrixA)
 (println "Matrix B:" matrixB)
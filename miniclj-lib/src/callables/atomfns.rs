@@ -0,0 +1,394 @@
+use crate::{callables::prelude::*, vm::Atom as AtomValue};
+
+fn expect_atom(name: &str, value: Value) -> RuntimeResult<AtomValue> {
+    match value {
+        Value::Atom(atom) => Ok(atom),
+        _ => Err(RuntimeError::WrongDataType(
+            name.into(),
+            "an atom",
+            value.type_str(),
+        )),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Atom;
+
+impl Callable for Atom {
+    fn name(&self) -> &'static str {
+        "atom"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args == 1 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<value>".into(),
+            ))
+        }
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, _: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if args.len() != 1 {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "one value".into(),
+                args.len(),
+            ));
+        }
+
+        let value = args.into_iter().next().unwrap();
+        Ok(Value::Atom(AtomValue::new(value)))
+    }
+}
+
+display_for_callable!(Atom);
+
+#[derive(Debug, Clone)]
+pub struct Deref;
+
+impl Callable for Deref {
+    fn name(&self) -> &'static str {
+        "deref"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args == 1 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<atom>".into(),
+            ))
+        }
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, _: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if args.len() != 1 {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "one atom".into(),
+                args.len(),
+            ));
+        }
+
+        let atom = expect_atom(self.name(), args.into_iter().next().unwrap())?;
+        Ok(atom.deref())
+    }
+}
+
+display_for_callable!(Deref);
+
+#[derive(Debug, Clone)]
+pub struct Reset;
+
+impl Callable for Reset {
+    fn name(&self) -> &'static str {
+        "reset!"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args == 2 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<atom> <value>".into(),
+            ))
+        }
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if args.len() != 2 {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "an atom and a value".into(),
+                args.len(),
+            ));
+        }
+
+        let mut args_iter = args.into_iter();
+        let atom = expect_atom(self.name(), args_iter.next().unwrap())?;
+        atom.reset(state, args_iter.next().unwrap())
+    }
+}
+
+display_for_callable!(Reset);
+
+#[derive(Debug, Clone)]
+pub struct Swap;
+
+impl Callable for Swap {
+    fn name(&self) -> &'static str {
+        "swap!"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args >= 2 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<atom> <function> <...args>".into(),
+            ))
+        }
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if args.len() < 2 {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "an atom, a function and any number of arguments".into(),
+                args.len(),
+            ));
+        }
+
+        let mut args_iter = args.into_iter();
+        let atom = expect_atom(self.name(), args_iter.next().unwrap())?;
+        let maybe_fn = args_iter.next().unwrap();
+        let fn_value = match maybe_fn {
+            Value::Callable(..) | Value::Lambda(..) => Ok(maybe_fn),
+            _ => Err(RuntimeError::WrongDataType(
+                self.name().into(),
+                "a function",
+                maybe_fn.type_str(),
+            )),
+        }?;
+        atom.swap(state, &fn_value, args_iter.collect())
+    }
+}
+
+display_for_callable!(Swap);
+
+#[derive(Debug, Clone)]
+pub struct CompareAndSet;
+
+impl Callable for CompareAndSet {
+    fn name(&self) -> &'static str {
+        "compare-and-set!"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args == 3 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<atom> <old value> <new value>".into(),
+            ))
+        }
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if args.len() != 3 {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "an atom, an old value and a new value".into(),
+                args.len(),
+            ));
+        }
+
+        let mut args_iter = args.into_iter();
+        let atom = expect_atom(self.name(), args_iter.next().unwrap())?;
+        let old_value = args_iter.next().unwrap();
+        let new_value = args_iter.next().unwrap();
+        Ok(Value::from(
+            atom.compare_and_set(state, &old_value, new_value)?,
+        ))
+    }
+}
+
+display_for_callable!(CompareAndSet);
+
+#[derive(Debug, Clone)]
+pub struct AddWatch;
+
+impl Callable for AddWatch {
+    fn name(&self) -> &'static str {
+        "add-watch"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args == 3 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<atom> <key> <function>".into(),
+            ))
+        }
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, _: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if args.len() != 3 {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "an atom, a key and a function".into(),
+                args.len(),
+            ));
+        }
+
+        let mut args_iter = args.into_iter();
+        let maybe_atom = args_iter.next().unwrap();
+        let atom = expect_atom(self.name(), maybe_atom.clone())?;
+        let key = args_iter.next().unwrap();
+        let maybe_fn = args_iter.next().unwrap();
+        let fn_value = match maybe_fn {
+            Value::Callable(..) | Value::Lambda(..) => Ok(maybe_fn),
+            _ => Err(RuntimeError::WrongDataType(
+                self.name().into(),
+                "a function",
+                maybe_fn.type_str(),
+            )),
+        }?;
+        atom.add_watch(key, fn_value);
+        Ok(maybe_atom)
+    }
+}
+
+display_for_callable!(AddWatch);
+
+#[derive(Debug, Clone)]
+pub struct RemoveWatch;
+
+impl Callable for RemoveWatch {
+    fn name(&self) -> &'static str {
+        "remove-watch"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args == 2 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<atom> <key>".into(),
+            ))
+        }
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, _: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if args.len() != 2 {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "an atom and a key".into(),
+                args.len(),
+            ));
+        }
+
+        let mut args_iter = args.into_iter();
+        let maybe_atom = args_iter.next().unwrap();
+        let atom = expect_atom(self.name(), maybe_atom.clone())?;
+        atom.remove_watch(&args_iter.next().unwrap());
+        Ok(maybe_atom)
+    }
+}
+
+display_for_callable!(RemoveWatch);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{Engine, EngineError};
+
+    fn eval(engine: &mut Engine, source: &str) -> String {
+        format!("{}", engine.eval_str(source).unwrap().readable())
+    }
+
+    #[test]
+    fn test_swap_and_compare_and_set() {
+        let mut engine = Engine::new();
+        assert_eq!(eval(&mut engine, "(def a (atom 1)) (swap! a + 2 3)"), "6");
+        assert_eq!(eval(&mut engine, "@a"), "6");
+        assert_eq!(eval(&mut engine, "(compare-and-set! a 5 7)"), "0");
+        assert_eq!(eval(&mut engine, "@a"), "6");
+        assert_eq!(eval(&mut engine, "(compare-and-set! a 6 [7])"), "1");
+        assert_eq!(eval(&mut engine, "(compare-and-set! a [7] 8)"), "1");
+        assert_eq!(eval(&mut engine, "(deref a)"), "8");
+        assert!(matches!(
+            engine.eval_str("(swap! 1 + 1)"),
+            Err(EngineError::RuntimeError(RuntimeError::WrongDataType(callable, ..)))
+                if callable == "swap!"
+        ));
+    }
+
+    #[test]
+    fn test_watches() {
+        let mut engine = Engine::new();
+        let source = "(def calls (atom []))
+            (def a (atom 0))
+            (add-watch a \"w\" (fn [k r old new] (swap! calls conj [k (= r a) old new])))
+            (reset! a 1)
+            (swap! a + 2)
+            (compare-and-set! a 0 9)
+            (compare-and-set! a 3 4)
+            @calls";
+        assert_eq!(
+            eval(&mut engine, source),
+            "[[\"w\" 1 0 1] [\"w\" 1 1 3] [\"w\" 1 3 4]]"
+        );
+
+        // Adding a watch with the same key replaces the previous one
+        let source = "(reset! calls [])
+            (add-watch a \"w\" (fn [k r old new] (swap! calls conj new)))
+            (reset! a 5)
+            (remove-watch a \"w\")
+            (reset! a 6)
+            @calls";
+        assert_eq!(eval(&mut engine, source), "[5]");
+    }
+
+    #[test]
+    fn test_display() {
+        let mut engine = Engine::new();
+        let atom = engine.eval_str("(def a (atom [1 \"b\"])) a").unwrap();
+        assert_eq!(format!("{}", atom), "#<atom [1 b]>");
+        assert_eq!(format!("{}", atom.readable()), "#<atom [1 \"b\"]>");
+
+        // An atom that contains itself isn't written again
+        engine.eval_str("(reset! a [a (atom a)])").unwrap();
+        assert_eq!(
+            format!("{}", atom),
+            "#<atom [#<atom ...> #<atom #<atom ...>>]>"
+        );
+        assert_eq!(
+            format!("{}", atom.readable()),
+            "#<atom [#<atom ...> #<atom #<atom ...>>]>"
+        );
+        assert!(format!("{:?}", atom).ends_with("#<atom #<atom ...>>]>"));
+        assert_eq!(
+            eval(&mut engine, "(pr-str a)"),
+            "\"#<atom [#<atom ...> #<atom #<atom ...>>]>\""
+        );
+    }
+}
@@ -11,7 +11,7 @@ use crate::{
 
 /// Base trait that all language callables must implement
 pub trait Callable: Display + Debug + DynClone {
-    fn name(&self) -> &'static str;
+    fn name(&self) -> &str;
 
     fn compile(&self, state: &mut CompilerState, args: Vec<SExpr>) -> CompilationResult {
         self.check_arity(args.len())?;
@@ -1,4 +1,7 @@
-use crate::{callables::prelude::*, vm::List};
+use crate::{
+    callables::prelude::*,
+    vm::{LazySeq, List, Thunk},
+};
 
 #[derive(Debug, Clone)]
 pub struct First;
@@ -12,7 +15,10 @@ impl Callable for First {
         if num_args == 1 {
             Ok(())
         } else {
-            Err(CompilationError::WrongArity(self.name(), "<collection>"))
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<collection>".into(),
+            ))
         }
     }
 
@@ -20,18 +26,21 @@ impl Callable for First {
         Some(state.get_callable_addr(Box::new(self.clone())))
     }
 
-    fn execute(&self, _: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
         if args.len() != 1 {
             return Err(RuntimeError::WrongArityS(
-                self.name(),
-                "a collection",
+                self.name().into(),
+                "a collection".into(),
                 args.len(),
             ));
         }
 
         let maybe_coll = args.into_iter().next().unwrap();
+        if let Value::LazySeq(seq) = maybe_coll {
+            return Ok(seq.force(state)?.map_or(Value::Nil, |(first, _)| first));
+        }
         let coll_as_list = List::try_from(maybe_coll).map_err(|type_str| {
-            RuntimeError::WrongDataType(self.name(), "a collection", type_str)
+            RuntimeError::WrongDataType(self.name().into(), "a collection", type_str)
         })?;
 
         let first = match coll_as_list {
@@ -56,7 +65,10 @@ impl Callable for Rest {
         if num_args == 1 {
             Ok(())
         } else {
-            Err(CompilationError::WrongArity(self.name(), "<collection>"))
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<collection>".into(),
+            ))
         }
     }
 
@@ -64,18 +76,24 @@ impl Callable for Rest {
         Some(state.get_callable_addr(Box::new(self.clone())))
     }
 
-    fn execute(&self, _: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
         if args.len() != 1 {
             return Err(RuntimeError::WrongArityS(
-                self.name(),
-                "a collection",
+                self.name().into(),
+                "a collection".into(),
                 args.len(),
             ));
         }
 
         let maybe_coll = args.into_iter().next().unwrap();
+        if let Value::LazySeq(seq) = maybe_coll {
+            let rest = seq
+                .force(state)?
+                .map_or_else(LazySeq::empty, |(_, rest)| rest);
+            return Ok(Value::LazySeq(rest));
+        }
         let coll_as_list = List::try_from(maybe_coll).map_err(|type_str| {
-            RuntimeError::WrongDataType(self.name(), "a collection", type_str)
+            RuntimeError::WrongDataType(self.name().into(), "a collection", type_str)
         })?;
 
         let rest = match coll_as_list {
@@ -101,8 +119,8 @@ impl Callable for Nth {
             Ok(())
         } else {
             Err(CompilationError::WrongArity(
-                self.name(),
-                "<collection> <index>",
+                self.name().into(),
+                "<collection> <index>".into(),
             ))
         }
     }
@@ -111,11 +129,11 @@ impl Callable for Nth {
         Some(state.get_callable_addr(Box::new(self.clone())))
     }
 
-    fn execute(&self, _: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
         if args.len() != 2 {
             return Err(RuntimeError::WrongArityS(
-                self.name(),
-                "a collection and an index",
+                self.name().into(),
+                "a collection and an index".into(),
                 args.len(),
             ));
         }
@@ -124,13 +142,24 @@ impl Callable for Nth {
         let maybe_coll = args_iter.next().unwrap();
         let maybe_coll_type = maybe_coll.type_str();
         let index = args_iter.next().unwrap().as_usize().map_err(|type_str| {
-            RuntimeError::WrongDataType(self.name(), "a positive number", type_str)
+            RuntimeError::WrongDataType(self.name().into(), "a positive number", type_str)
         })?;
 
         match maybe_coll {
             Value::List(l) => l
                 .nth(index)
                 .ok_or(RuntimeError::IndexOutOfBounds(maybe_coll_type)),
+            Value::LazySeq(mut seq) => {
+                for _ in 0..index {
+                    seq = match seq.force(state)? {
+                        Some((_, rest)) => rest,
+                        None => return Err(RuntimeError::IndexOutOfBounds(maybe_coll_type)),
+                    };
+                }
+                seq.force(state)?
+                    .map(|(value, _)| value)
+                    .ok_or(RuntimeError::IndexOutOfBounds(maybe_coll_type))
+            }
             Value::Vector(v) => v
                 .into_iter()
                 .nth(index)
@@ -141,7 +170,7 @@ impl Callable for Nth {
                 .ok_or(RuntimeError::IndexOutOfBounds(maybe_coll_type))
                 .map(|c| Value::String(String::from(c))),
             _ => Err(RuntimeError::WrongDataType(
-                self.name(),
+                self.name().into(),
                 "a collection",
                 maybe_coll_type,
             )),
@@ -164,8 +193,8 @@ impl Callable for Get {
             Ok(())
         } else {
             Err(CompilationError::WrongArity(
-                self.name(),
-                "<collection> <key>",
+                self.name().into(),
+                "<collection> <key>".into(),
             ))
         }
     }
@@ -174,11 +203,11 @@ impl Callable for Get {
         Some(state.get_callable_addr(Box::new(self.clone())))
     }
 
-    fn execute(&self, _: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
         if args.len() != 2 {
             return Err(RuntimeError::WrongArityS(
-                self.name(),
-                "a collection and a key",
+                self.name().into(),
+                "a collection and a key".into(),
                 args.len(),
             ));
         }
@@ -188,25 +217,25 @@ impl Callable for Get {
         let key = args_iter.next().unwrap();
 
         match maybe_coll {
-            Value::List(_) => Ok(Value::Nil),
+            Value::List(_) | Value::LazySeq(_) => Ok(Value::Nil),
             Value::Vector(v) => {
                 let index = key.as_usize().map_err(|type_str| {
-                    RuntimeError::WrongDataType(self.name(), "a positive number", type_str)
+                    RuntimeError::WrongDataType(self.name().into(), "a positive number", type_str)
                 })?;
                 Ok(v.into_iter().nth(index).unwrap_or(Value::Nil))
             }
-            Value::Set(s) => Ok(s.get(&key).cloned().unwrap_or(Value::Nil)),
-            Value::Map(m) => Ok(m.get(&key).cloned().unwrap_or(Value::Nil)),
+            Value::Set(s) => Ok(s.get(&key.realize(state)?).cloned().unwrap_or(Value::Nil)),
+            Value::Map(m) => Ok(m.get(&key.realize(state)?).cloned().unwrap_or(Value::Nil)),
             Value::String(s) => {
                 let index = key.as_usize().map_err(|type_str| {
-                    RuntimeError::WrongDataType(self.name(), "a positive number", type_str)
+                    RuntimeError::WrongDataType(self.name().into(), "a positive number", type_str)
                 })?;
                 Ok(s.chars()
                     .nth(index)
                     .map_or(Value::Nil, |c| Value::String(String::from(c))))
             }
             _ => Err(RuntimeError::WrongDataType(
-                self.name(),
+                self.name().into(),
                 "a collection",
                 maybe_coll.type_str(),
             )),
@@ -228,7 +257,10 @@ impl Callable for Count {
         if num_args == 1 {
             Ok(())
         } else {
-            Err(CompilationError::WrongArity(self.name(), "<collection>"))
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<collection>".into(),
+            ))
         }
     }
 
@@ -236,11 +268,11 @@ impl Callable for Count {
         Some(state.get_callable_addr(Box::new(self.clone())))
     }
 
-    fn execute(&self, _: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
         if args.len() != 1 {
             return Err(RuntimeError::WrongArityS(
-                self.name(),
-                "a collection",
+                self.name().into(),
+                "a collection".into(),
                 args.len(),
             ));
         }
@@ -248,13 +280,21 @@ impl Callable for Count {
         let maybe_coll = args.into_iter().next().unwrap();
         match maybe_coll {
             Value::List(l) => Ok(l.len()),
+            Value::LazySeq(mut seq) => {
+                let mut count = 0;
+                while let Some((_, rest)) = seq.force(state)? {
+                    count += 1;
+                    seq = rest;
+                }
+                Ok(count)
+            }
             Value::Vector(v) => Ok(v.len()),
             Value::Set(s) => Ok(s.len()),
             Value::Map(m) => Ok(m.len()),
             Value::String(s) => Ok(s.chars().count()),
             Value::Nil => Ok(0),
             _ => Err(RuntimeError::WrongDataType(
-                self.name(),
+                self.name().into(),
                 "a collection",
                 maybe_coll.type_str(),
             )),
@@ -277,7 +317,10 @@ impl Callable for IsEmpty {
         if num_args == 1 {
             Ok(())
         } else {
-            Err(CompilationError::WrongArity(self.name(), "<collection>"))
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<collection>".into(),
+            ))
         }
     }
 
@@ -285,11 +328,11 @@ impl Callable for IsEmpty {
         Some(state.get_callable_addr(Box::new(self.clone())))
     }
 
-    fn execute(&self, _: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
         if args.len() != 1 {
             return Err(RuntimeError::WrongArityS(
-                self.name(),
-                "a collection",
+                self.name().into(),
+                "a collection".into(),
                 args.len(),
             ));
         }
@@ -297,6 +340,7 @@ impl Callable for IsEmpty {
         let maybe_coll = args.into_iter().next().unwrap();
         match maybe_coll {
             Value::List(List::EmptyList) => Ok(true),
+            Value::LazySeq(seq) => Ok(seq.force(state)?.is_none()),
             Value::List(List::Cons(..)) => Ok(false),
             Value::Vector(v) => Ok(v.is_empty()),
             Value::Set(s) => Ok(s.is_empty()),
@@ -304,7 +348,7 @@ impl Callable for IsEmpty {
             Value::String(s) => Ok(s.is_empty()),
             Value::Nil => Ok(true),
             _ => Err(RuntimeError::WrongDataType(
-                self.name(),
+                self.name().into(),
                 "a collection",
                 maybe_coll.type_str(),
             )),
@@ -314,3 +358,141 @@ impl Callable for IsEmpty {
 }
 
 display_for_callable!(IsEmpty);
+
+#[derive(Debug, Clone)]
+pub struct Take;
+
+impl Callable for Take {
+    fn name(&self) -> &'static str {
+        "take"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args == 2 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<n> <collection>".into(),
+            ))
+        }
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, _: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if args.len() != 2 {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "a number and a collection".into(),
+                args.len(),
+            ));
+        }
+
+        let mut args_iter = args.into_iter();
+        let n = args_iter.next().unwrap().as_usize().map_err(|type_str| {
+            RuntimeError::WrongDataType(self.name().into(), "a positive number", type_str)
+        })?;
+        let seq = LazySeq::try_from(args_iter.next().unwrap()).map_err(|type_str| {
+            RuntimeError::WrongDataType(self.name().into(), "a collection", type_str)
+        })?;
+
+        Ok(Value::LazySeq(LazySeq::new(Thunk::Take(n, seq))))
+    }
+}
+
+display_for_callable!(Take);
+
+#[derive(Debug, Clone)]
+pub struct TakeWhile;
+
+impl Callable for TakeWhile {
+    fn name(&self) -> &'static str {
+        "take-while"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args == 2 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<function> <collection>".into(),
+            ))
+        }
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, _: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if args.len() != 2 {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "a function and one collection".into(),
+                args.len(),
+            ));
+        }
+
+        let mut args_iter = args.into_iter();
+        let maybe_fn = args_iter.next().unwrap();
+        let fn_value = match maybe_fn {
+            Value::Callable(..) | Value::Lambda(..) => Ok(maybe_fn),
+            _ => Err(RuntimeError::WrongDataType(
+                self.name().into(),
+                "a function",
+                maybe_fn.type_str(),
+            )),
+        }?;
+        let seq = LazySeq::try_from(args_iter.next().unwrap()).map_err(|type_str| {
+            RuntimeError::WrongDataType(self.name().into(), "a collection", type_str)
+        })?;
+
+        Ok(Value::LazySeq(LazySeq::new(Thunk::TakeWhile(
+            fn_value, seq,
+        ))))
+    }
+}
+
+display_for_callable!(TakeWhile);
+
+#[derive(Debug, Clone)]
+pub struct Doall;
+
+impl Callable for Doall {
+    fn name(&self) -> &'static str {
+        "doall"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args == 1 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<collection>".into(),
+            ))
+        }
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if args.len() != 1 {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "a collection".into(),
+                args.len(),
+            ));
+        }
+
+        args.into_iter().next().unwrap().realize(state)
+    }
+}
+
+display_for_callable!(Doall);
@@ -1,6 +1,7 @@
-use std::collections::HashMap as RustHashMap;
-
-use crate::callables::prelude::*;
+use crate::{
+    callables::prelude::*,
+    vm::{ValueMap, ValueSet},
+};
 
 #[derive(Debug, Clone)]
 pub struct List;
@@ -64,8 +65,8 @@ impl Callable for Set {
         Some(state.get_callable_addr(Box::new(self.clone())))
     }
 
-    fn execute(&self, _: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
-        Ok(Value::Set(args.into_iter().collect()))
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        Ok(Value::Set(state.realize_all(args)?.into_iter().collect()))
     }
 }
 
@@ -84,8 +85,8 @@ impl Callable for HashMap {
             Ok(())
         } else {
             Err(CompilationError::WrongArity(
-                self.name(),
-                "<...pairs of values>",
+                self.name().into(),
+                "<...pairs of values>".into(),
             ))
         }
     }
@@ -94,20 +95,20 @@ impl Callable for HashMap {
         Some(state.get_callable_addr(Box::new(self.clone())))
     }
 
-    fn execute(&self, _: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
         if args.len() % 2 == 1 {
             return Err(RuntimeError::WrongArityS(
-                self.name(),
-                "a pair number of values",
+                self.name().into(),
+                "a pair number of values".into(),
                 args.len(),
             ));
         }
 
-        let mut hashmap = RustHashMap::new();
+        let mut hashmap = ValueMap::new();
         let mut args_iter = args.into_iter();
         while let Some(key) = args_iter.next() {
             let val = args_iter.next().unwrap();
-            hashmap.insert(key, val);
+            hashmap.insert(key.realize(state)?, val);
         }
 
         Ok(Value::Map(hashmap))
@@ -115,3 +116,73 @@ impl Callable for HashMap {
 }
 
 display_for_callable!(HashMap);
+
+#[derive(Debug, Clone)]
+pub struct SortedSet;
+
+impl Callable for SortedSet {
+    fn name(&self) -> &'static str {
+        "sorted-set"
+    }
+
+    fn check_arity(&self, _: usize) -> Result<(), CompilationError> {
+        Ok(())
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        let mut set = ValueSet::sorted();
+        set.extend(state.realize_all(args)?);
+        Ok(Value::Set(set))
+    }
+}
+
+display_for_callable!(SortedSet);
+
+#[derive(Debug, Clone)]
+pub struct SortedMap;
+
+impl Callable for SortedMap {
+    fn name(&self) -> &'static str {
+        "sorted-map"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args % 2 == 0 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<...pairs of values>".into(),
+            ))
+        }
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if args.len() % 2 == 1 {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "a pair number of values".into(),
+                args.len(),
+            ));
+        }
+
+        let mut map = ValueMap::sorted();
+        let mut args_iter = args.into_iter();
+        while let Some(key) = args_iter.next() {
+            let val = args_iter.next().unwrap();
+            map.insert(key.realize(state)?, val);
+        }
+
+        Ok(Value::Map(map))
+    }
+}
+
+display_for_callable!(SortedMap);
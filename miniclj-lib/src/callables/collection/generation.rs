@@ -1,4 +1,13 @@
-use crate::callables::prelude::*;
+use std::rc::Rc;
+
+use crate::{
+    callables::prelude::*,
+    compiler::{CompilationResult, SExpr},
+    constant::Constant,
+    instruction::Instruction,
+    memaddress::Lifetime,
+    vm::{self, Thunk},
+};
 
 #[derive(Debug, Clone)]
 pub struct Range;
@@ -9,12 +18,69 @@ impl Callable for Range {
     }
 
     fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
-        if (1..=3).contains(&num_args) {
+        if (0..=3).contains(&num_args) {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                ") or (range <stop num>) or (range <start> <stop>) or (range <start> <stop> <step>"
+                    .into(),
+            ))
+        }
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, _: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if args.len() > 3 {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "at most three numbers".into(),
+                args.len(),
+            ));
+        }
+
+        let mut nums = args
+            .into_iter()
+            .map(|value| {
+                value.as_usize().map_err(|type_str| {
+                    RuntimeError::WrongDataType(self.name().into(), "a positive number", type_str)
+                })
+            })
+            .collect::<RuntimeResult<Vec<usize>>>()?
+            .into_iter();
+
+        let (start, stop, step) = match nums.len() {
+            0 => (0, None, 1),
+            1 => (0, nums.next(), 1),
+            _ => (nums.next().unwrap(), nums.next(), nums.next().unwrap_or(1)),
+        };
+
+        Ok(Value::LazySeq(vm::LazySeq::new(Thunk::Range(
+            start, stop, step,
+        ))))
+    }
+}
+
+display_for_callable!(Range);
+
+#[derive(Debug, Clone)]
+pub struct Iterate;
+
+impl Callable for Iterate {
+    fn name(&self) -> &'static str {
+        "iterate"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args == 2 {
             Ok(())
         } else {
             Err(CompilationError::WrongArity(
-                self.name(),
-                "<stop num>) or (range <start> <stop>) or (range <start> <stop> <step>",
+                self.name().into(),
+                "<function> <initial value>".into(),
             ))
         }
     }
@@ -24,47 +90,192 @@ impl Callable for Range {
     }
 
     fn execute(&self, _: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
-        if args.is_empty() {
+        if args.len() != 2 {
             return Err(RuntimeError::WrongArityS(
-                self.name(),
-                "at least one number",
+                self.name().into(),
+                "a function and an initial value".into(),
                 args.len(),
             ));
         }
 
         let mut args_iter = args.into_iter();
+        let maybe_fn = args_iter.next().unwrap();
+        let fn_value = match maybe_fn {
+            Value::Callable(..) | Value::Lambda(..) => Ok(maybe_fn),
+            _ => Err(RuntimeError::WrongDataType(
+                self.name().into(),
+                "a function",
+                maybe_fn.type_str(),
+            )),
+        }?;
+        let initial_value = args_iter.next().unwrap();
+
+        let rest = vm::LazySeq::new(Thunk::Iterate(fn_value, initial_value.clone()));
+        Ok(Value::LazySeq(vm::LazySeq::cons(initial_value, rest)))
+    }
+}
 
-        let start = if args_iter.len() == 1 {
-            0
+display_for_callable!(Iterate);
+
+#[derive(Debug, Clone)]
+pub struct Repeat;
+
+impl Callable for Repeat {
+    fn name(&self) -> &'static str {
+        "repeat"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if (1..=2).contains(&num_args) {
+            Ok(())
         } else {
-            args_iter.next().unwrap().as_usize().map_err(|type_str| {
-                RuntimeError::WrongDataType(self.name(), "a positive number", type_str)
-            })?
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<value>) or (repeat <n> <value>".into(),
+            ))
+        }
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, _: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if args.is_empty() || args.len() > 2 {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "a value, or a number and a value".into(),
+                args.len(),
+            ));
+        }
+
+        let mut args_iter = args.into_iter();
+        let times = if args_iter.len() == 2 {
+            let times = args_iter.next().unwrap().as_usize().map_err(|type_str| {
+                RuntimeError::WrongDataType(self.name().into(), "a positive number", type_str)
+            })?;
+            Some(times)
+        } else {
+            None
         };
-        let stop = args_iter.next().unwrap().as_usize().map_err(|type_str| {
-            RuntimeError::WrongDataType(self.name(), "a positive number", type_str)
-        })?;
-        let step = match args_iter.next() {
-            Some(value) => value.as_usize().map_err(|type_str| {
-                RuntimeError::WrongDataType(self.name(), "a positive number", type_str)
-            }),
-            None => Ok(1),
-        }?;
+        let value = args_iter.next().unwrap();
+
+        Ok(Value::LazySeq(vm::LazySeq::new(Thunk::Repeat(
+            value, times,
+        ))))
+    }
+}
+
+display_for_callable!(Repeat);
+
+#[derive(Debug, Clone)]
+pub struct Cycle;
+
+impl Callable for Cycle {
+    fn name(&self) -> &'static str {
+        "cycle"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args == 1 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<collection>".into(),
+            ))
+        }
+    }
 
-        let mut list = Vec::new();
-        let mut next_val = start;
-        while next_val < stop {
-            list.push(next_val);
-            next_val += step;
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if args.len() != 1 {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "a collection".into(),
+                args.len(),
+            ));
         }
 
-        Ok(Value::List(
-            list.into_iter()
-                .rev()
-                .map(|n| Value::from(n as i64))
-                .collect(),
-        ))
+        let maybe_coll = args.into_iter().next().unwrap();
+        let values = vm::LazySeq::try_from(maybe_coll)
+            .map_err(|type_str| {
+                RuntimeError::WrongDataType(self.name().into(), "a collection", type_str)
+            })?
+            .realize(state)?;
+
+        Ok(Value::LazySeq(vm::LazySeq::new(Thunk::Cycle(
+            Rc::new(values),
+            0,
+        ))))
     }
 }
 
-display_for_callable!(Range);
+display_for_callable!(Cycle);
+
+#[derive(Debug, Clone)]
+pub struct LazySeq;
+
+impl Callable for LazySeq {
+    fn name(&self) -> &'static str {
+        "lazy-seq"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args == 1 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<body>".into(),
+            ))
+        }
+    }
+
+    /// The body is compiled as a lambda that receives the local variables
+    /// of the current scope, and is called the first time the sequence is used
+    fn inner_compile(&self, state: &mut CompilerState, args: Vec<SExpr>) -> CompilationResult {
+        let body_arg = args.into_iter().next().unwrap();
+        let (captured_names, captured_addrs): (Vec<_>, Vec<_>) =
+            state.local_symbols().into_iter().unzip();
+
+        let jump_lambda_instr = Instruction::new_jump(None);
+        let jump_lambda_instr_ptr = state.add_instruction(jump_lambda_instr);
+        let lambda_start_ptr = state.instruction_ptr();
+        let lambda_const = Constant::new_lambda(lambda_start_ptr, captured_names.len());
+        let lambda_addr = state.insert_constant(lambda_const);
+
+        state.compile_lambda(captured_names, body_arg)?;
+        state.fill_jump(jump_lambda_instr_ptr, state.instruction_ptr());
+
+        let callable_addr = state.get_callable_addr(Box::new(self.clone()));
+        let mut arg_addrs = vec![lambda_addr];
+        arg_addrs.extend(captured_addrs);
+        let res_addr = state.new_address(Lifetime::Temporal);
+        let instruction = Instruction::new_call(callable_addr, arg_addrs, res_addr);
+        state.add_instruction(instruction);
+
+        Ok(res_addr)
+    }
+
+    fn execute(&self, _: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        let mut args_iter = args.into_iter();
+        let lambda = args_iter.next().ok_or_else(|| {
+            RuntimeError::CompilerError(format!(
+                "Compiler should output \"{}\" calls with a body lambda",
+                self.name()
+            ))
+        })?;
+        let captured_values = args_iter.collect();
+
+        Ok(Value::LazySeq(vm::LazySeq::new(Thunk::Call(
+            lambda,
+            captured_values,
+        ))))
+    }
+}
+
+display_for_callable!(LazySeq);
@@ -1,4 +1,7 @@
-use crate::{callables::prelude::*, vm::List};
+use crate::{
+    callables::prelude::*,
+    vm::{LazySeq, List},
+};
 
 #[derive(Debug, Clone)]
 pub struct Cons;
@@ -13,8 +16,8 @@ impl Callable for Cons {
             Ok(())
         } else {
             Err(CompilationError::WrongArity(
-                self.name(),
-                "<value> <collection>",
+                self.name().into(),
+                "<value> <collection>".into(),
             ))
         }
     }
@@ -26,8 +29,8 @@ impl Callable for Cons {
     fn execute(&self, _: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
         if args.len() != 2 {
             return Err(RuntimeError::WrongArityS(
-                self.name(),
-                "a value and a collection",
+                self.name().into(),
+                "a value and a collection".into(),
                 args.len(),
             ));
         }
@@ -36,8 +39,11 @@ impl Callable for Cons {
         let value = args_iter.next().unwrap();
         let maybe_coll = args_iter.next().unwrap();
 
+        if let Value::LazySeq(seq) = maybe_coll {
+            return Ok(Value::LazySeq(LazySeq::cons(value, seq)));
+        }
         let coll_as_list = List::try_from(maybe_coll).map_err(|type_str| {
-            RuntimeError::WrongDataType(self.name(), "a collection", type_str)
+            RuntimeError::WrongDataType(self.name().into(), "a collection", type_str)
         })?;
 
         let list = List::Cons(Box::new(value), Box::new(coll_as_list));
@@ -60,8 +66,8 @@ impl Callable for Conj {
             Ok(())
         } else {
             Err(CompilationError::WrongArity(
-                self.name(),
-                "<collection> <...values>",
+                self.name().into(),
+                "<collection> <...values>".into(),
             ))
         }
     }
@@ -70,11 +76,11 @@ impl Callable for Conj {
         Some(state.get_callable_addr(Box::new(self.clone())))
     }
 
-    fn execute(&self, _: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
         if args.is_empty() {
             return Err(RuntimeError::WrongArityS(
-                self.name(),
-                "a collection and any number of values",
+                self.name().into(),
+                "a collection and any number of values".into(),
                 args.len(),
             ));
         }
@@ -89,6 +95,12 @@ impl Callable for Conj {
                 }
                 Ok(Value::List(list))
             }
+            Value::LazySeq(mut seq) => {
+                for value in args_iter {
+                    seq = LazySeq::cons(value, seq);
+                }
+                Ok(Value::LazySeq(seq))
+            }
             Value::Vector(mut vector) => {
                 for value in args_iter {
                     vector.push(value);
@@ -97,19 +109,19 @@ impl Callable for Conj {
             }
             Value::Set(mut set) => {
                 for value in args_iter {
-                    set.insert(value);
+                    set.insert(value.realize(state)?);
                 }
                 Ok(Value::Set(set))
             }
             Value::Map(mut map) => {
                 for value in args_iter {
                     let (key, val) = value.into_map_entry()?;
-                    map.insert(key, val);
+                    map.insert(key.realize(state)?, val);
                 }
                 Ok(Value::Map(map))
             }
             _ => Err(RuntimeError::WrongDataType(
-                self.name(),
+                self.name().into(),
                 "a collection",
                 maybe_coll.type_str(),
             )),
@@ -132,8 +144,8 @@ impl Callable for Del {
             Ok(())
         } else {
             Err(CompilationError::WrongArity(
-                self.name(),
-                "<collection> <...values>",
+                self.name().into(),
+                "<collection> <...values>".into(),
             ))
         }
     }
@@ -142,11 +154,11 @@ impl Callable for Del {
         Some(state.get_callable_addr(Box::new(self.clone())))
     }
 
-    fn execute(&self, _: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
         if args.len() != 2 {
             return Err(RuntimeError::WrongArityS(
-                self.name(),
-                "a collection and any number of values",
+                self.name().into(),
+                "a collection and any number of values".into(),
                 args.len(),
             ));
         }
@@ -157,18 +169,18 @@ impl Callable for Del {
         match maybe_coll {
             Value::Set(mut set) => {
                 for value in args_iter {
-                    set.remove(&value);
+                    set.remove(&value.realize(state)?);
                 }
                 Ok(Value::Set(set))
             }
             Value::Map(mut map) => {
                 for value in args_iter {
-                    map.remove(&value);
+                    map.remove(&value.realize(state)?);
                 }
                 Ok(Value::Map(map))
             }
             _ => Err(RuntimeError::WrongDataType(
-                self.name(),
+                self.name().into(),
                 "an unordered collection",
                 maybe_coll.type_str(),
             )),
@@ -1,6 +1,66 @@
 use std::collections::VecDeque;
 
-use crate::{callables::prelude::*, vm::List};
+use crate::{
+    callables::{collection::modification::Conj, prelude::*},
+    vm::{LazySeq, List, Thunk},
+};
+
+fn expect_fn(name: &str, value: Value) -> RuntimeResult<Value> {
+    match value {
+        Value::Callable(..) | Value::Lambda(..) => Ok(value),
+        _ => Err(RuntimeError::WrongDataType(
+            name.into(),
+            "a function",
+            value.type_str(),
+        )),
+    }
+}
+
+/// Reduces the elements of a collection in a single pass, without
+/// converting vectors and lazy sequences into intermediate lists
+fn reduce_coll<F>(
+    name: &str,
+    state: &VMState,
+    coll: Value,
+    init: Value,
+    mut f: F,
+) -> RuntimeResult<Value>
+where
+    F: FnMut(Value, Value) -> RuntimeResult<Value>,
+{
+    let mut acc = init;
+    match coll {
+        Value::Nil => {}
+        Value::Vector(vector) => {
+            for value in vector {
+                acc = f(acc, value)?;
+            }
+        }
+        Value::LazySeq(mut seq) => {
+            while let Some((first, rest)) = seq.force(state)? {
+                acc = f(acc, first)?;
+                seq = rest;
+            }
+        }
+        coll => {
+            let list = List::try_from(coll).map_err(|type_str| {
+                RuntimeError::WrongDataType(name.into(), "a collection", type_str)
+            })?;
+            for value in list {
+                acc = f(acc, value)?;
+            }
+        }
+    }
+    Ok(acc)
+}
+
+/// Builds the reducing function of a transducer, applying `xform` to `reducer`.
+/// The reducing function isn't called with only the accumulator
+/// at the end of the process, as `miniclj` lambdas have a single arity
+fn build_reducer(state: &VMState, xform: &Value, reducer: Value) -> RuntimeResult<Value> {
+    let completing = Value::Callable(Box::new(Completing(reducer)));
+    state.execute_callable(xform, vec![completing])
+}
 
 #[derive(Debug, Clone)]
 pub struct Map;
@@ -11,12 +71,12 @@ impl Callable for Map {
     }
 
     fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
-        if num_args >= 2 {
+        if num_args >= 1 {
             Ok(())
         } else {
             Err(CompilationError::WrongArity(
-                self.name(),
-                "<function> <...collections>",
+                self.name().into(),
+                "<function> <...collections>".into(),
             ))
         }
     }
@@ -26,29 +86,38 @@ impl Callable for Map {
     }
 
     fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
-        if args.len() < 2 {
+        if args.is_empty() {
             return Err(RuntimeError::WrongArityS(
-                self.name(),
-                "a function and at least one collection",
+                self.name().into(),
+                "a function and any number of collections".into(),
                 args.len(),
             ));
         }
 
         let mut args_iter = args.into_iter();
-        let maybe_fn = args_iter.next().unwrap();
-        let fn_value = match maybe_fn {
-            Value::Callable(..) | Value::Lambda(..) => Ok(maybe_fn),
-            _ => Err(RuntimeError::WrongDataType(
-                self.name(),
-                "a function",
-                maybe_fn.type_str(),
-            )),
-        }?;
+        let fn_value = expect_fn(self.name(), args_iter.next().unwrap())?;
+        if args_iter.len() == 0 {
+            return Ok(Value::Callable(Box::new(MapTransducer(fn_value))));
+        }
 
-        let mut lists = args_iter
+        let colls = args_iter.collect::<Vec<Value>>();
+        if colls.iter().any(|coll| matches!(coll, Value::LazySeq(_))) {
+            let seqs = colls
+                .into_iter()
+                .map(|coll| {
+                    LazySeq::try_from(coll).map_err(|type_str| {
+                        RuntimeError::WrongDataType(self.name().into(), "a collection", type_str)
+                    })
+                })
+                .collect::<RuntimeResult<Vec<LazySeq>>>()?;
+            return Ok(Value::LazySeq(LazySeq::new(Thunk::Map(fn_value, seqs))));
+        }
+
+        let mut lists = colls
+            .into_iter()
             .map(|arg| {
                 List::try_from(arg).map_err(|type_str| {
-                    RuntimeError::WrongDataType(self.name(), "a collection", type_str)
+                    RuntimeError::WrongDataType(self.name().into(), "a collection", type_str)
                 })
             })
             .collect::<RuntimeResult<Vec<List>>>()?;
@@ -66,13 +135,7 @@ impl Callable for Map {
                 }
             }
             lists = next_lists;
-            let current_result = match &fn_value {
-                Value::Callable(callable) => callable.execute(state, args_for_callable),
-                Value::Lambda(ins_ptr, arity) => {
-                    state.execute_lambda(*ins_ptr, *arity, args_for_callable)
-                }
-                _ => unreachable!(),
-            }?;
+            let current_result = state.execute_callable(&fn_value, args_for_callable)?;
             result_vec.push_front(current_result);
         }
     }
@@ -80,6 +143,87 @@ impl Callable for Map {
 
 display_for_callable!(Map);
 
+/// Transducer returned by `map` when it's called without collections
+#[derive(Debug, Clone)]
+struct MapTransducer(Value);
+
+impl Callable for MapTransducer {
+    fn name(&self) -> &'static str {
+        "map"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args == 1 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<function>".into(),
+            ))
+        }
+    }
+
+    fn execute(&self, _: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if args.len() != 1 {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "a reducing function".into(),
+                args.len(),
+            ));
+        }
+
+        let reducer = expect_fn(self.name(), args.into_iter().next().unwrap())?;
+        Ok(Value::Callable(Box::new(MapReducer(
+            self.0.clone(),
+            reducer,
+        ))))
+    }
+}
+
+display_for_callable!(MapTransducer);
+
+/// Reducing function that applies a function to each element
+/// before passing it to the next reducing function
+#[derive(Debug, Clone)]
+struct MapReducer(Value, Value);
+
+impl Callable for MapReducer {
+    fn name(&self) -> &'static str {
+        "map"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args <= 2 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<accumulator>? <value>?".into(),
+            ))
+        }
+    }
+
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        let MapReducer(fn_value, reducer) = self;
+        match args.len() {
+            0 | 1 => state.execute_callable(reducer, args),
+            2 => {
+                let mut args_iter = args.into_iter();
+                let acc = args_iter.next().unwrap();
+                let value = state.execute_callable(fn_value, vec![args_iter.next().unwrap()])?;
+                state.execute_callable(reducer, vec![acc, value])
+            }
+            n => Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "an accumulator and a value".into(),
+                n,
+            )),
+        }
+    }
+}
+
+display_for_callable!(MapReducer);
+
 #[derive(Debug, Clone)]
 pub struct Filter;
 
@@ -89,12 +233,12 @@ impl Callable for Filter {
     }
 
     fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
-        if num_args == 2 {
+        if num_args == 1 || num_args == 2 {
             Ok(())
         } else {
             Err(CompilationError::WrongArity(
-                self.name(),
-                "<function> <collection>",
+                self.name().into(),
+                "<function> <collection>?".into(),
             ))
         }
     }
@@ -104,41 +248,32 @@ impl Callable for Filter {
     }
 
     fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
-        if args.len() != 2 {
+        if args.len() != 1 && args.len() != 2 {
             return Err(RuntimeError::WrongArityS(
-                self.name(),
-                "a function and one collection",
+                self.name().into(),
+                "a function and an optional collection".into(),
                 args.len(),
             ));
         }
 
         let mut args_iter = args.into_iter();
-        let maybe_fn = args_iter.next().unwrap();
-        let maybe_coll = args_iter.next().unwrap();
-
-        let fn_value = match maybe_fn {
-            Value::Callable(..) | Value::Lambda(..) => Ok(maybe_fn),
-            _ => Err(RuntimeError::WrongDataType(
-                self.name(),
-                "a function",
-                maybe_fn.type_str(),
-            )),
-        }?;
+        let fn_value = expect_fn(self.name(), args_iter.next().unwrap())?;
+        let maybe_coll = match args_iter.next() {
+            Some(coll) => coll,
+            None => return Ok(Value::Callable(Box::new(FilterTransducer(fn_value)))),
+        };
 
+        if let Value::LazySeq(seq) = maybe_coll {
+            return Ok(Value::LazySeq(LazySeq::new(Thunk::Filter(fn_value, seq))));
+        }
         let mut list = List::try_from(maybe_coll).map_err(|type_str| {
-            RuntimeError::WrongDataType(self.name(), "a collection", type_str)
+            RuntimeError::WrongDataType(self.name().into(), "a collection", type_str)
         })?;
 
         let mut result_vec = VecDeque::new();
         while let List::Cons(next, rest) = list {
             let args_for_callable = vec![*next.clone()];
-            let current_result = match &fn_value {
-                Value::Callable(callable) => callable.execute(state, args_for_callable),
-                Value::Lambda(ins_ptr, arity) => {
-                    state.execute_lambda(*ins_ptr, *arity, args_for_callable)
-                }
-                _ => unreachable!(),
-            }?;
+            let current_result = state.execute_callable(&fn_value, args_for_callable)?;
             if current_result.is_truthy() {
                 result_vec.push_front(*next);
             }
@@ -150,6 +285,94 @@ impl Callable for Filter {
 
 display_for_callable!(Filter);
 
+/// Transducer returned by `filter` when it's called without a collection
+#[derive(Debug, Clone)]
+struct FilterTransducer(Value);
+
+impl Callable for FilterTransducer {
+    fn name(&self) -> &'static str {
+        "filter"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args == 1 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<function>".into(),
+            ))
+        }
+    }
+
+    fn execute(&self, _: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if args.len() != 1 {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "a reducing function".into(),
+                args.len(),
+            ));
+        }
+
+        let reducer = expect_fn(self.name(), args.into_iter().next().unwrap())?;
+        Ok(Value::Callable(Box::new(FilterReducer(
+            self.0.clone(),
+            reducer,
+        ))))
+    }
+}
+
+display_for_callable!(FilterTransducer);
+
+/// Reducing function that only passes the elements that
+/// satisfy a predicate to the next reducing function
+#[derive(Debug, Clone)]
+struct FilterReducer(Value, Value);
+
+impl Callable for FilterReducer {
+    fn name(&self) -> &'static str {
+        "filter"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args <= 2 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<accumulator>? <value>?".into(),
+            ))
+        }
+    }
+
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        let FilterReducer(predicate, reducer) = self;
+        match args.len() {
+            0 | 1 => state.execute_callable(reducer, args),
+            2 => {
+                let mut args_iter = args.into_iter();
+                let acc = args_iter.next().unwrap();
+                let value = args_iter.next().unwrap();
+                if state
+                    .execute_callable(predicate, vec![value.clone()])?
+                    .is_truthy()
+                {
+                    state.execute_callable(reducer, vec![acc, value])
+                } else {
+                    Ok(acc)
+                }
+            }
+            n => Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "an accumulator and a value".into(),
+                n,
+            )),
+        }
+    }
+}
+
+display_for_callable!(FilterReducer);
+
 #[derive(Debug, Clone)]
 pub struct Reduce;
 
@@ -163,8 +386,8 @@ impl Callable for Reduce {
             Ok(())
         } else {
             Err(CompilationError::WrongArity(
-                self.name(),
-                "<function> <collection>",
+                self.name().into(),
+                "<function> <collection>".into(),
             ))
         }
     }
@@ -176,8 +399,8 @@ impl Callable for Reduce {
     fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
         if args.len() != 2 {
             return Err(RuntimeError::WrongArityS(
-                self.name(),
-                "a function and one collection",
+                self.name().into(),
+                "a function and one collection".into(),
                 args.len(),
             ));
         }
@@ -186,55 +409,378 @@ impl Callable for Reduce {
         let maybe_fn = args_iter.next().unwrap();
         let maybe_coll = args_iter.next().unwrap();
 
-        let fn_value = match maybe_fn {
-            Value::Callable(..) | Value::Lambda(..) => Ok(maybe_fn),
-            _ => Err(RuntimeError::WrongDataType(
-                self.name(),
-                "a function",
-                maybe_fn.type_str(),
+        let fn_value = expect_fn(self.name(), maybe_fn)?;
+
+        let mut seq = LazySeq::try_from(maybe_coll).map_err(|type_str| {
+            RuntimeError::WrongDataType(self.name().into(), "a collection", type_str)
+        })?;
+
+        let mut reduce_result = match seq.force(state)? {
+            Some((first, rest)) => {
+                seq = rest;
+                first
+            }
+            None => return state.execute_callable(&fn_value, Vec::new()),
+        };
+        while let Some((next, rest)) = seq.force(state)? {
+            reduce_result = state.execute_callable(&fn_value, vec![reduce_result, next])?;
+            seq = rest;
+        }
+
+        Ok(reduce_result)
+    }
+}
+
+display_for_callable!(Reduce);
+
+/// Reducing function that wraps a function that only accepts an
+/// accumulator and a value, returning the accumulator when it's
+/// called with only one argument
+#[derive(Debug, Clone)]
+struct Completing(Value);
+
+impl Callable for Completing {
+    fn name(&self) -> &'static str {
+        "completing"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args <= 2 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<accumulator>? <value>?".into(),
+            ))
+        }
+    }
+
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        match args.len() {
+            1 => Ok(args.into_iter().next().unwrap()),
+            0 | 2 => state.execute_callable(&self.0, args),
+            n => Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "an accumulator and a value".into(),
+                n,
             )),
-        }?;
+        }
+    }
+}
 
-        let coll = List::try_from(maybe_coll).map_err(|type_str| {
-            RuntimeError::WrongDataType(self.name(), "a collection", type_str)
+display_for_callable!(Completing);
+
+#[derive(Debug, Clone)]
+pub struct Comp;
+
+impl Callable for Comp {
+    fn name(&self) -> &'static str {
+        "comp"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args >= 1 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<...functions>".into(),
+            ))
+        }
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, _: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if args.is_empty() {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "at least one function".into(),
+                args.len(),
+            ));
+        }
+
+        let fns = args
+            .into_iter()
+            .map(|arg| expect_fn(self.name(), arg))
+            .collect::<RuntimeResult<Vec<Value>>>()?;
+        Ok(Value::Callable(Box::new(Composition(fns))))
+    }
+}
+
+display_for_callable!(Comp);
+
+/// Function returned by `comp`, applies the functions from right to left
+#[derive(Debug, Clone)]
+struct Composition(Vec<Value>);
+
+impl Callable for Composition {
+    fn name(&self) -> &'static str {
+        "comp"
+    }
+
+    fn check_arity(&self, _: usize) -> Result<(), CompilationError> {
+        Ok(())
+    }
+
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        let mut fns_iter = self.0.iter().rev();
+        let mut result = state.execute_callable(fns_iter.next().unwrap(), args)?;
+        for fn_value in fns_iter {
+            result = state.execute_callable(fn_value, vec![result])?;
+        }
+        Ok(result)
+    }
+}
+
+display_for_callable!(Composition);
+
+#[derive(Debug, Clone)]
+pub struct Transduce;
+
+impl Callable for Transduce {
+    fn name(&self) -> &'static str {
+        "transduce"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args == 3 || num_args == 4 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<transducer> <function> <initial value>? <collection>".into(),
+            ))
+        }
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if args.len() != 3 && args.len() != 4 {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "a transducer, a function, an optional initial value and a collection".into(),
+                args.len(),
+            ));
+        }
+
+        let has_init = args.len() == 4;
+        let mut args_iter = args.into_iter();
+        let xform = expect_fn(self.name(), args_iter.next().unwrap())?;
+        let fn_value = expect_fn(self.name(), args_iter.next().unwrap())?;
+        let init = if has_init {
+            args_iter.next().unwrap()
+        } else {
+            state.execute_callable(&fn_value, Vec::new())?
+        };
+        let coll = args_iter.next().unwrap();
+
+        let reducer = build_reducer(state, &xform, fn_value)?;
+        let result = reduce_coll(self.name(), state, coll, init, |acc, value| {
+            state.execute_callable(&reducer, vec![acc, value])
         })?;
+        state.execute_callable(&reducer, vec![result])
+    }
+}
 
-        match coll {
-            List::EmptyList => match fn_value {
-                Value::Callable(callable) => callable.execute(state, Vec::new()),
-                Value::Lambda(ins_ptr, arity) => state.execute_lambda(ins_ptr, arity, Vec::new()),
-                _ => unreachable!(),
-            },
-            List::Cons(first, rest) => match *rest {
-                List::EmptyList => Ok(*first),
-                List::Cons(second, rest) => {
-                    let args_for_callable = vec![*first, *second];
-                    let mut reduce_result = match &fn_value {
-                        Value::Callable(callable) => callable.execute(state, args_for_callable),
-                        Value::Lambda(ins_ptr, arity) => {
-                            state.execute_lambda(*ins_ptr, *arity, args_for_callable)
-                        }
-                        _ => unreachable!(),
-                    }?;
-
-                    let mut list = *rest;
-                    while let List::Cons(next, rest) = list {
-                        let args_for_callable = vec![reduce_result, *next];
-                        reduce_result = match &fn_value {
-                            Value::Callable(callable) => callable.execute(state, args_for_callable),
-                            Value::Lambda(ins_ptr, arity) => {
-                                state.execute_lambda(*ins_ptr, *arity, args_for_callable)
-                            }
-                            _ => unreachable!(),
-                        }?;
-                        list = *rest;
-                    }
+display_for_callable!(Transduce);
 
-                    Ok(reduce_result)
-                }
-            },
+#[derive(Debug, Clone)]
+pub struct Into;
+
+impl Callable for Into {
+    fn name(&self) -> &'static str {
+        "into"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args == 2 || num_args == 3 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<collection> <transducer>? <collection>".into(),
+            ))
+        }
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if args.len() != 2 && args.len() != 3 {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "a collection, an optional transducer and another collection".into(),
+                args.len(),
+            ));
+        }
+
+        let has_xform = args.len() == 3;
+        let mut args_iter = args.into_iter();
+        let to = args_iter.next().unwrap();
+        let xform = if has_xform {
+            Some(expect_fn(self.name(), args_iter.next().unwrap())?)
+        } else {
+            None
+        };
+        let from = args_iter.next().unwrap();
+
+        let conj = Value::Callable(Box::new(Conj));
+        match xform {
+            Some(xform) => {
+                let reducer = build_reducer(state, &xform, conj)?;
+                let result = reduce_coll(self.name(), state, from, to, |acc, value| {
+                    state.execute_callable(&reducer, vec![acc, value])
+                })?;
+                state.execute_callable(&reducer, vec![result])
+            }
+            None => reduce_coll(self.name(), state, from, to, |acc, value| {
+                state.execute_callable(&conj, vec![acc, value])
+            }),
         }
     }
 }
 
-display_for_callable!(Reduce);
+display_for_callable!(Into);
+
+#[derive(Debug, Clone)]
+pub struct Sequence;
+
+impl Callable for Sequence {
+    fn name(&self) -> &'static str {
+        "sequence"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args == 1 || num_args == 2 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<transducer>? <collection>".into(),
+            ))
+        }
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if args.len() != 1 && args.len() != 2 {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "an optional transducer and a collection".into(),
+                args.len(),
+            ));
+        }
+
+        let has_xform = args.len() == 2;
+        let mut args_iter = args.into_iter();
+        let xform = if has_xform {
+            Some(expect_fn(self.name(), args_iter.next().unwrap())?)
+        } else {
+            None
+        };
+        let seq = LazySeq::try_from(args_iter.next().unwrap()).map_err(|type_str| {
+            RuntimeError::WrongDataType(self.name().into(), "a collection", type_str)
+        })?;
+
+        match xform {
+            Some(xform) => {
+                let reducer = build_reducer(state, &xform, Value::Callable(Box::new(Conj)))?;
+                Ok(Value::LazySeq(LazySeq::new(Thunk::Sequence(reducer, seq))))
+            }
+            None => Ok(Value::LazySeq(seq)),
+        }
+    }
+}
+
+display_for_callable!(Sequence);
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::callables::{collection::generation::Range, conditionals::IsTrue, FactorOp};
+
+    fn v(n: i64) -> Value {
+        Value::from(n)
+    }
+
+    fn c<C: Callable + 'static>(callable: C) -> Value {
+        Value::Callable(Box::new(callable))
+    }
+
+    fn to_string(vm: &VMState, value: RuntimeResult<Value>) -> String {
+        format!("{}", value.unwrap().realize(vm).unwrap())
+    }
+
+    #[test]
+    fn test_transducers() {
+        let vm = VMState::new(HashMap::new(), Vec::new());
+        let filter = Filter.execute(&vm, vec![c(IsTrue)]).unwrap();
+        let map = Map.execute(&vm, vec![c(FactorOp::Sub)]).unwrap();
+        let xf = Comp.execute(&vm, vec![filter, map]).unwrap();
+        let coll = || Value::Vector(vec![v(1), Value::Nil, v(2), Value::from(false), v(3)]);
+
+        let sum = Transduce.execute(&vm, vec![xf.clone(), c(FactorOp::Add), coll()]);
+        assert_eq!(to_string(&vm, sum), "-6");
+        let sum = Transduce.execute(&vm, vec![xf.clone(), c(FactorOp::Add), v(10), coll()]);
+        assert_eq!(to_string(&vm, sum), "4");
+        let vector = Into.execute(&vm, vec![Value::Vector(vec![v(0)]), xf, coll()]);
+        assert_eq!(to_string(&vm, vector), "[0 -1 -2 -3]");
+        let vector = Into.execute(
+            &vm,
+            vec![Value::Vector(vec![v(0)]), Value::Vector(vec![v(1)])],
+        );
+        assert_eq!(to_string(&vm, vector), "[0 1]");
+    }
+
+    #[test]
+    fn test_sequence() {
+        let vm = VMState::new(HashMap::new(), Vec::new());
+        let map = Map.execute(&vm, vec![c(FactorOp::Sub)]).unwrap();
+        // The transducer is only applied to the elements that are realized
+        let infinite = Range.execute(&vm, vec![]).unwrap();
+        let seq = match Sequence.execute(&vm, vec![map, infinite]).unwrap() {
+            Value::LazySeq(seq) => seq,
+            value => panic!("Expected a lazy sequence, got {}", value),
+        };
+        let (first, _) = seq.force(&vm).unwrap().unwrap();
+        assert_eq!(first, v(0));
+        let seq = Sequence.execute(&vm, vec![Value::Vector(vec![v(1), v(2)])]);
+        assert_eq!(to_string(&vm, seq), "(1 2)");
+    }
+
+    #[test]
+    fn test_errors() {
+        let vm = VMState::new(HashMap::new(), Vec::new());
+        assert!(matches!(
+            Into.execute(
+                &vm,
+                vec![Value::Vector(vec![]), v(5), Value::Vector(vec![])]
+            ),
+            Err(RuntimeError::WrongDataType(callable, "a function", _)) if callable == "into"
+        ));
+        let map = Map.execute(&vm, vec![c(IsTrue)]).unwrap();
+        assert!(matches!(
+            Transduce.execute(&vm, vec![map.clone(), c(FactorOp::Add), v(0), v(5)]),
+            Err(RuntimeError::WrongDataType(callable, "a collection", _))
+                if callable == "transduce"
+        ));
+        // Without an initial value, the function is called without arguments
+        assert!(matches!(
+            Transduce.execute(&vm, vec![map, c(FactorOp::Sub), Value::Vector(vec![])]),
+            Err(RuntimeError::WrongArityS(callable, ..)) if callable == "-"
+        ));
+    }
+}
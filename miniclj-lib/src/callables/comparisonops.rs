@@ -1,6 +1,8 @@
+use std::cmp::Ordering;
+
 use num::Rational64;
 
-use crate::callables::prelude::*;
+use crate::{callables::prelude::*, vm::List};
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum ComparisonOp {
@@ -26,7 +28,7 @@ impl Callable for ComparisonOp {
 
     fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
         if num_args == 0 {
-            Err(CompilationError::EmptyArgs(self.name()))
+            Err(CompilationError::EmptyArgs(self.name().into()))
         } else {
             Ok(())
         }
@@ -36,44 +38,255 @@ impl Callable for ComparisonOp {
         Some(state.get_callable_addr(Box::new(*self)))
     }
 
-    fn execute(&self, _: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        let args = state.realize_all(args)?;
         if args.is_empty() {
             return Err(RuntimeError::WrongArityS(
-                self.name(),
-                "at least one number",
+                self.name().into(),
+                "at least one value".into(),
                 0,
             ));
         }
 
-        let args_as_nums = |args: Vec<Value>| {
-            args.into_iter()
-                .map(|v| {
-                    if let Value::Number(n) = v {
-                        Ok(n)
-                    } else {
-                        Err(RuntimeError::WrongDataType(
-                            self.name(),
-                            "a number",
-                            v.type_str(),
-                        ))
-                    }
-                })
-                .collect::<RuntimeResult<Vec<Rational64>>>()
-        };
-
+        // Every value can be compared, using the total order of `Value`
         Ok(Value::from(match self {
             ComparisonOp::Eq => args.iter().all(|v| v == &args[0]),
             ComparisonOp::Ne => args.iter().any(|v| v != &args[0]),
-            ComparisonOp::Gt => args_as_nums(args)?.windows(2).all(|w| w[0] > w[1]),
-            ComparisonOp::Lt => args_as_nums(args)?.windows(2).all(|w| w[0] < w[1]),
-            ComparisonOp::Ge => args_as_nums(args)?.windows(2).all(|w| w[0] >= w[1]),
-            ComparisonOp::Le => args_as_nums(args)?.windows(2).all(|w| w[0] <= w[1]),
+            ComparisonOp::Gt => args.windows(2).all(|w| w[0] > w[1]),
+            ComparisonOp::Lt => args.windows(2).all(|w| w[0] < w[1]),
+            ComparisonOp::Ge => args.windows(2).all(|w| w[0] >= w[1]),
+            ComparisonOp::Le => args.windows(2).all(|w| w[0] <= w[1]),
         }))
     }
 }
 
 display_for_callable!(ComparisonOp);
 
+/// Converts an ordering into the number returned by `compare`
+fn ordering_to_value(ordering: Ordering) -> Value {
+    Value::from(ordering as i64)
+}
+
+#[derive(Debug, Clone)]
+pub struct Compare;
+
+impl Callable for Compare {
+    fn name(&self) -> &'static str {
+        "compare"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args == 2 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<x> <y>".into(),
+            ))
+        }
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if args.len() != 2 {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "<x> <y>".into(),
+                args.len(),
+            ));
+        }
+        let args = state.realize_all(args)?;
+        Ok(ordering_to_value(args[0].cmp(&args[1])))
+    }
+}
+
+display_for_callable!(Compare);
+
+#[derive(Debug, Clone)]
+pub struct Sort;
+
+impl Sort {
+    /// Calls the comparator once with two values. The comparison functions
+    /// like `<` return whether the first value goes first, so they can only
+    /// tell `Less` apart, while any other function must return a number like
+    /// `compare`, which is negative when the first value goes first
+    fn compare_with(
+        state: &VMState,
+        comparator: &Value,
+        a: &Value,
+        b: &Value,
+    ) -> RuntimeResult<Ordering> {
+        let result = state.execute_callable(comparator, vec![a.clone(), b.clone()])?;
+        match result {
+            result if Sort::is_predicate(comparator) => Ok(if result.is_truthy() {
+                Ordering::Less
+            } else {
+                Ordering::Equal
+            }),
+            Value::Number(n) => Ok(n.cmp(&Rational64::from(0))),
+            value => Err(RuntimeError::WrongDataType(
+                "sort".into(),
+                "a comparator that returns a number",
+                value.type_str(),
+            )),
+        }
+    }
+
+    fn is_predicate(comparator: &Value) -> bool {
+        matches!(comparator, Value::Callable(c) if matches!(c.name(), "<" | ">" | "<=" | ">="))
+    }
+
+    /// Stable merge sort that calls the comparator once per comparison
+    /// and stops at its first error
+    fn merge_sort(
+        state: &VMState,
+        comparator: &Value,
+        mut values: Vec<Value>,
+    ) -> RuntimeResult<Vec<Value>> {
+        if values.len() < 2 {
+            return Ok(values);
+        }
+        let right = values.split_off(values.len() / 2);
+        let mut left = Sort::merge_sort(state, comparator, values)?
+            .into_iter()
+            .peekable();
+        let mut right = Sort::merge_sort(state, comparator, right)?
+            .into_iter()
+            .peekable();
+
+        let mut merged = Vec::with_capacity(left.len() + right.len());
+        while let (Some(l), Some(r)) = (left.peek(), right.peek()) {
+            if Sort::compare_with(state, comparator, r, l)? == Ordering::Less {
+                merged.extend(right.next());
+            } else {
+                merged.extend(left.next());
+            }
+        }
+        merged.extend(left);
+        merged.extend(right);
+        Ok(merged)
+    }
+
+    /// Checks that the comparator agrees with the order of the sorted
+    /// values, which isn't the case if it doesn't define a total order.
+    /// The comparison functions always do, as they use the order of `Value`
+    fn check_sorted(state: &VMState, comparator: &Value, values: &[Value]) -> RuntimeResult<()> {
+        if Sort::is_predicate(comparator) {
+            return Ok(());
+        }
+        for pair in values.windows(2) {
+            if Sort::compare_with(state, comparator, &pair[0], &pair[1])? == Ordering::Greater {
+                return Err(RuntimeError::InconsistentComparator("sort"));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Callable for Sort {
+    fn name(&self) -> &'static str {
+        "sort"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args == 1 || num_args == 2 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "[comparator] <coll>".into(),
+            ))
+        }
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if args.len() != 1 && args.len() != 2 {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "[comparator] <coll>".into(),
+                args.len(),
+            ));
+        }
+        let mut args = state.realize_all(args)?;
+        let mut values = match args.pop().unwrap() {
+            Value::Nil => Vec::new(),
+            coll => List::try_from(coll)
+                .map_err(|type_str| {
+                    RuntimeError::WrongDataType(self.name().into(), "a collection", type_str)
+                })?
+                .into_iter()
+                .collect::<Vec<Value>>(),
+        };
+
+        match args.pop() {
+            None => values.sort(),
+            Some(comparator @ (Value::Callable(_) | Value::Lambda(..))) => {
+                values = Sort::merge_sort(state, &comparator, values)?;
+                Sort::check_sorted(state, &comparator, &values)?;
+            }
+            Some(value) => {
+                return Err(RuntimeError::WrongDataType(
+                    self.name().into(),
+                    "a function",
+                    value.type_str(),
+                ))
+            }
+        }
+        Ok(Value::List(values.into_iter().rev().collect()))
+    }
+}
+
+display_for_callable!(Sort);
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Extreme {
+    Min,
+    Max,
+}
+
+impl Callable for Extreme {
+    fn name(&self) -> &'static str {
+        match self {
+            Extreme::Min => "min",
+            Extreme::Max => "max",
+        }
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args == 0 {
+            Err(CompilationError::EmptyArgs(self.name().into()))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(*self)))
+    }
+
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        let args = state.realize_all(args)?;
+        let extreme = match self {
+            Extreme::Min => args.into_iter().min(),
+            Extreme::Max => args.into_iter().max(),
+        };
+        extreme.ok_or(RuntimeError::WrongArityS(
+            self.name().into(),
+            "at least one value".into(),
+            0,
+        ))
+    }
+}
+
+display_for_callable!(Extreme);
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
@@ -241,4 +454,91 @@ mod tests {
             false.into()
         );
     }
+
+    #[test]
+    fn test_compare() {
+        let vm = VMState::new(HashMap::new(), Vec::new());
+        let s = |s: &str| Value::String(String::from(s));
+        assert_eq!(Compare.execute(&vm, vec![v(2), v(3)]).unwrap(), v(-1));
+        assert_eq!(Compare.execute(&vm, vec![s("b"), s("a")]).unwrap(), v(1));
+        assert_eq!(
+            Compare
+                .execute(
+                    &vm,
+                    vec![
+                        Value::Vector(vec![v(1), v(2)]),
+                        Value::Vector(vec![v(1), v(2)])
+                    ]
+                )
+                .unwrap(),
+            v(0)
+        );
+        assert_eq!(
+            ComparisonOp::Lt
+                .execute(
+                    &vm,
+                    vec![Value::Nil, v(5), s("a"), s("b"), Value::Vector(vec![])]
+                )
+                .unwrap(),
+            true.into()
+        );
+
+        let unsorted = Value::Vector(vec![s("b"), v(3), Value::Nil, s("a"), v(1)]);
+        let sorted = [Value::Nil, v(1), v(3), s("a"), s("b")];
+        assert_eq!(
+            Sort.execute(&vm, vec![unsorted.clone()]).unwrap(),
+            Value::List(sorted.iter().cloned().rev().collect())
+        );
+        assert_eq!(
+            Sort.execute(
+                &vm,
+                vec![Value::Callable(Box::new(ComparisonOp::Gt)), unsorted]
+            )
+            .unwrap(),
+            Value::List(sorted.iter().cloned().collect())
+        );
+        assert_eq!(
+            Extreme::Max.execute(&vm, vec![v(2), s("a"), v(7)]).unwrap(),
+            s("a")
+        );
+    }
+
+    #[test]
+    fn test_sort_comparator() {
+        use crate::engine::{Engine, EngineError};
+
+        let mut engine = Engine::new();
+        let list = |values: &[i64]| Value::List(values.iter().rev().map(|n| v(*n)).collect());
+        let source = "(def calls (atom 0))
+            (sort (fn [a b] (do (swap! calls + 1) (compare a b))) [3 1 2])";
+        assert_eq!(engine.eval_str(source).unwrap(), list(&[1, 2, 3]));
+        // Three comparisons to sort and two to check the result
+        assert_eq!(engine.eval_str("@calls").unwrap(), v(5));
+        assert_eq!(
+            engine
+                .eval_str("(sort (fn [a b] (- b a)) [3 1 2])")
+                .unwrap(),
+            list(&[3, 2, 1])
+        );
+        assert_eq!(
+            engine.eval_str("(sort >= [3 1 3 2])").unwrap(),
+            list(&[3, 3, 2, 1])
+        );
+        assert!(matches!(
+            engine.eval_str("(sort (fn [a b] (< a b)) [3 1 2])"),
+            Err(EngineError::RuntimeError(
+                RuntimeError::InconsistentComparator("sort")
+            ))
+        ));
+        assert!(matches!(
+            engine.eval_str("(sort (fn [a b] \"a\") [3 1 2])"),
+            Err(EngineError::RuntimeError(RuntimeError::WrongDataType(..)))
+        ));
+
+        let vm = VMState::new(HashMap::new(), Vec::new());
+        assert!(matches!(
+            Compare.execute(&vm, vec![v(1)]),
+            Err(RuntimeError::WrongArityS(callable, _, 1)) if callable == "compare"
+        ));
+    }
 }
@@ -17,7 +17,10 @@ impl Callable for IsTrue {
         if num_args == 1 {
             Ok(())
         } else {
-            Err(CompilationError::WrongArity(self.name(), "<value>"))
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<value>".into(),
+            ))
         }
     }
 
@@ -28,8 +31,8 @@ impl Callable for IsTrue {
     fn execute(&self, _: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
         if args.len() != 1 {
             return Err(RuntimeError::WrongArityS(
-                self.name(),
-                "one value",
+                self.name().into(),
+                "one value".into(),
                 args.len(),
             ));
         }
@@ -54,8 +57,8 @@ impl Callable for If {
             Ok(())
         } else {
             Err(CompilationError::WrongArity(
-                self.name(),
-                "<condition> <true expression> <false expression>",
+                self.name().into(),
+                "<condition> <true expression> <false expression>".into(),
             ))
         }
     }
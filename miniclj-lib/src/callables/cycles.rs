@@ -24,8 +24,8 @@ impl Callable for Loop {
             Ok(())
         } else {
             Err(CompilationError::WrongArity(
-                self.name(),
-                "<bindings vector> <body>",
+                self.name().into(),
+                "<bindings vector> <body>".into(),
             ))
         }
     }
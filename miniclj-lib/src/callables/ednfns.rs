@@ -0,0 +1,97 @@
+use escape8259::{escape, unescape};
+
+use crate::callables::prelude::*;
+
+#[derive(Debug, Clone)]
+pub struct EdnReadString;
+
+impl Callable for EdnReadString {
+    fn name(&self) -> &'static str {
+        "edn/read-string"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args == 1 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<string>".into(),
+            ))
+        }
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, _: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if args.len() != 1 {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "a string".into(),
+                args.len(),
+            ));
+        }
+        match args.into_iter().next().unwrap() {
+            Value::String(s) => {
+                let text = unescape(&s).unwrap_or(s);
+                Value::from_edn(&text).map_err(|err| RuntimeError::CouldntParse(err, "EDN"))
+            }
+            value => Err(RuntimeError::WrongDataType(
+                self.name().into(),
+                "a string",
+                value.type_str(),
+            )),
+        }
+    }
+}
+
+display_for_callable!(EdnReadString);
+
+#[derive(Debug, Clone)]
+pub struct EdnWriteString;
+
+impl Callable for EdnWriteString {
+    fn name(&self) -> &'static str {
+        "edn/write-string"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args == 1 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<value>".into(),
+            ))
+        }
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if args.len() != 1 {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "a value".into(),
+                args.len(),
+            ));
+        }
+        let value = args.into_iter().next().unwrap().realize(state)?;
+        value
+            .to_edn()
+            .map(|edn| Value::String(escape(edn)))
+            .map_err(|type_str| {
+                RuntimeError::WrongDataType(
+                    self.name().into(),
+                    "a value that can be written as EDN",
+                    type_str,
+                )
+            })
+    }
+}
+
+display_for_callable!(EdnWriteString);
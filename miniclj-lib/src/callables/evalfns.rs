@@ -0,0 +1,154 @@
+use escape8259::unescape;
+
+use crate::{callables::prelude::*, parsers::SExprsParser};
+
+#[derive(Debug, Clone)]
+pub struct ReadString;
+
+impl Callable for ReadString {
+    fn name(&self) -> &'static str {
+        "read-string"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args == 1 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<string>".into(),
+            ))
+        }
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, _: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if args.len() != 1 {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "a string".into(),
+                args.len(),
+            ));
+        }
+        let string = match args.into_iter().next().unwrap() {
+            Value::String(s) => unescape(&s).unwrap_or(s),
+            value => {
+                return Err(RuntimeError::WrongDataType(
+                    self.name().into(),
+                    "a string",
+                    value.type_str(),
+                ))
+            }
+        };
+        // Only the first expression is read, like in Clojure
+        match SExprsParser::parse(&string) {
+            Ok(exprs) => Ok(exprs.into_iter().next().map_or(Value::Nil, Value::from)),
+            Err(_) => Err(RuntimeError::CouldntParse(
+                format!("\"{}\"", string),
+                "miniclj data",
+            )),
+        }
+    }
+}
+
+display_for_callable!(ReadString);
+
+#[derive(Debug, Clone)]
+pub struct Eval;
+
+impl Callable for Eval {
+    fn name(&self) -> &'static str {
+        "eval"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args == 1 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<form>".into(),
+            ))
+        }
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if args.len() != 1 {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "a form".into(),
+                args.len(),
+            ));
+        }
+        let form = state.realize_all(args)?.into_iter().next().unwrap();
+        let expr = form.into_sexpr().map_err(|type_str| {
+            RuntimeError::WrongDataType(self.name().into(), "a form", type_str)
+        })?;
+        state.eval(expr)
+    }
+}
+
+display_for_callable!(Eval);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(source: &str) -> RuntimeResult<Value> {
+        ReadString.execute(
+            &VMState::default(),
+            vec![Value::String(String::from(source))],
+        )
+    }
+
+    #[test]
+    fn test_read_string() {
+        let value = read("(+ 1 [2 \\\"a\\\"]) ignored").unwrap();
+        assert_eq!(format!("{}", value.readable()), "'(+ 1 [2 \"a\"])");
+        let expr = value.clone().into_sexpr().unwrap();
+        assert_eq!(Value::from(expr), value);
+
+        assert!(matches!(read("(1"), Err(RuntimeError::CouldntParse(..))));
+        assert!(matches!(
+            ReadString.execute(&VMState::default(), vec![Value::from(1)]),
+            Err(RuntimeError::WrongDataType(..))
+        ));
+        assert!(matches!(
+            ReadString.execute(&VMState::default(), vec![]),
+            Err(RuntimeError::WrongArityS(..))
+        ));
+    }
+
+    #[test]
+    fn test_eval() {
+        let state = VMState::from_compiler(CompilerState::default());
+        let form = read("(* 2 (+ 1 2))").unwrap();
+        assert_eq!(Eval.execute(&state, vec![form]).unwrap(), Value::from(6));
+        let form = read("(undefined 1)").unwrap();
+        assert!(matches!(
+            Eval.execute(&state, vec![form]),
+            Err(RuntimeError::CompilationError(..))
+        ));
+        // The state is still usable after a form fails to compile
+        let form = read("(- 5 1)").unwrap();
+        assert_eq!(Eval.execute(&state, vec![form]).unwrap(), Value::from(4));
+
+        let form = read("(+ 1 2)").unwrap();
+        assert!(Eval.execute(&VMState::default(), vec![form]).is_err());
+        assert!(matches!(
+            Eval.execute(&state, vec![Value::Lambda(0, 0)]),
+            Err(RuntimeError::WrongDataType(..))
+        ));
+        assert!(matches!(
+            Eval.execute(&state, vec![]),
+            Err(RuntimeError::WrongArityS(..))
+        ));
+    }
+}
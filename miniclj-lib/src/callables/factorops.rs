@@ -22,7 +22,9 @@ impl Callable for FactorOp {
 
     fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
         match (self, num_args) {
-            (FactorOp::Sub | FactorOp::Div, 0) => Err(CompilationError::EmptyArgs(self.name())),
+            (FactorOp::Sub | FactorOp::Div, 0) => {
+                Err(CompilationError::EmptyArgs(self.name().into()))
+            }
             _ => Ok(()),
         }
     }
@@ -40,7 +42,7 @@ impl Callable for FactorOp {
             .map(|value| match value {
                 Value::Number(n) => Ok(n),
                 _ => Err(RuntimeError::WrongDataType(
-                    self.name(),
+                    self.name().into(),
                     "a number",
                     value.type_str(),
                 )),
@@ -52,8 +54,8 @@ impl Callable for FactorOp {
             FactorOp::Add => Ok(Value::Number(nums.fold(zero, |a, b| a + b))),
             FactorOp::Sub => match nums.len() {
                 0 => Err(RuntimeError::WrongArityS(
-                    self.name(),
-                    "at least one number",
+                    self.name().into(),
+                    "at least one number".into(),
                     0,
                 )),
                 1 => Ok(Value::Number(-nums.next().unwrap())),
@@ -66,8 +68,8 @@ impl Callable for FactorOp {
             FactorOp::Mul => Ok(Value::Number(nums.fold(one, |a, b| a * b))),
             FactorOp::Div => match nums.len() {
                 0 => Err(RuntimeError::WrongArityS(
-                    self.name(),
-                    "at least one number",
+                    self.name().into(),
+                    "at least one number".into(),
                     0,
                 )),
                 1 => Ok(Value::Number(nums.next().unwrap().recip())),
@@ -0,0 +1,311 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::PathBuf,
+};
+
+use escape8259::{escape, unescape};
+
+use crate::callables::prelude::*;
+
+/// Reads the path of a file from the first argument of a callable
+fn path_arg(callable: &str, value: Value) -> RuntimeResult<PathBuf> {
+    match value {
+        Value::String(s) => Ok(PathBuf::from(unescape(&s).unwrap_or(s))),
+        value => Err(RuntimeError::WrongDataType(
+            callable.into(),
+            "a string",
+            value.type_str(),
+        )),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Slurp;
+
+impl Callable for Slurp {
+    fn name(&self) -> &'static str {
+        "slurp"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args == 1 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<path>".into(),
+            ))
+        }
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if args.len() != 1 {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "a path".into(),
+                args.len(),
+            ));
+        }
+        let path = path_arg(self.name(), args.into_iter().next().unwrap())?;
+        state
+            .fs_permissions()
+            .check_read(path)
+            .and_then(fs::read_to_string)
+            .map(|content| Value::String(escape(content)))
+            .map_err(|err| RuntimeError::IOError("read file", err))
+    }
+}
+
+display_for_callable!(Slurp);
+
+#[derive(Debug, Clone)]
+pub struct Spit;
+
+impl Callable for Spit {
+    fn name(&self) -> &'static str {
+        "spit"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args == 2 || num_args == 3 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<path> <content> [append]".into(),
+            ))
+        }
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if args.len() != 2 && args.len() != 3 {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "<path> <content> [append]".into(),
+                args.len(),
+            ));
+        }
+        let mut args = state.realize_all(args)?.into_iter();
+        let path = path_arg(self.name(), args.next().unwrap())?;
+        let content = match args.next().unwrap() {
+            Value::String(s) => unescape(&s).unwrap_or(s),
+            value => format!("{}", value),
+        };
+        let append = args.next().is_some_and(|value| value.is_truthy());
+
+        state
+            .fs_permissions()
+            .check_write(path)
+            .and_then(|path| {
+                OpenOptions::new()
+                    .write(true)
+                    .create(true)
+                    .append(append)
+                    .truncate(!append)
+                    .open(path)
+            })
+            .and_then(|mut file| file.write_all(content.as_bytes()))
+            .map(|()| Value::Nil)
+            .map_err(|err| RuntimeError::IOError("write file", err))
+    }
+}
+
+display_for_callable!(Spit);
+
+#[derive(Debug, Clone)]
+pub struct LineSeq;
+
+impl Callable for LineSeq {
+    fn name(&self) -> &'static str {
+        "line-seq"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args == 1 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<path>".into(),
+            ))
+        }
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if args.len() != 1 {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "a path".into(),
+                args.len(),
+            ));
+        }
+        let path = path_arg(self.name(), args.into_iter().next().unwrap())?;
+        let content = state
+            .fs_permissions()
+            .check_read(path)
+            .and_then(fs::read_to_string)
+            .map_err(|err| RuntimeError::IOError("read file", err))?;
+        Ok(Value::List(
+            content
+                .lines()
+                .rev()
+                .map(|line| Value::String(escape(line)))
+                .collect(),
+        ))
+    }
+}
+
+display_for_callable!(LineSeq);
+
+#[derive(Debug, Clone)]
+pub struct FileExists;
+
+impl Callable for FileExists {
+    fn name(&self) -> &'static str {
+        "file-exists?"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args == 1 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<path>".into(),
+            ))
+        }
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if args.len() != 1 {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "a path".into(),
+                args.len(),
+            ));
+        }
+        let path = path_arg(self.name(), args.into_iter().next().unwrap())?;
+        state
+            .fs_permissions()
+            .check_read(path)
+            .map(|path| Value::from(path.exists()))
+            .map_err(|err| RuntimeError::IOError("check file", err))
+    }
+}
+
+display_for_callable!(FileExists);
+
+#[derive(Debug, Clone)]
+pub struct ListDir;
+
+impl Callable for ListDir {
+    fn name(&self) -> &'static str {
+        "list-dir"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args == 1 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<path>".into(),
+            ))
+        }
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if args.len() != 1 {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "a path".into(),
+                args.len(),
+            ));
+        }
+        let path = path_arg(self.name(), args.into_iter().next().unwrap())?;
+        let mut names = state
+            .fs_permissions()
+            .check_read(path)
+            .and_then(fs::read_dir)
+            .and_then(|entries| {
+                entries
+                    .map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+                    .collect::<std::io::Result<Vec<String>>>()
+            })
+            .map_err(|err| RuntimeError::IOError("list directory", err))?;
+        // The order of the entries depends on the file system
+        names.sort_unstable();
+        Ok(Value::Vector(
+            names
+                .into_iter()
+                .map(|name| Value::String(escape(name)))
+                .collect(),
+        ))
+    }
+}
+
+display_for_callable!(ListDir);
+
+#[derive(Debug, Clone)]
+pub struct DeleteFile;
+
+impl Callable for DeleteFile {
+    fn name(&self) -> &'static str {
+        "delete-file"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args == 1 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<path>".into(),
+            ))
+        }
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if args.len() != 1 {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "a path".into(),
+                args.len(),
+            ));
+        }
+        let path = path_arg(self.name(), args.into_iter().next().unwrap())?;
+        state
+            .fs_permissions()
+            .check_write(path)
+            .and_then(fs::remove_file)
+            .map(|()| Value::Nil)
+            .map_err(|err| RuntimeError::IOError("delete file", err))
+    }
+}
+
+display_for_callable!(DeleteFile);
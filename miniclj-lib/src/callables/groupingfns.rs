@@ -13,7 +13,7 @@ impl Callable for Do {
 
     fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
         if num_args == 0 {
-            Err(CompilationError::EmptyArgs(self.name()))
+            Err(CompilationError::EmptyArgs(self.name().into()))
         } else {
             Ok(())
         }
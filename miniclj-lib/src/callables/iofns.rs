@@ -1,27 +1,21 @@
 use std::io::Write;
 
-use escape8259::unescape;
+use escape8259::{escape, unescape};
 
-use crate::callables::prelude::*;
+use crate::{callables::prelude::*, parsers::NumberLiteralParser};
 
 fn inner_print<T: Write>(writer: &mut T, args: Vec<Value>) -> std::io::Result<()> {
     let mut args_iter = args.into_iter();
     if let Some(v) = args_iter.next() {
         if let Value::String(s) = v {
-            writer.write_fmt(format_args!("{}", unescape(&s).unwrap()))?;
+            writer.write_fmt(format_args!("{}", unescape(&s).unwrap_or(s)))?;
         } else {
             writer.write_fmt(format_args!("{}", v))?;
         }
     }
     for v in args_iter {
         if let Value::String(s) = v {
-            match unescape(&s) {
-                Ok(a) => writer.write_fmt(format_args!(" {}", a))?,
-                Err(e) => {
-                    println!("{:?}", e);
-                    writer.write_all(b"")?;
-                }
-            }
+            writer.write_fmt(format_args!(" {}", unescape(&s).unwrap_or(s)))?;
         } else {
             writer.write_fmt(format_args!(" {}", v))?;
         }
@@ -29,29 +23,13 @@ fn inner_print<T: Write>(writer: &mut T, args: Vec<Value>) -> std::io::Result<()
     Ok(())
 }
 
-#[cfg(target_arch = "wasm32")]
-#[derive(serde::Serialize)]
-struct MinicljOutputWindow {
-    pub minicljoutput: String,
-}
-
-#[cfg(target_arch = "wasm32")]
-fn append_string_to_output_div(new_output: &str) {
-    use js_sys::{JsString, Object};
-    use wasm_bindgen::prelude::*;
-
-    let window = web_sys::window().expect("not running in a browser environment");
-
-    let prev_output_obj = window
-        .get("minicljoutput")
-        .unwrap_or_else(|| JsString::from(String::new()).into());
-    let prev_output_jsstr = prev_output_obj.to_string();
-    let prev_output_string = String::from(prev_output_jsstr);
-    let minicljoutput = prev_output_string + new_output;
-    let output = MinicljOutputWindow { minicljoutput };
-
-    let output_obj = Object::from(JsValue::from_serde(&output).unwrap());
-    Object::assign(&window, &output_obj);
+fn inner_pr<T: Write>(writer: &mut T, args: Vec<Value>) -> std::io::Result<()> {
+    let output = args
+        .iter()
+        .map(|v| format!("{}", v.readable()))
+        .collect::<Vec<String>>()
+        .join(" ");
+    writer.write_all(output.as_bytes())
 }
 
 #[derive(Debug, Clone)]
@@ -70,21 +48,13 @@ impl Callable for Print {
         Some(state.get_callable_addr(Box::new(self.clone())))
     }
 
-    #[cfg(not(target_arch = "wasm32"))]
-    fn execute(&self, _: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
-        inner_print(&mut std::io::stdout(), args)
-            .map(|()| Value::Nil)
-            .map_err(|err| RuntimeError::IOError("print to stdout", err))
-    }
-
-    #[cfg(target_arch = "wasm32")]
-    fn execute(&self, _: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        let args = state.realize_all(args)?;
         let mut buf = Vec::new();
         inner_print(&mut buf, args)
-            .map_err(|err| RuntimeError::IOError("print in a web context", err))?;
-        append_string_to_output_div(std::str::from_utf8(&buf).unwrap());
-
-        Ok(Value::Nil)
+            .and_then(|()| state.print(&String::from_utf8_lossy(&buf)))
+            .map(|()| Value::Nil)
+            .map_err(|err| RuntimeError::IOError("print", err))
     }
 }
 
@@ -106,29 +76,75 @@ impl Callable for Println {
         Some(state.get_callable_addr(Box::new(self.clone())))
     }
 
-    #[cfg(not(target_arch = "wasm32"))]
-    fn execute(&self, _: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
-        let result = inner_print(&mut std::io::stdout(), args)
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        let args = state.realize_all(args)?;
+        let mut buf = Vec::new();
+        inner_print(&mut buf, args)
+            .and_then(|()| buf.write_all(b"\n"))
+            .and_then(|()| state.print(&String::from_utf8_lossy(&buf)))
             .map(|()| Value::Nil)
-            .map_err(|err| RuntimeError::IOError("print to stdout", err));
-        println!();
-        result
+            .map_err(|err| RuntimeError::IOError("print", err))
+    }
+}
+
+display_for_callable!(Println);
+
+#[derive(Debug, Clone)]
+pub struct Pr;
+
+impl Callable for Pr {
+    fn name(&self) -> &'static str {
+        "pr"
+    }
+
+    fn check_arity(&self, _: usize) -> Result<(), CompilationError> {
+        Ok(())
     }
 
-    #[cfg(target_arch = "wasm32")]
-    fn execute(&self, _: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        let args = state.realize_all(args)?;
         let mut buf = Vec::new();
-        inner_print(&mut buf, args)
-            .map_err(|err| RuntimeError::IOError("print in a web context", err))?;
-        buf.write(b"\n")
-            .map_err(|err| RuntimeError::IOError("print in a web context", err))?;
-        append_string_to_output_div(std::str::from_utf8(&buf).unwrap());
+        inner_pr(&mut buf, args)
+            .and_then(|()| state.print(&String::from_utf8_lossy(&buf)))
+            .map(|()| Value::Nil)
+            .map_err(|err| RuntimeError::IOError("print", err))
+    }
+}
 
-        Ok(Value::Nil)
+display_for_callable!(Pr);
+
+#[derive(Debug, Clone)]
+pub struct Prn;
+
+impl Callable for Prn {
+    fn name(&self) -> &'static str {
+        "prn"
+    }
+
+    fn check_arity(&self, _: usize) -> Result<(), CompilationError> {
+        Ok(())
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        let args = state.realize_all(args)?;
+        let mut buf = Vec::new();
+        inner_pr(&mut buf, args)
+            .and_then(|()| buf.write_all(b"\n"))
+            .and_then(|()| state.print(&String::from_utf8_lossy(&buf)))
+            .map(|()| Value::Nil)
+            .map_err(|err| RuntimeError::IOError("print", err))
     }
 }
 
-display_for_callable!(Println);
+display_for_callable!(Prn);
 
 #[derive(Debug, Clone)]
 pub struct Read;
@@ -142,7 +158,7 @@ impl Callable for Read {
         if num_args == 0 {
             Ok(())
         } else {
-            Err(CompilationError::WrongArity(self.name(), ""))
+            Err(CompilationError::WrongArity(self.name().into(), "".into()))
         }
     }
 
@@ -150,45 +166,204 @@ impl Callable for Read {
         Some(state.get_callable_addr(Box::new(self.clone())))
     }
 
-    #[cfg(not(target_arch = "wasm32"))]
-    fn execute(&self, _: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
         if !args.is_empty() {
             return Err(RuntimeError::WrongArityS(
-                self.name(),
-                "no arguments",
+                self.name().into(),
+                "no arguments".into(),
                 args.len(),
             ));
         }
 
-        let mut buffer = String::new();
-        std::io::stdin()
-            .read_line(&mut buffer)
-            .map_err(|e| RuntimeError::IOError("read from stdin", e))?;
-        Ok(Value::String(String::from(buffer.trim())))
+        let line = state
+            .read_line()
+            .map_err(|e| RuntimeError::IOError("read", e))?
+            .unwrap_or_default();
+        Ok(Value::String(String::from(line.trim())))
+    }
+}
+
+display_for_callable!(Read);
+
+#[derive(Debug, Clone)]
+pub struct ReadLine;
+
+impl Callable for ReadLine {
+    fn name(&self) -> &'static str {
+        "read-line"
     }
 
-    #[cfg(target_arch = "wasm32")]
-    fn execute(&self, _: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
-        use std::io::{Error, ErrorKind};
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args == 0 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(self.name().into(), "".into()))
+        }
+    }
 
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
         if !args.is_empty() {
             return Err(RuntimeError::WrongArityS(
-                self.name(),
-                "no arguments",
+                self.name().into(),
+                "no arguments".into(),
                 args.len(),
             ));
         }
 
-        let window = web_sys::window().expect("not running in a browser environment");
-        let input = window
-            .prompt_with_message("Input:")
-            .map_err(|_| {
-                RuntimeError::IOError("read in a web context", Error::from(ErrorKind::Unsupported))
-            })?
-            .unwrap_or_else(|| String::new());
+        // Unlike `read`, the end of the input is distinguished from an empty line
+        state
+            .read_line()
+            .map(|line| line.map_or(Value::Nil, |line| Value::String(escape(line))))
+            .map_err(|e| RuntimeError::IOError("read", e))
+    }
+}
+
+display_for_callable!(ReadLine);
+
+#[derive(Debug, Clone)]
+pub struct ReadAll;
+
+impl Callable for ReadAll {
+    fn name(&self) -> &'static str {
+        "read-all"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args == 0 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(self.name().into(), "".into()))
+        }
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if !args.is_empty() {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "no arguments".into(),
+                args.len(),
+            ));
+        }
 
-        Ok(Value::String(String::from(input.trim())))
+        state
+            .read_all()
+            .map(|input| Value::String(escape(input)))
+            .map_err(|e| RuntimeError::IOError("read", e))
     }
 }
 
-display_for_callable!(Read);
+display_for_callable!(ReadAll);
+
+#[derive(Debug, Clone)]
+pub struct ReadNumber;
+
+impl Callable for ReadNumber {
+    fn name(&self) -> &'static str {
+        "read-number"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args == 0 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(self.name().into(), "".into()))
+        }
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if !args.is_empty() {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "no arguments".into(),
+                args.len(),
+            ));
+        }
+
+        let line = match state
+            .read_line()
+            .map_err(|e| RuntimeError::IOError("read", e))?
+        {
+            Some(line) => line,
+            None => return Ok(Value::Nil),
+        };
+        NumberLiteralParser::parse(line.trim())
+            .map(Value::Number)
+            .map_err(|_| RuntimeError::CouldntParse(format!("\"{}\"", line.trim()), "a number"))
+    }
+}
+
+display_for_callable!(ReadNumber);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{engine::Engine, vm::BufferIO};
+
+    fn engine_with_input(input: &str) -> Engine {
+        let engine = Engine::new();
+        engine.vm_state().set_io(Box::new(BufferIO::new(input)));
+        engine
+    }
+
+    #[test]
+    fn test_read_line_eof() {
+        let mut engine = engine_with_input("a\n\nb");
+        let value =
+            engine.eval_str("[(read-line) (read-line) (read-line) (read-line) (read-line)]");
+        let expected = Value::Vector(vec![
+            Value::String(String::from("a")),
+            Value::String(String::new()),
+            Value::String(String::from("b")),
+            Value::Nil,
+            Value::Nil,
+        ]);
+        assert_eq!(value.unwrap(), expected);
+
+        // The empty lines don't stop a loop that reads until the end of the input
+        let mut engine = engine_with_input("1\n\n2\n");
+        let source = "(loop [lines 0] (if (= (read-line) nil) lines (recur (+ lines 1))))";
+        assert_eq!(engine.eval_str(source).unwrap(), Value::from(3));
+
+        // The other functions don't return `nil` at the end of the input, except `read-number`
+        let mut engine = engine_with_input("");
+        let value = engine.eval_str("[(read) (read-all) (read-number)]");
+        let expected = Value::Vector(vec![
+            Value::String(String::new()),
+            Value::String(String::new()),
+            Value::Nil,
+        ]);
+        assert_eq!(value.unwrap(), expected);
+
+        let state = VMState::default();
+        assert!(matches!(
+            ReadLine.execute(&state, vec![Value::Nil]),
+            Err(RuntimeError::WrongArityS(callable, _, 1)) if callable == "read-line"
+        ));
+    }
+
+    #[test]
+    fn test_print_invalid_escape() {
+        let state = VMState::default();
+        let output = BufferIO::default();
+        state.set_io(Box::new(output.clone()));
+        let args = vec![
+            Value::String(String::from("a\\q")),
+            Value::String(String::from("b\\n")),
+            Value::String(String::from("\\x")),
+        ];
+        Println.execute(&state, args).unwrap();
+        assert_eq!(output.take_output(), "a\\q b\n \\x\n");
+    }
+}
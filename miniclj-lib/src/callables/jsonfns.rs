@@ -0,0 +1,501 @@
+use std::{fmt::Write, iter::Peekable, str::CharIndices};
+
+use escape8259::{escape, unescape};
+use num::{CheckedMul, Rational64};
+use smol_str::SmolStr;
+
+use crate::{callables::prelude::*, vm::ValueMap};
+
+/// Reads JSON text into values: objects are read as maps with string
+/// keys, arrays as vectors, `true` and `false` as 1 and 0, and numbers
+/// as exact rationals, since the language doesn't have floats. Keys can
+/// also be read as keywords, represented like in EDN as symbols that
+/// start with a colon
+struct JsonReader<'a> {
+    input: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+    keyword_keys: bool,
+}
+
+impl<'a> JsonReader<'a> {
+    fn read(input: &'a str, keyword_keys: bool) -> Result<Value, String> {
+        let mut reader = JsonReader {
+            input,
+            chars: input.char_indices().peekable(),
+            keyword_keys,
+        };
+        let value = reader.read_value()?;
+        reader.skip_whitespace();
+        match reader.chars.next() {
+            Some((offset, c)) => Err(format!("unexpected {} at byte {}", c, offset)),
+            None => Ok(value),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.chars.peek(), Some((_, ' ' | '\t' | '\n' | '\r'))) {
+            self.chars.next();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.chars.next() {
+            Some((_, c)) if c == expected => Ok(()),
+            Some((offset, c)) => Err(format!(
+                "expected {} but found {} at byte {}",
+                expected, c, offset
+            )),
+            None => Err(format!("expected {} but the input ended", expected)),
+        }
+    }
+
+    fn read_value(&mut self) -> Result<Value, String> {
+        self.skip_whitespace();
+        match self.chars.peek().copied() {
+            Some((_, '{')) => self.read_object(),
+            Some((_, '[')) => self.read_array(),
+            Some((_, '"')) => Ok(Value::String(escape(self.read_string()?))),
+            Some((_, '-' | '0'..='9')) => self.read_number(),
+            Some((offset, _)) => {
+                for (word, value) in [
+                    ("true", Value::from(true)),
+                    ("false", Value::from(false)),
+                    ("null", Value::Nil),
+                ] {
+                    if self.input[offset..].starts_with(word) {
+                        for _ in 0..word.len() {
+                            self.chars.next();
+                        }
+                        return Ok(value);
+                    }
+                }
+                Err(format!("unexpected value at byte {}", offset))
+            }
+            None => Err(String::from("expected a value but the input ended")),
+        }
+    }
+
+    fn read_object(&mut self) -> Result<Value, String> {
+        self.expect('{')?;
+        let mut map = ValueMap::new();
+        self.skip_whitespace();
+        if matches!(self.chars.peek(), Some((_, '}'))) {
+            self.chars.next();
+            return Ok(Value::Map(map));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.read_string()?;
+            let key = if self.keyword_keys {
+                Value::Symbol(SmolStr::from(format!(":{}", key)))
+            } else {
+                Value::String(escape(key))
+            };
+            self.skip_whitespace();
+            self.expect(':')?;
+            let value = self.read_value()?;
+            map.insert(key, value);
+            self.skip_whitespace();
+            match self.chars.next() {
+                Some((_, ',')) => {}
+                Some((_, '}')) => return Ok(Value::Map(map)),
+                Some((offset, c)) => {
+                    return Err(format!("unexpected {} at byte {}", c, offset));
+                }
+                None => return Err(String::from("unclosed object")),
+            }
+        }
+    }
+
+    fn read_array(&mut self) -> Result<Value, String> {
+        self.expect('[')?;
+        let mut vector = Vec::new();
+        self.skip_whitespace();
+        if matches!(self.chars.peek(), Some((_, ']'))) {
+            self.chars.next();
+            return Ok(Value::Vector(vector));
+        }
+        loop {
+            vector.push(self.read_value()?);
+            self.skip_whitespace();
+            match self.chars.next() {
+                Some((_, ',')) => {}
+                Some((_, ']')) => return Ok(Value::Vector(vector)),
+                Some((offset, c)) => {
+                    return Err(format!("unexpected {} at byte {}", c, offset));
+                }
+                None => return Err(String::from("unclosed array")),
+            }
+        }
+    }
+
+    /// Reads a string, returning it without its escape sequences
+    fn read_string(&mut self) -> Result<String, String> {
+        let start = match self.chars.peek() {
+            Some((offset, _)) => *offset,
+            None => return Err(String::from("expected a string but the input ended")),
+        };
+        self.expect('"')?;
+        loop {
+            match self.chars.next() {
+                Some((_, '\\')) => {
+                    self.chars.next();
+                }
+                Some((end, '"')) => {
+                    return unescape(&self.input[start + 1..end]).map_err(|_| {
+                        format!("invalid escape sequence in string at byte {}", start)
+                    });
+                }
+                Some(_) => {}
+                None => return Err(String::from("unclosed string")),
+            }
+        }
+    }
+
+    fn read_number(&mut self) -> Result<Value, String> {
+        let start = self.chars.peek().unwrap().0;
+        let mut end = start;
+        while let Some((offset, c)) = self.chars.peek().copied() {
+            if !matches!(c, '-' | '+' | '.' | 'e' | 'E' | '0'..='9') {
+                break;
+            }
+            end = offset + 1;
+            self.chars.next();
+        }
+        let text = &self.input[start..end];
+        parse_number(text)
+            .map(Value::Number)
+            .map_err(|reason| format!("{} {} at byte {}", reason, text, start))
+    }
+}
+
+/// Parses a JSON number exactly, or approximates it if it doesn't fit
+/// in a rational, returning why it couldn't be parsed otherwise
+fn parse_number(text: &str) -> Result<Rational64, &'static str> {
+    let (mantissa, exponent) = match text.find(['e', 'E']) {
+        Some(idx) => (&text[..idx], &text[idx + 1..]),
+        None => (text, "0"),
+    };
+    let (negative, mantissa) = match mantissa.strip_prefix('-') {
+        Some(mantissa) => (true, mantissa),
+        None => (false, mantissa),
+    };
+    let (integer, decimals) = match mantissa.split_once('.') {
+        Some((_, "")) => return Err("invalid number"),
+        Some(parts) => parts,
+        None => (mantissa, ""),
+    };
+    let exponent_digits = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+    let leading_zero = integer.len() > 1 && integer.starts_with('0');
+    if integer.is_empty()
+        || leading_zero
+        || exponent_digits.is_empty()
+        || !(integer.chars().chain(decimals.chars()))
+            .chain(exponent_digits.chars())
+            .all(|c| c.is_ascii_digit())
+    {
+        return Err("invalid number");
+    }
+
+    let number = exact_number(integer, decimals, exponent)
+        .or_else(|| Rational64::approximate_float(text.parse::<f64>().ok()?.abs()))
+        .ok_or("number out of range")?;
+    Ok(if negative { -number } else { number })
+}
+
+/// Returns the value of a valid number without its sign,
+/// or `None` if it doesn't fit in a rational
+fn exact_number(integer: &str, decimals: &str, exponent: &str) -> Option<Rational64> {
+    let digits = format!("{}{}", integer, decimals).parse::<i64>().ok()?;
+    let scale = exponent.parse::<i32>().ok()? - decimals.len() as i32;
+    let power = Rational64::from(10i64.checked_pow(scale.unsigned_abs())?);
+    if scale >= 0 {
+        Rational64::from(digits).checked_mul(&power)
+    } else {
+        Some(Rational64::from(digits) / power)
+    }
+}
+
+/// Writes a value as JSON text, indenting it if `indent` is `Some`
+fn write_json(
+    out: &mut String,
+    state: &VMState,
+    value: &Value,
+    indent: Option<usize>,
+) -> RuntimeResult<()> {
+    let write_seq = |out: &mut String,
+                     open: char,
+                     close: char,
+                     items: Vec<(Option<&str>, &Value)>|
+     -> RuntimeResult<()> {
+        out.push(open);
+        for (idx, (key, item)) in items.iter().enumerate() {
+            if idx != 0 {
+                out.push(',');
+            }
+            if let Some(indent) = indent {
+                write!(out, "\n{:width$}", "", width = (indent + 1) * 2).unwrap();
+            }
+            if let Some(key) = key {
+                write!(out, "\"{}\":", escape(unescape(key).unwrap_or_default())).unwrap();
+                if indent.is_some() {
+                    out.push(' ');
+                }
+            }
+            write_json(out, state, item, indent.map(|indent| indent + 1))?;
+        }
+        if let (Some(indent), false) = (indent, items.is_empty()) {
+            write!(out, "\n{:width$}", "", width = indent * 2).unwrap();
+        }
+        out.push(close);
+        Ok(())
+    };
+
+    match value {
+        Value::Nil => out.push_str("null"),
+        Value::Number(n) if n.is_integer() => write!(out, "{}", n.numer()).unwrap(),
+        Value::Number(_) => {
+            return Err(RuntimeError::WrongDataType(
+                "json/write".into(),
+                "an integer",
+                "a fraction",
+            ))
+        }
+        Value::String(s) => write!(out, "\"{}\"", escape(unescape(s).unwrap_or_default())).unwrap(),
+        Value::Vector(vector) => write_seq(
+            out,
+            '[',
+            ']',
+            vector.iter().map(|item| (None, item)).collect(),
+        )?,
+        Value::List(list) => write_seq(
+            out,
+            '[',
+            ']',
+            list.iter().map(|item| (None, item)).collect(),
+        )?,
+        Value::Set(set) => write_seq(out, '[', ']', set.iter().map(|item| (None, item)).collect())?,
+        Value::LazySeq(seq) => {
+            let values = seq.realize(state)?;
+            write_seq(
+                out,
+                '[',
+                ']',
+                values.iter().map(|item| (None, item)).collect(),
+            )?
+        }
+        Value::Map(map) => {
+            let entries = map
+                .iter()
+                .map(|(key, val)| match key {
+                    Value::String(key) => Ok((Some(key.as_str()), val)),
+                    Value::Symbol(key) if key.starts_with(':') => Ok((Some(&key[1..]), val)),
+                    key => Err(RuntimeError::WrongDataType(
+                        "json/write".into(),
+                        "a map with string or keyword keys",
+                        key.type_str(),
+                    )),
+                })
+                .collect::<RuntimeResult<Vec<_>>>()?;
+            write_seq(out, '{', '}', entries)?
+        }
+        value => {
+            return Err(RuntimeError::WrongDataType(
+                "json/write".into(),
+                "a value that can be written as JSON",
+                value.type_str(),
+            ))
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+pub struct JsonParse;
+
+impl Callable for JsonParse {
+    fn name(&self) -> &'static str {
+        "json/parse"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args == 1 || num_args == 2 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<string> [keyword-keys]".into(),
+            ))
+        }
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, _: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if args.len() != 1 && args.len() != 2 {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "<string> [keyword-keys]".into(),
+                args.len(),
+            ));
+        }
+        let keyword_keys = args.get(1).is_some_and(Value::is_truthy);
+        match args.into_iter().next().unwrap() {
+            Value::String(s) => {
+                let text = unescape(&s).unwrap_or(s);
+                JsonReader::read(&text, keyword_keys)
+                    .map_err(|err| RuntimeError::CouldntParse(err, "JSON"))
+            }
+            value => Err(RuntimeError::WrongDataType(
+                self.name().into(),
+                "a string",
+                value.type_str(),
+            )),
+        }
+    }
+}
+
+display_for_callable!(JsonParse);
+
+#[derive(Debug, Clone)]
+pub struct JsonWrite;
+
+impl Callable for JsonWrite {
+    fn name(&self) -> &'static str {
+        "json/write"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args == 1 || num_args == 2 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<value> [pretty]".into(),
+            ))
+        }
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if args.len() != 1 && args.len() != 2 {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "<value> [pretty]".into(),
+                args.len(),
+            ));
+        }
+        let pretty = args.get(1).is_some_and(Value::is_truthy);
+        let mut out = String::new();
+        write_json(&mut out, state, &args[0], pretty.then_some(0))?;
+        Ok(Value::String(escape(out)))
+    }
+}
+
+display_for_callable!(JsonWrite);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(value: &Value, pretty: bool) -> String {
+        let mut out = String::new();
+        write_json(&mut out, &VMState::default(), value, pretty.then_some(0)).unwrap();
+        out
+    }
+
+    #[test]
+    fn test_read() {
+        let value = JsonReader::read(
+            r#" {"a": [1, -2.5, 1e2, 25E-2, true, null], "b\n": "\"q\""} "#,
+            false,
+        );
+        let expected = ValueMap::from([
+            (
+                Value::String(String::from("a")),
+                Value::Vector(vec![
+                    Value::from(1),
+                    Value::Number(Rational64::new(-5, 2)),
+                    Value::from(100),
+                    Value::Number(Rational64::new(1, 4)),
+                    Value::from(true),
+                    Value::Nil,
+                ]),
+            ),
+            (
+                Value::String(String::from("b\\n")),
+                Value::String(String::from("\\\"q\\\"")),
+            ),
+        ]);
+        assert_eq!(value, Ok(Value::Map(expected)));
+
+        assert!(JsonReader::read("[1, 2", false).is_err());
+        assert!(JsonReader::read("[1] 2", false).is_err());
+        assert!(JsonReader::read("01.", false).is_err());
+        assert!(JsonReader::read("1e", false).is_err());
+    }
+
+    #[test]
+    fn test_read_inexact_numbers() {
+        let read = |text: &str| JsonReader::read(text, false);
+        assert_eq!(
+            read("3.14159265358979323846"),
+            Ok(Value::Number(Rational64::new(245850922, 78256779)))
+        );
+        assert_eq!(
+            read("-3.14159265358979323846"),
+            Ok(Value::Number(Rational64::new(-245850922, 78256779)))
+        );
+        assert_eq!(read("1e-300"), Ok(Value::from(0)));
+        assert_eq!(read("1E+2"), Ok(Value::from(100)));
+        assert_eq!(
+            read("1e300"),
+            Err(String::from("number out of range 1e300 at byte 0"))
+        );
+        assert_eq!(read("[-]"), Err(String::from("invalid number - at byte 1")));
+    }
+
+    #[test]
+    fn test_write() {
+        let value = JsonReader::read(r#"[{"key": "a \"b\""}, [], 3, null]"#, false).unwrap();
+        assert_eq!(write(&value, false), r#"[{"key":"a \"b\""},[],3,null]"#);
+        assert_eq!(
+            write(&value, true),
+            "[\n  {\n    \"key\": \"a \\\"b\\\"\"\n  },\n  [],\n  3,\n  null\n]"
+        );
+
+        let mut out = String::new();
+        let fraction = Value::Number(Rational64::new(1, 3));
+        assert!(write_json(&mut out, &VMState::default(), &fraction, None).is_err());
+        let map = Value::Map(ValueMap::from([(Value::from(1), Value::Nil)]));
+        assert!(write_json(&mut out, &VMState::default(), &map, None).is_err());
+    }
+
+    #[test]
+    fn test_keyword_keys() {
+        let value = JsonReader::read(r#"{"a": {"b c": 1}}"#, true).unwrap();
+        let keyword = |name: &str| Value::Symbol(SmolStr::from(name));
+        let expected = ValueMap::from([(
+            keyword(":a"),
+            Value::Map(ValueMap::from([(keyword(":b c"), Value::from(1))])),
+        )]);
+        assert_eq!(value, Value::Map(expected));
+        assert_eq!(write(&value, false), r#"{"a":{"b c":1}}"#);
+
+        let state = VMState::default();
+        let args = vec![Value::String(String::from("{\"a\": 1}")), Value::from(true)];
+        assert_eq!(
+            JsonParse.execute(&state, args).unwrap(),
+            Value::Map(ValueMap::from([(keyword(":a"), Value::from(1))]))
+        );
+        assert!(matches!(
+            JsonParse.execute(&state, vec![]),
+            Err(RuntimeError::WrongArityS(..))
+        ));
+    }
+}
@@ -20,8 +20,8 @@ impl Callable for Lambda {
             Ok(())
         } else {
             Err(CompilationError::WrongArity(
-                self.name(),
-                "<args vector> <body>",
+                self.name().into(),
+                "<args vector> <body>".into(),
             ))
         }
     }
@@ -39,7 +39,7 @@ impl Callable for Lambda {
                         Ok(arg_name)
                     } else {
                         Err(CompilationError::WrongArgument(
-                            self.name(),
+                            self.name().into(),
                             "a vector of symbols",
                             "a vector of something else",
                         ))
@@ -48,7 +48,7 @@ impl Callable for Lambda {
                 .collect::<Result<Vec<SmolStr>, CompilationError>>()
         } else {
             Err(CompilationError::WrongArgument(
-                self.name(),
+                self.name().into(),
                 "a vector of symbols",
                 args_vec_arg.type_str(),
             ))
@@ -8,6 +8,8 @@ macro_rules! display_for_callable {
     };
 }
 
+/// Exposes the callables related to atoms
+mod atomfns;
 /// Exposes the base `Callable` trait
 mod callable;
 /// Exposes the callables related to collections
@@ -18,16 +20,31 @@ mod comparisonops;
 mod conditionals;
 /// Exposes the `loop` and `recur` callables
 mod cycles;
+/// Exposes the callables that read and write EDN
+mod ednfns;
+/// Exposes the `read-string` and `eval` callables
+mod evalfns;
 /// Exposes the callables related to mathematical operations
 mod factorops;
+/// Exposes the callables that read and write files
+mod filefns;
 /// Exposes the `do` callable
 mod groupingfns;
 /// Exposes the callables related to input and output
 mod iofns;
+/// Exposes the callables that read and write JSON
+mod jsonfns;
 /// Exposes the `fn` callable
 mod lambda;
+/// Exposes the callable used to wrap the Rust functions
+/// registered by applications that embed the language
+mod nativefn;
 /// Exposes callables related to adding variables to the local and global scope
 mod scopefns;
+/// Exposes the callables that interact with the process and its environment
+mod systemfns;
+/// Exposes the callables used to write tests
+mod testfns;
 /// Exposes callables used to cast values of some types to others
 mod typecastingfns;
 
@@ -37,6 +54,7 @@ pub use callable::Callable;
 pub use collection::creation::{HashMap, List, Set, Vector};
 pub use comparisonops::ComparisonOp;
 pub use factorops::FactorOp;
+pub use nativefn::NativeFn;
 
 macro_rules! add_fn {
     ($table: expr, $callable: path) => {
@@ -50,19 +68,36 @@ pub struct CallablesTable(RustHashMap<String, Box<dyn Callable>>);
 impl Default for CallablesTable {
     fn default() -> CallablesTable {
         let mut table: RustHashMap<String, Box<dyn Callable>> = RustHashMap::new();
+        add_fn!(table, atomfns::Atom);
+        add_fn!(table, atomfns::Deref);
+        add_fn!(table, atomfns::Reset);
+        add_fn!(table, atomfns::Swap);
+        add_fn!(table, atomfns::CompareAndSet);
+        add_fn!(table, atomfns::AddWatch);
+        add_fn!(table, atomfns::RemoveWatch);
+
         add_fn!(table, collection::access::First);
         add_fn!(table, collection::access::Rest);
         add_fn!(table, collection::access::Nth);
         add_fn!(table, collection::access::Get);
         add_fn!(table, collection::access::Count);
         add_fn!(table, collection::access::IsEmpty);
+        add_fn!(table, collection::access::Take);
+        add_fn!(table, collection::access::TakeWhile);
+        add_fn!(table, collection::access::Doall);
 
         add_fn!(table, collection::creation::List);
         add_fn!(table, collection::creation::Vector);
         add_fn!(table, collection::creation::Set);
         add_fn!(table, collection::creation::HashMap);
+        add_fn!(table, collection::creation::SortedSet);
+        add_fn!(table, collection::creation::SortedMap);
 
         add_fn!(table, collection::generation::Range);
+        add_fn!(table, collection::generation::Iterate);
+        add_fn!(table, collection::generation::Repeat);
+        add_fn!(table, collection::generation::Cycle);
+        add_fn!(table, collection::generation::LazySeq);
 
         add_fn!(table, collection::modification::Cons);
         add_fn!(table, collection::modification::Conj);
@@ -71,6 +106,10 @@ impl Default for CallablesTable {
         add_fn!(table, collection::transducers::Map);
         add_fn!(table, collection::transducers::Filter);
         add_fn!(table, collection::transducers::Reduce);
+        add_fn!(table, collection::transducers::Comp);
+        add_fn!(table, collection::transducers::Transduce);
+        add_fn!(table, collection::transducers::Into);
+        add_fn!(table, collection::transducers::Sequence);
 
         add_fn!(table, comparisonops::ComparisonOp::Eq);
         add_fn!(table, comparisonops::ComparisonOp::Ne);
@@ -78,6 +117,10 @@ impl Default for CallablesTable {
         add_fn!(table, comparisonops::ComparisonOp::Lt);
         add_fn!(table, comparisonops::ComparisonOp::Ge);
         add_fn!(table, comparisonops::ComparisonOp::Le);
+        add_fn!(table, comparisonops::Compare);
+        add_fn!(table, comparisonops::Sort);
+        add_fn!(table, comparisonops::Extreme::Min);
+        add_fn!(table, comparisonops::Extreme::Max);
 
         add_fn!(table, conditionals::IsTrue);
         add_fn!(table, conditionals::If);
@@ -87,16 +130,37 @@ impl Default for CallablesTable {
         add_fn!(table, cycles::Loop);
         add_fn!(table, cycles::Recur);
 
+        add_fn!(table, ednfns::EdnReadString);
+        add_fn!(table, ednfns::EdnWriteString);
+
+        add_fn!(table, evalfns::ReadString);
+        add_fn!(table, evalfns::Eval);
+
         add_fn!(table, factorops::FactorOp::Add);
         add_fn!(table, factorops::FactorOp::Sub);
         add_fn!(table, factorops::FactorOp::Mul);
         add_fn!(table, factorops::FactorOp::Div);
 
+        add_fn!(table, filefns::Slurp);
+        add_fn!(table, filefns::Spit);
+        add_fn!(table, filefns::LineSeq);
+        add_fn!(table, filefns::FileExists);
+        add_fn!(table, filefns::ListDir);
+        add_fn!(table, filefns::DeleteFile);
+
         add_fn!(table, groupingfns::Do);
 
         add_fn!(table, iofns::Print);
         add_fn!(table, iofns::Println);
+        add_fn!(table, iofns::Pr);
+        add_fn!(table, iofns::Prn);
         add_fn!(table, iofns::Read);
+        add_fn!(table, iofns::ReadLine);
+        add_fn!(table, iofns::ReadAll);
+        add_fn!(table, iofns::ReadNumber);
+
+        add_fn!(table, jsonfns::JsonParse);
+        add_fn!(table, jsonfns::JsonWrite);
 
         add_fn!(table, lambda::Lambda);
 
@@ -104,8 +168,18 @@ impl Default for CallablesTable {
         add_fn!(table, scopefns::Defn);
         add_fn!(table, scopefns::Let);
 
+        add_fn!(table, systemfns::Exit);
+        add_fn!(table, systemfns::Getenv);
+
+        add_fn!(table, testfns::Deftest);
+        add_fn!(table, testfns::Is);
+        add_fn!(table, testfns::Testing);
+        add_fn!(table, testfns::Are);
+
         add_fn!(table, typecastingfns::NumberCast);
         add_fn!(table, typecastingfns::StringCast);
+        add_fn!(table, typecastingfns::Format);
+        add_fn!(table, typecastingfns::PrStr);
         add_fn!(table, typecastingfns::Ord);
         add_fn!(table, typecastingfns::Chr);
 
@@ -117,6 +191,16 @@ impl CallablesTable {
     pub fn get(&self, name: &str) -> Option<Box<dyn Callable>> {
         self.0.get(name).cloned()
     }
+
+    /// Adds a callable to the table, replacing
+    /// the one with the same name, if any
+    pub fn insert(&mut self, callable: Box<dyn Callable>) {
+        self.0.insert(String::from(callable.name()), callable);
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.0.keys().map(String::as_str).collect()
+    }
 }
 
 impl std::fmt::Debug for CallablesTable {
@@ -0,0 +1,90 @@
+use std::rc::Rc;
+
+use smol_str::SmolStr;
+
+use crate::callables::prelude::*;
+
+type NativeFunction = dyn Fn(&VMState, Vec<Value>) -> RuntimeResult<Value>;
+
+/// A callable implemented in Rust by an application that embeds the language
+#[derive(Clone)]
+pub struct NativeFn {
+    name: SmolStr,
+    params: SmolStr,
+    arity: usize,
+    variadic: bool,
+    function: Rc<NativeFunction>,
+}
+
+impl NativeFn {
+    /// Creates a callable named `name`, that receives the arguments
+    /// named in `params`. If the last parameter starts with `...`
+    /// it receives the rest of the arguments, if any
+    pub fn new<F>(name: &str, params: &[&str], function: F) -> NativeFn
+    where
+        F: Fn(&VMState, Vec<Value>) -> RuntimeResult<Value> + 'static,
+    {
+        let variadic = params.last().is_some_and(|param| param.starts_with("..."));
+        let arity = if variadic {
+            params.len() - 1
+        } else {
+            params.len()
+        };
+        let params = params
+            .iter()
+            .map(|param| format!("<{}>", param))
+            .collect::<Vec<String>>()
+            .join(" ");
+        NativeFn {
+            name: SmolStr::from(name),
+            params: SmolStr::from(params),
+            arity,
+            variadic,
+            function: Rc::new(function),
+        }
+    }
+
+    fn accepts(&self, num_args: usize) -> bool {
+        num_args == self.arity || (self.variadic && num_args > self.arity)
+    }
+}
+
+impl Callable for NativeFn {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if self.accepts(num_args) {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name.clone(),
+                self.params.clone(),
+            ))
+        }
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if !self.accepts(args.len()) {
+            return Err(RuntimeError::WrongArityS(
+                self.name.clone(),
+                self.params.clone(),
+                args.len(),
+            ));
+        }
+        (self.function)(state, args)
+    }
+}
+
+impl std::fmt::Debug for NativeFn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "NativeFn({} {})", self.name, self.params)
+    }
+}
+
+display_for_callable!(NativeFn);
@@ -23,8 +23,8 @@ impl Callable for Def {
             Ok(())
         } else {
             Err(CompilationError::WrongArity(
-                self.name(),
-                "<symbol> <value>",
+                self.name().into(),
+                "<symbol> <value>".into(),
             ))
         }
     }
@@ -38,7 +38,7 @@ impl Callable for Def {
             Ok(symbol)
         } else {
             Err(CompilationError::WrongArgument(
-                self.name(),
+                self.name().into(),
                 "a symbol",
                 symbol_arg.type_str(),
             ))
@@ -79,8 +79,8 @@ impl Callable for Defn {
             Ok(())
         } else {
             Err(CompilationError::WrongArity(
-                self.name(),
-                "<symbol> <args vector> <body>",
+                self.name().into(),
+                "<symbol> <args vector> <body>".into(),
             ))
         }
     }
@@ -95,7 +95,7 @@ impl Callable for Defn {
             Ok(symbol)
         } else {
             Err(CompilationError::WrongArgument(
-                self.name(),
+                self.name().into(),
                 "a symbol",
                 symbol_arg.type_str(),
             ))
@@ -109,7 +109,7 @@ impl Callable for Defn {
                         Ok(arg_name)
                     } else {
                         Err(CompilationError::WrongArgument(
-                            self.name(),
+                            self.name().into(),
                             "a vector of symbols",
                             "a vector of something else",
                         ))
@@ -118,7 +118,7 @@ impl Callable for Defn {
                 .collect::<Result<Vec<SmolStr>, CompilationError>>()
         } else {
             Err(CompilationError::WrongArgument(
-                self.name(),
+                self.name().into(),
                 "a vector of symbols",
                 args_vec_arg.type_str(),
             ))
@@ -156,13 +156,13 @@ impl Callable for Defn {
 display_for_callable!(Defn);
 
 pub fn as_bindings_vector(
-    fn_name: &'static str,
+    fn_name: &str,
     expr: SExpr,
 ) -> Result<Vec<(SmolStr, SExpr)>, CompilationError> {
     let bindings_vector = match expr {
         SExpr::Vector(vector) if vector.len() % 2 == 0 => Ok(vector),
         other => Err(CompilationError::WrongArgument(
-            fn_name,
+            fn_name.into(),
             "a vector of symbol-value pairs",
             other.type_str(),
         )),
@@ -175,7 +175,7 @@ pub fn as_bindings_vector(
         let symbol = match key {
             SExpr::Literal(Literal::Symbol(symbol)) => Ok(symbol),
             _ => Err(CompilationError::WrongArgument(
-                fn_name,
+                fn_name.into(),
                 "a vector of symbol-value pairs",
                 "a vector with something other than symbols in odd positions",
             )),
@@ -201,8 +201,8 @@ impl Callable for Let {
             Ok(())
         } else {
             Err(CompilationError::WrongArity(
-                self.name(),
-                "<bindings vector> <body>",
+                self.name().into(),
+                "<bindings vector> <body>".into(),
             ))
         }
     }
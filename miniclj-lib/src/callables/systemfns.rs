@@ -0,0 +1,147 @@
+use escape8259::{escape, unescape};
+
+use crate::callables::prelude::*;
+
+#[derive(Debug, Clone)]
+pub struct Exit;
+
+impl Callable for Exit {
+    fn name(&self) -> &'static str {
+        "exit"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args <= 1 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "[status]".into(),
+            ))
+        }
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, _: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if args.len() > 1 {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "[status]".into(),
+                args.len(),
+            ));
+        }
+        let status = match args.into_iter().next() {
+            Some(value) => value
+                .as_i64()
+                .ok()
+                .and_then(|status| i32::try_from(status).ok())
+                .ok_or_else(|| {
+                    RuntimeError::WrongDataType(self.name().into(), "an integer", value.type_str())
+                })?,
+            None => 0,
+        };
+        // The error unwinds the execution until it reaches the application
+        Err(RuntimeError::Exit(status))
+    }
+}
+
+display_for_callable!(Exit);
+
+#[derive(Debug, Clone)]
+pub struct Getenv;
+
+impl Callable for Getenv {
+    fn name(&self) -> &'static str {
+        "getenv"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args == 1 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<name>".into(),
+            ))
+        }
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if args.len() != 1 {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "a variable name".into(),
+                args.len(),
+            ));
+        }
+        match args.into_iter().next().unwrap() {
+            Value::String(name) => {
+                let name = unescape(&name).unwrap_or(name);
+                state
+                    .env_permissions()
+                    .check(&name)
+                    .map_err(|err| RuntimeError::IOError("read environment variable", err))?;
+                Ok(std::env::var(name)
+                    .map(|value| Value::String(escape(value)))
+                    .unwrap_or(Value::Nil))
+            }
+            value => Err(RuntimeError::WrongDataType(
+                self.name().into(),
+                "a string",
+                value.type_str(),
+            )),
+        }
+    }
+}
+
+display_for_callable!(Getenv);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::EnvPermissions;
+
+    #[test]
+    fn test_getenv() {
+        std::env::set_var("MINICLJ_GETENV_TEST", "a \"value\"");
+        let name = Value::String(String::from("MINICLJ_GETENV_TEST"));
+        let mut vm = VMState::default();
+        assert!(matches!(
+            Getenv.execute(&vm, vec![name.clone()]),
+            Err(RuntimeError::IOError(..))
+        ));
+
+        let mut permissions = EnvPermissions::default();
+        permissions.allow("MINICLJ_GETENV_TEST");
+        permissions.allow("MINICLJ_UNDEFINED_TEST");
+        vm.set_env_permissions(permissions);
+        assert_eq!(
+            Getenv.execute(&vm, vec![name.clone()]).unwrap(),
+            Value::String(String::from("a \\\"value\\\""))
+        );
+        assert_eq!(
+            Getenv
+                .execute(
+                    &vm,
+                    vec![Value::String(String::from("MINICLJ_UNDEFINED_TEST"))]
+                )
+                .unwrap(),
+            Value::Nil
+        );
+        assert!(matches!(
+            Getenv.execute(&vm, vec![name.clone(), name]),
+            Err(RuntimeError::WrongArityS(_, _, 2))
+        ));
+        assert!(matches!(
+            Getenv.execute(&vm, vec![Value::Nil]),
+            Err(RuntimeError::WrongDataType(..))
+        ));
+    }
+}
@@ -0,0 +1,341 @@
+use escape8259::unescape;
+use smol_str::SmolStr;
+
+use crate::{
+    callables::{prelude::*, scopefns::Defn, ComparisonOp},
+    compiler::{CompilationResult, Literal, SExpr},
+    instruction::Instruction,
+    memaddress::Lifetime,
+};
+
+fn body_as_expr(body: Vec<SExpr>) -> SExpr {
+    match body.len() {
+        0 => SExpr::Literal(Literal::Nil),
+        1 => body.into_iter().next().unwrap(),
+        _ => {
+            let mut exprs = vec![SExpr::Literal(Literal::Symbol(SmolStr::from("do")))];
+            exprs.extend(body);
+            SExpr::Expr(exprs, None)
+        }
+    }
+}
+
+fn string_arg(fn_name: &str, state: &VMState, value: Value) -> RuntimeResult<Option<String>> {
+    match value.realize(state)? {
+        Value::Nil => Ok(None),
+        Value::String(s) => Ok(Some(unescape(&s).unwrap_or(s))),
+        value => Err(RuntimeError::WrongDataType(
+            fn_name.into(),
+            "a string",
+            value.type_str(),
+        )),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Deftest;
+
+impl Callable for Deftest {
+    fn name(&self) -> &'static str {
+        "deftest"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args == 0 {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<symbol> <body>*".into(),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn inner_compile(&self, state: &mut CompilerState, args: Vec<SExpr>) -> CompilationResult {
+        let mut args_iter = args.into_iter();
+        let symbol = match args_iter.next().unwrap() {
+            SExpr::Literal(Literal::Symbol(symbol)) => Ok(symbol),
+            other => Err(CompilationError::WrongArgument(
+                self.name().into(),
+                "a symbol",
+                other.type_str(),
+            )),
+        }?;
+        let body = body_as_expr(args_iter.collect());
+
+        let defn_args = vec![
+            SExpr::Literal(Literal::Symbol(symbol.clone())),
+            SExpr::Vector(Vec::new()),
+            body,
+        ];
+        let test_addr = Defn.inner_compile(state, defn_args)?;
+        state.add_test(symbol, test_addr);
+        Ok(test_addr)
+    }
+
+    fn execute(&self, _: &VMState, _: Vec<Value>) -> RuntimeResult<Value> {
+        Err(RuntimeError::CompilerError(format!(
+            "Compiler shouldn't output \"{}\" calls",
+            self.name()
+        )))
+    }
+}
+
+display_for_callable!(Deftest);
+
+#[derive(Debug, Clone)]
+pub struct Is;
+
+impl Callable for Is {
+    fn name(&self) -> &'static str {
+        "is"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args == 1 || num_args == 2 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<expression> <message>?".into(),
+            ))
+        }
+    }
+
+    fn inner_compile(&self, state: &mut CompilerState, args: Vec<SExpr>) -> CompilationResult {
+        let mut args_iter = args.into_iter();
+        let expr = args_iter.next().unwrap();
+        let message_addr = match args_iter.next() {
+            Some(message) => state.compile(message)?,
+            None => state.compile(SExpr::Literal(Literal::Nil))?,
+        };
+
+        // The source code is stored like a string literal, with its
+        // quotes and backslashes escaped
+        let source = format!("{}", expr)
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"");
+        let source_addr = state.compile(SExpr::Literal(Literal::String(source)))?;
+
+        // Equality assertions keep both sides, to report
+        // the expected and the actual values
+        let mut arg_addrs = match expr {
+            SExpr::Expr(exprs, _)
+                if exprs.len() == 3
+                    && exprs[0] == SExpr::Literal(Literal::Symbol(SmolStr::from("=")))
+                    && state.get_symbol("=").is_none() =>
+            {
+                exprs
+                    .into_iter()
+                    .skip(1)
+                    .map(|expr| state.compile(expr))
+                    .collect::<Result<Vec<MemAddress>, CompilationError>>()?
+            }
+            expr => vec![state.compile(expr)?],
+        };
+        arg_addrs.push(source_addr);
+        arg_addrs.push(message_addr);
+
+        let callable_addr = state.get_callable_addr(Box::new(self.clone()));
+        let res_addr = state.new_address(Lifetime::Temporal);
+        let instruction = Instruction::new_call(callable_addr, arg_addrs, res_addr);
+        state.add_instruction(instruction);
+
+        Ok(res_addr)
+    }
+
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if args.len() != 3 && args.len() != 4 {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "the compiled expression, its source and a message".into(),
+                args.len(),
+            ));
+        }
+
+        let mut args = state.realize_all(args)?;
+        let message = string_arg(self.name(), state, args.pop().unwrap())?;
+        let source = string_arg(self.name(), state, args.pop().unwrap())?.unwrap_or_default();
+
+        let (result, expected, actual) = if args.len() == 2 {
+            let result = ComparisonOp::Eq.execute(state, args.clone())?;
+            let expected = format!("{}", args[0].readable());
+            let actual = format!("{}", args[1].readable());
+            (result, expected, actual)
+        } else {
+            let result = args.pop().unwrap();
+            let actual = format!("{}", result.readable());
+            (result, source.clone(), actual)
+        };
+
+        state
+            .test_report()
+            .add_assertion(result.is_truthy(), source, expected, actual, message);
+        Ok(result)
+    }
+}
+
+display_for_callable!(Is);
+
+#[derive(Debug, Clone)]
+pub struct Testing;
+
+impl Callable for Testing {
+    fn name(&self) -> &'static str {
+        "testing"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args == 0 {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<description> <body>*".into(),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn inner_compile(&self, state: &mut CompilerState, args: Vec<SExpr>) -> CompilationResult {
+        let mut args_iter = args.into_iter();
+        let description_addr = state.compile(args_iter.next().unwrap())?;
+        let callable_addr = state.get_callable_addr(Box::new(self.clone()));
+
+        // Calling `testing` with the description opens the context,
+        // and calling it without arguments closes it
+        let open_addr = state.new_address(Lifetime::Temporal);
+        let open_instr = Instruction::new_call(callable_addr, vec![description_addr], open_addr);
+        state.add_instruction(open_instr);
+
+        let res_addr = state.compile(body_as_expr(args_iter.collect()))?;
+
+        let close_addr = state.new_address(Lifetime::Temporal);
+        let close_instr = Instruction::new_call(callable_addr, Vec::new(), close_addr);
+        state.add_instruction(close_instr);
+
+        Ok(res_addr)
+    }
+
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        if args.len() > 1 {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "an optional description".into(),
+                args.len(),
+            ));
+        }
+
+        match args.into_iter().next() {
+            Some(description) => {
+                let description = string_arg(self.name(), state, description)?;
+                state
+                    .test_report()
+                    .push_context(description.unwrap_or_default());
+            }
+            None => state.test_report().pop_context(),
+        }
+        Ok(Value::Nil)
+    }
+}
+
+display_for_callable!(Testing);
+
+fn substitute(expr: SExpr, bindings: &[(SmolStr, SExpr)]) -> SExpr {
+    let substitute_all = |exprs: Vec<SExpr>| {
+        exprs
+            .into_iter()
+            .map(|expr| substitute(expr, bindings))
+            .collect()
+    };
+    match expr {
+        SExpr::Literal(Literal::Symbol(symbol)) => bindings
+            .iter()
+            .find(|(name, _)| name == &symbol)
+            .map_or(SExpr::Literal(Literal::Symbol(symbol)), |(_, value)| {
+                value.clone()
+            }),
+        SExpr::Expr(exprs, offset) => SExpr::Expr(substitute_all(exprs), offset),
+        SExpr::ShortLambda(exprs) => SExpr::ShortLambda(substitute_all(exprs)),
+        SExpr::List(exprs) => SExpr::List(substitute_all(exprs)),
+        SExpr::Vector(exprs) => SExpr::Vector(substitute_all(exprs)),
+        SExpr::Set(exprs) => SExpr::Set(substitute_all(exprs)),
+        SExpr::Map(exprs) => SExpr::Map(substitute_all(exprs)),
+        literal => literal,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Are;
+
+impl Callable for Are {
+    fn name(&self) -> &'static str {
+        "are"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args >= 2 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<args vector> <expression> <args>*".into(),
+            ))
+        }
+    }
+
+    fn inner_compile(&self, state: &mut CompilerState, args: Vec<SExpr>) -> CompilationResult {
+        let mut args_iter = args.into_iter();
+        let args_vec_arg = args_iter.next().unwrap();
+        let template = args_iter.next().unwrap();
+        let values = args_iter.collect::<Vec<SExpr>>();
+
+        let arg_names = match args_vec_arg {
+            SExpr::Vector(vector) if !vector.is_empty() => vector
+                .into_iter()
+                .map(|expr| match expr {
+                    SExpr::Literal(Literal::Symbol(arg_name)) => Ok(arg_name),
+                    _ => Err(CompilationError::WrongArgument(
+                        self.name().into(),
+                        "a vector of symbols",
+                        "a vector of something else",
+                    )),
+                })
+                .collect::<Result<Vec<SmolStr>, CompilationError>>(),
+            other => Err(CompilationError::WrongArgument(
+                self.name().into(),
+                "a vector of symbols",
+                other.type_str(),
+            )),
+        }?;
+        if values.len() % arg_names.len() != 0 {
+            return Err(CompilationError::WrongArgument(
+                self.name().into(),
+                "a multiple of the number of symbols as arguments",
+                "a different number of arguments",
+            ));
+        }
+
+        // Each group of arguments is replaced in the expression,
+        // which is then asserted like an `is` form
+        let mut res_addr = state.compile(SExpr::Literal(Literal::Nil))?;
+        for group in values.chunks(arg_names.len()) {
+            let bindings = arg_names
+                .iter()
+                .cloned()
+                .zip(group.iter().cloned())
+                .collect::<Vec<(SmolStr, SExpr)>>();
+            res_addr = Is.inner_compile(state, vec![substitute(template.clone(), &bindings)])?;
+        }
+
+        Ok(res_addr)
+    }
+
+    fn execute(&self, _: &VMState, _: Vec<Value>) -> RuntimeResult<Value> {
+        Err(RuntimeError::CompilerError(format!(
+            "Compiler shouldn't output \"{}\" calls",
+            self.name()
+        )))
+    }
+}
+
+display_for_callable!(Are);
@@ -1,4 +1,4 @@
-use num::Signed;
+use num::{Rational64, Signed};
 
 use crate::{callables::prelude::*, parsers::NumberLiteralParser};
 
@@ -14,7 +14,10 @@ impl Callable for NumberCast {
         if num_args == 1 {
             Ok(())
         } else {
-            Err(CompilationError::WrongArity(self.name(), "<string>"))
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<string>".into(),
+            ))
         }
     }
 
@@ -30,7 +33,7 @@ impl Callable for NumberCast {
                 .map_err(|_| RuntimeError::CouldntParse(format!("\"{}\"", string), "a number"))
         } else {
             Err(RuntimeError::WrongDataType(
-                self.name(),
+                self.name().into(),
                 "a string",
                 maybe_string.type_str(),
             ))
@@ -56,7 +59,8 @@ impl Callable for StringCast {
         Some(state.get_callable_addr(Box::new(self.clone())))
     }
 
-    fn execute(&self, _: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        let args = state.realize_all(args)?;
         let formatted_args = args
             .into_iter()
             .map(|value| {
@@ -74,6 +78,184 @@ impl Callable for StringCast {
 
 display_for_callable!(StringCast);
 
+#[derive(Debug, Clone)]
+pub struct Format;
+
+impl Format {
+    const MAX_PRECISION: u32 = 18;
+
+    fn format_decimal(n: &Rational64, precision: u32) -> String {
+        let scale = 10_i128.pow(precision);
+        let numer = i128::from(*n.numer()) * scale;
+        let denom = i128::from(*n.denom());
+
+        // Round half away from zero
+        let mut scaled = numer / denom;
+        if (numer % denom).abs() * 2 >= denom {
+            scaled += numer.signum();
+        }
+
+        let sign = if scaled < 0 { "-" } else { "" };
+        let scaled = scaled.unsigned_abs();
+        let scale = scale.unsigned_abs();
+        if precision == 0 {
+            format!("{}{}", sign, scaled)
+        } else {
+            format!(
+                "{}{}.{:0width$}",
+                sign,
+                scaled / scale,
+                scaled % scale,
+                width = precision as usize
+            )
+        }
+    }
+}
+
+impl Callable for Format {
+    fn name(&self) -> &'static str {
+        "format"
+    }
+
+    fn check_arity(&self, num_args: usize) -> Result<(), CompilationError> {
+        if num_args != 0 {
+            Ok(())
+        } else {
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<format string> <...values>".into(),
+            ))
+        }
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        let args = state.realize_all(args)?;
+        if args.is_empty() {
+            return Err(RuntimeError::WrongArityS(
+                self.name().into(),
+                "a format string and any number of values".into(),
+                args.len(),
+            ));
+        }
+
+        let num_args = args.len();
+        let mut args_iter = args.into_iter();
+        let format_string = match args_iter.next().unwrap() {
+            Value::String(s) => Ok(s),
+            value => Err(RuntimeError::WrongDataType(
+                self.name().into(),
+                "a string",
+                value.type_str(),
+            )),
+        }?;
+        let invalid_format =
+            || RuntimeError::CouldntParse(format!("\"{}\"", format_string), "a format string");
+        let mut next_value = || {
+            args_iter.next().ok_or(RuntimeError::WrongArityS(
+                self.name().into(),
+                "a value for each format specifier".into(),
+                num_args,
+            ))
+        };
+        let next_integer = |value: Value| {
+            value.as_i64().map_err(|type_str| {
+                RuntimeError::WrongDataType(self.name().into(), "an integer", type_str)
+            })
+        };
+
+        let mut result = String::new();
+        let mut chars = format_string.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                result.push(c);
+                continue;
+            }
+
+            let precision = if chars.peek() == Some(&'.') {
+                chars.next();
+                let mut digits = String::new();
+                while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                    digits.push(digit);
+                }
+                match digits.parse::<u32>() {
+                    Ok(precision) if precision <= Format::MAX_PRECISION => Some(precision),
+                    _ => return Err(invalid_format()),
+                }
+            } else {
+                None
+            };
+
+            match (chars.next(), precision) {
+                (Some('%'), None) => result.push('%'),
+                (Some('s'), None) => result.push_str(&format!("{}", next_value()?)),
+                (Some('d'), None) => {
+                    let n = next_integer(next_value()?)?;
+                    result.push_str(&format!("{}", n));
+                }
+                (Some('x'), None) => {
+                    let n = next_integer(next_value()?)?;
+                    let sign = if n < 0 { "-" } else { "" };
+                    result.push_str(&format!("{}{:x}", sign, n.unsigned_abs()));
+                }
+                (Some('f'), precision) => match next_value()? {
+                    Value::Number(n) => {
+                        let decimal = Format::format_decimal(&n, precision.unwrap_or(6));
+                        result.push_str(&decimal);
+                    }
+                    value => {
+                        return Err(RuntimeError::WrongDataType(
+                            self.name().into(),
+                            "a number",
+                            value.type_str(),
+                        ))
+                    }
+                },
+                _ => return Err(invalid_format()),
+            }
+        }
+
+        Ok(Value::String(result))
+    }
+}
+
+display_for_callable!(Format);
+
+#[derive(Debug, Clone)]
+pub struct PrStr;
+
+impl Callable for PrStr {
+    fn name(&self) -> &'static str {
+        "pr-str"
+    }
+
+    fn check_arity(&self, _: usize) -> Result<(), CompilationError> {
+        Ok(())
+    }
+
+    fn get_as_address(&self, state: &mut CompilerState) -> Option<MemAddress> {
+        Some(state.get_callable_addr(Box::new(self.clone())))
+    }
+
+    fn execute(&self, state: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
+        let args = state.realize_all(args)?;
+        let formatted_args = args
+            .iter()
+            .map(|value| format!("{}", value.readable()))
+            .collect::<Vec<String>>()
+            .join(" ");
+        // Strings store their escape sequences, so the quotes and backslashes
+        // of the readable output have to be escaped to be printed back
+        let escaped_args = formatted_args.replace('\\', "\\\\").replace('"', "\\\"");
+        Ok(Value::String(escaped_args))
+    }
+}
+
+display_for_callable!(PrStr);
+
 #[derive(Debug, Clone)]
 pub struct Ord;
 
@@ -86,7 +268,10 @@ impl Callable for Ord {
         if num_args == 1 {
             Ok(())
         } else {
-            Err(CompilationError::WrongArity(self.name(), "<string>"))
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<string>".into(),
+            ))
         }
     }
 
@@ -97,8 +282,8 @@ impl Callable for Ord {
     fn execute(&self, _: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
         if args.len() != 1 {
             return Err(RuntimeError::WrongArityS(
-                self.name(),
-                "a string",
+                self.name().into(),
+                "a string".into(),
                 args.len(),
             ));
         }
@@ -108,14 +293,14 @@ impl Callable for Ord {
             match s.chars().next() {
                 Some(c) => Ok(Value::from(c as i64)),
                 None => Err(RuntimeError::WrongDataType(
-                    self.name(),
+                    self.name().into(),
                     "a string with at least one character",
                     "an empty string",
                 )),
             }
         } else {
             Err(RuntimeError::WrongDataType(
-                self.name(),
+                self.name().into(),
                 "a string",
                 maybe_string.type_str(),
             ))
@@ -137,7 +322,10 @@ impl Callable for Chr {
         if num_args == 1 {
             Ok(())
         } else {
-            Err(CompilationError::WrongArity(self.name(), "<number>"))
+            Err(CompilationError::WrongArity(
+                self.name().into(),
+                "<number>".into(),
+            ))
         }
     }
 
@@ -148,8 +336,8 @@ impl Callable for Chr {
     fn execute(&self, _: &VMState, args: Vec<Value>) -> RuntimeResult<Value> {
         if args.len() != 1 {
             return Err(RuntimeError::WrongArityS(
-                self.name(),
-                "a number",
+                self.name().into(),
+                "a number".into(),
                 args.len(),
             ));
         }
@@ -158,7 +346,7 @@ impl Callable for Chr {
         if let Value::Number(n) = maybe_num {
             if !n.is_integer() || n.is_negative() {
                 Err(RuntimeError::WrongDataType(
-                    self.name(),
+                    self.name().into(),
                     "a positive integer",
                     "a decimal or negative integer",
                 ))
@@ -173,7 +361,7 @@ impl Callable for Chr {
             }
         } else {
             Err(RuntimeError::WrongDataType(
-                self.name(),
+                self.name().into(),
                 "a number",
                 maybe_num.type_str(),
             ))
@@ -182,3 +370,57 @@ impl Callable for Chr {
 }
 
 display_for_callable!(Chr);
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn s(string: &str) -> Value {
+        Value::String(String::from(string))
+    }
+
+    fn f(num: i64, den: i64) -> Value {
+        Value::Number(Rational64::new(num, den))
+    }
+
+    #[test]
+    fn test_format() {
+        let vm = VMState::new(HashMap::new(), Vec::new());
+        assert_eq!(
+            Format
+                .execute(&vm, vec![s("%s-%d-%x%%"), s("a"), f(42, 1), f(255, 1)])
+                .unwrap(),
+            s("a-42-ff%")
+        );
+        assert_eq!(
+            Format
+                .execute(&vm, vec![s("%.2f %.0f %f"), f(1, 3), f(5, 2), f(-2, 3)])
+                .unwrap(),
+            s("0.33 3 -0.666667")
+        );
+        assert!(matches!(
+            Format.execute(&vm, vec![s("%d"), f(1, 2)]),
+            Err(RuntimeError::WrongDataType(..))
+        ));
+        assert!(matches!(
+            Format.execute(&vm, vec![s("%s %s"), s("a")]),
+            Err(RuntimeError::WrongArityS(..))
+        ));
+        assert!(matches!(
+            Format.execute(&vm, vec![s("%q"), s("a")]),
+            Err(RuntimeError::CouldntParse(..))
+        ));
+    }
+
+    #[test]
+    fn test_pr_str() {
+        let vm = VMState::new(HashMap::new(), Vec::new());
+        let vector = Value::Vector(vec![s("a b"), f(1, 3), Value::Nil]);
+        assert_eq!(
+            PrStr.execute(&vm, vec![vector, s("c")]).unwrap(),
+            s("[\\\"a b\\\" 1/3 nil] \\\"c\\\"")
+        );
+    }
+}
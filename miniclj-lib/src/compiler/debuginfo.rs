@@ -0,0 +1,66 @@
+use smol_str::SmolStr;
+
+use crate::{instruction::InstructionPtr, memaddress::MemAddress};
+
+/// Relates the bytecode produced by the compiler to the source code:
+/// the line of each instruction, the first instruction of each
+/// function and the addresses of the global symbols
+#[derive(Debug, Clone, Default)]
+pub struct DebugInfo {
+    lines: Vec<Option<usize>>,
+    functions: Vec<(SmolStr, InstructionPtr)>,
+    globals: Vec<(SmolStr, MemAddress)>,
+}
+
+impl DebugInfo {
+    pub fn new(
+        lines: Vec<Option<usize>>,
+        functions: Vec<(SmolStr, InstructionPtr)>,
+        globals: Vec<(SmolStr, MemAddress)>,
+    ) -> DebugInfo {
+        DebugInfo {
+            lines,
+            functions,
+            globals,
+        }
+    }
+
+    /// Returns the line (starting from 1) of the
+    /// expression that produced an instruction
+    pub fn line(&self, instruction_ptr: InstructionPtr) -> Option<usize> {
+        self.lines.get(instruction_ptr).copied().flatten()
+    }
+
+    /// Returns the first instruction of every group of consecutive
+    /// instructions produced by the expressions in a line
+    pub fn line_instructions(&self, line: usize) -> Vec<InstructionPtr> {
+        self.lines
+            .iter()
+            .enumerate()
+            .filter(|(instruction_ptr, instruction_line)| {
+                **instruction_line == Some(line)
+                    && (*instruction_ptr == 0 || self.lines[instruction_ptr - 1] != Some(line))
+            })
+            .map(|(instruction_ptr, _)| instruction_ptr)
+            .collect()
+    }
+
+    /// Returns the name of the function that starts at `entry_ptr`
+    pub fn function_name(&self, entry_ptr: InstructionPtr) -> Option<&str> {
+        self.functions
+            .iter()
+            .find(|(_, function_ptr)| *function_ptr == entry_ptr)
+            .map(|(name, _)| name.as_str())
+    }
+
+    pub fn global_address(&self, name: &str) -> Option<MemAddress> {
+        self.globals
+            .iter()
+            .find(|(global_name, _)| global_name == name)
+            .map(|(_, address)| *address)
+    }
+
+    pub fn globals(&self) -> &[(SmolStr, MemAddress)] {
+        &self.globals
+    }
+}
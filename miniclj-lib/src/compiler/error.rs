@@ -15,7 +15,7 @@ pub enum CompilationError {
     CallableNotDefined(SmolStr),
     /// Returned when a expression tried to call a callable with
     /// no arguments, and the callalbe expects at least one
-    EmptyArgs(&'static str),
+    EmptyArgs(SmolStr),
     /// Returned by the compiler when a symbol wasn't defined
     /// in the current scope (or any other parent scope)
     SymbolNotDefined(SmolStr),
@@ -24,10 +24,10 @@ pub enum CompilationError {
     /// type of its arguments during compilation, some functions with
     /// a custom compilation process (such as `fn`, `defn` and `let`)
     /// use their arguments during compilation
-    WrongArgument(&'static str, &'static str, &'static str),
+    WrongArgument(SmolStr, &'static str, &'static str),
     /// Returned when the user tried to call a callable with
     /// the wrong number of arguments
-    WrongArity(&'static str, &'static str),
+    WrongArity(SmolStr, SmolStr),
     /// Returned when the user tried to call the `recur` callable
     /// with a different number of arguments than it's corresponding
     /// `loop` call
@@ -1,9 +1,11 @@
+pub mod debuginfo;
 pub mod error;
 pub mod literal;
 pub mod sexpr;
 pub mod state;
 pub mod symboltable;
 
+pub use debuginfo::DebugInfo;
 pub use error::{CompilationError, CompilationResult};
 pub use literal::Literal;
 pub use sexpr::SExpr;
@@ -1,3 +1,5 @@
+use std::fmt::{self, Display, Formatter};
+
 use crate::compiler::Literal;
 
 pub type SExprs = Vec<SExpr>;
@@ -6,7 +8,9 @@ pub type SExprs = Vec<SExpr>;
 /// Returned by the `SExprParser`
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SExpr {
-    Expr(SExprs),
+    /// A call, with the byte offset where it starts in the source
+    /// code if it was parsed instead of generated by the compiler
+    Expr(SExprs, Option<usize>),
     ShortLambda(SExprs),
     List(SExprs),
     Vector(SExprs),
@@ -18,7 +22,7 @@ pub enum SExpr {
 impl SExpr {
     pub fn type_str(&self) -> &'static str {
         match self {
-            SExpr::Expr(_) => "a s-expression",
+            SExpr::Expr(..) => "a s-expression",
             SExpr::ShortLambda(_) => "a lambda function",
             SExpr::List(_) => "a list",
             SExpr::Vector(_) => "a vector",
@@ -28,3 +32,26 @@ impl SExpr {
         }
     }
 }
+
+fn join_exprs(exprs: &[SExpr]) -> String {
+    exprs
+        .iter()
+        .map(|expr| format!("{}", expr))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+impl Display for SExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SExpr::Expr(exprs, _) => write!(f, "({})", join_exprs(exprs)),
+            SExpr::ShortLambda(exprs) => write!(f, "#({})", join_exprs(exprs)),
+            SExpr::List(exprs) => write!(f, "'({})", join_exprs(exprs)),
+            SExpr::Vector(exprs) => write!(f, "[{}]", join_exprs(exprs)),
+            SExpr::Set(exprs) => write!(f, "#{{{}}}", join_exprs(exprs)),
+            SExpr::Map(exprs) => write!(f, "{{{}}}", join_exprs(exprs)),
+            SExpr::Literal(Literal::String(s)) => write!(f, "\"{}\"", s),
+            SExpr::Literal(literal) => write!(f, "{}", literal),
+        }
+    }
+}
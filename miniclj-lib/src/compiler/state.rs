@@ -4,12 +4,32 @@ use smol_str::SmolStr;
 
 use crate::{
     callables::{Callable, CallablesTable, HashMap, List, Set, Vector},
-    compiler::{CompilationError, CompilationResult, Literal, SExpr, SymbolTable},
+    compiler::{CompilationError, CompilationResult, DebugInfo, Literal, SExpr, SymbolTable},
     constant::Constant,
     instruction::{Instruction, InstructionPtr},
     memaddress::{Lifetime, MemAddress},
 };
 
+/// The state of the compiler before compiling an expression,
+/// used to undo its changes if it couldn't be compiled
+#[derive(Debug)]
+pub struct Checkpoint {
+    constants_len: usize,
+    instruction_ptr: InstructionPtr,
+    globals: (RustHashMap<SmolStr, MemAddress>, usize),
+    tests: Vec<(SmolStr, MemAddress)>,
+}
+
+impl Checkpoint {
+    pub fn constants_len(&self) -> usize {
+        self.constants_len
+    }
+
+    pub fn instruction_ptr(&self) -> InstructionPtr {
+        self.instruction_ptr
+    }
+}
+
 /// Structure used to process `SExpr`s into bytecode
 #[derive(Debug, Default)]
 pub struct CompilerState {
@@ -18,38 +38,23 @@ pub struct CompilerState {
     symbol_table: Rc<SymbolTable>,
     loop_jumps_stack: Vec<(InstructionPtr, Vec<MemAddress>)>,
     callables_table: CallablesTable,
+    tests: Vec<(SmolStr, MemAddress)>,
+    offsets_stack: Vec<usize>,
+    instruction_offsets: Vec<Option<usize>>,
 }
 
 impl CompilerState {
     pub fn compile(&mut self, expr: SExpr) -> CompilationResult {
         match expr {
-            SExpr::Expr(exprs) => {
-                let mut exprs_iter = exprs.into_iter();
-                let first_expr = match exprs_iter.next() {
-                    Some(first_expr) => first_expr,
-                    None => return self.compile(SExpr::List(Vec::new())),
-                };
-                if let SExpr::Literal(Literal::Symbol(symbol)) = first_expr.clone() {
-                    // Check that there isn't an override for the function
-                    if self.symbol_table.get(&symbol).is_none() {
-                        return match self.callables_table.get(&symbol) {
-                            Some(callable) => callable.compile(self, exprs_iter.collect()),
-                            None => Err(CompilationError::CallableNotDefined(symbol)),
-                        };
-                    }
+            SExpr::Expr(exprs, offset) => {
+                if let Some(offset) = offset {
+                    self.offsets_stack.push(offset);
                 }
-
-                let callable_addr = self.compile(first_expr)?;
-
-                let arg_addrs = exprs_iter
-                    .map(|expr| self.compile(expr))
-                    .collect::<Result<Vec<MemAddress>, CompilationError>>()?;
-
-                let res_addr = self.new_address(Lifetime::Temporal);
-                let instruction = Instruction::new_call(callable_addr, arg_addrs, res_addr);
-                self.add_instruction(instruction);
-
-                Ok(res_addr)
+                let result = self.compile_call(exprs);
+                if offset.is_some() {
+                    self.offsets_stack.pop();
+                }
+                result
             }
             SExpr::ShortLambda(exprs) => {
                 let jump_lambda_instr = Instruction::new_jump(None);
@@ -58,7 +63,7 @@ impl CompilerState {
                 let lambda_const = Constant::new_lambda(lambda_start_ptr, 1);
                 let lambda_addr = self.insert_constant(lambda_const);
 
-                self.compile_lambda(vec![SmolStr::from("%")], SExpr::Expr(exprs))?;
+                self.compile_lambda(vec![SmolStr::from("%")], SExpr::Expr(exprs, None))?;
                 self.fill_jump(jump_lambda_instr_ptr, self.instruction_ptr());
                 Ok(lambda_addr)
             }
@@ -83,6 +88,35 @@ impl CompilerState {
         }
     }
 
+    fn compile_call(&mut self, exprs: Vec<SExpr>) -> CompilationResult {
+        let mut exprs_iter = exprs.into_iter();
+        let first_expr = match exprs_iter.next() {
+            Some(first_expr) => first_expr,
+            None => return self.compile(SExpr::List(Vec::new())),
+        };
+        if let SExpr::Literal(Literal::Symbol(symbol)) = first_expr.clone() {
+            // Check that there isn't an override for the function
+            if self.symbol_table.get(&symbol).is_none() {
+                return match self.callables_table.get(&symbol) {
+                    Some(callable) => callable.compile(self, exprs_iter.collect()),
+                    None => Err(CompilationError::CallableNotDefined(symbol)),
+                };
+            }
+        }
+
+        let callable_addr = self.compile(first_expr)?;
+
+        let arg_addrs = exprs_iter
+            .map(|expr| self.compile(expr))
+            .collect::<Result<Vec<MemAddress>, CompilationError>>()?;
+
+        let res_addr = self.new_address(Lifetime::Temporal);
+        let instruction = Instruction::new_call(callable_addr, arg_addrs, res_addr);
+        self.add_instruction(instruction);
+
+        Ok(res_addr)
+    }
+
     pub fn compile_lambda(
         &mut self,
         arg_names: Vec<SmolStr>,
@@ -108,6 +142,45 @@ impl CompilerState {
         self.symbol_table.get(symbol)
     }
 
+    pub fn local_symbols(&self) -> Vec<(SmolStr, MemAddress)> {
+        self.symbol_table.local_symbols()
+    }
+
+    pub fn global_symbols(&self) -> Vec<SmolStr> {
+        self.symbol_table.global_symbols()
+    }
+
+    /// Returns the callable of the language with that name, if any
+    pub fn callable(&self, name: &str) -> Option<Box<dyn Callable>> {
+        self.callables_table.get(name)
+    }
+
+    /// Makes a callable available to the code compiled after this call
+    pub fn register_callable(&mut self, callable: Box<dyn Callable>) {
+        self.callables_table.insert(callable);
+    }
+
+    pub fn callable_names(&self) -> Vec<&str> {
+        self.callables_table.names()
+    }
+
+    /// Returns how a language callable should be called, built from
+    /// the message of the error returned when checking its arity
+    pub fn callable_signature(&self, name: &str) -> Option<String> {
+        let callable = self.callables_table.get(name)?;
+        let signature = (0..16)
+            .find_map(|num_args| match callable.check_arity(num_args) {
+                Err(CompilationError::WrongArity(_, args)) => Some(args),
+                _ => None,
+            })
+            .unwrap_or_else(|| SmolStr::from("<...args>"));
+        if signature.is_empty() {
+            Some(format!("({})", name))
+        } else {
+            Some(format!("({} {})", name, signature))
+        }
+    }
+
     pub fn new_address(&self, lifetime: Lifetime) -> MemAddress {
         self.symbol_table.new_address(lifetime)
     }
@@ -143,6 +216,8 @@ impl CompilerState {
 
     pub fn add_instruction(&mut self, instruction: Instruction) -> InstructionPtr {
         self.instructions.push(instruction);
+        self.instruction_offsets
+            .push(self.offsets_stack.last().copied());
         self.instructions.len() - 1
     }
 
@@ -168,6 +243,83 @@ impl CompilerState {
         self.insert_constant(callable.into())
     }
 
+    /// Returns the instruction where the function assigned to
+    /// the global symbol `name` starts, if it was defined
+    pub fn function_entry(&self, name: &str) -> Option<InstructionPtr> {
+        let global_addr = self.symbol_table.get(name)?;
+        self.instructions
+            .iter()
+            .find_map(|instruction| match instruction {
+                Instruction::Assignment { src, dst } if dst == &global_addr => self
+                    .constants
+                    .iter()
+                    .find_map(|(constant, address)| match constant {
+                        Constant::Lambda(instruction_ptr, _) if address == src => {
+                            Some(*instruction_ptr)
+                        }
+                        _ => None,
+                    }),
+                _ => None,
+            })
+    }
+
+    /// Returns the information used by the debugger to relate the
+    /// instructions compiled until now to `source`, their source code
+    pub fn debug_info(&self, source: &str) -> DebugInfo {
+        let line_starts = source
+            .match_indices('\n')
+            .map(|(idx, _)| idx + 1)
+            .collect::<Vec<usize>>();
+        let lines = self
+            .instruction_offsets
+            .iter()
+            .map(|offset| {
+                offset.map(|offset| {
+                    // Lines are counted from 1
+                    match line_starts.binary_search(&offset) {
+                        Ok(idx) => idx + 2,
+                        Err(idx) => idx + 1,
+                    }
+                })
+            })
+            .collect();
+
+        let mut global_symbols = self.global_symbols();
+        global_symbols.sort();
+        let functions = global_symbols
+            .iter()
+            .filter_map(|name| Some((name.clone(), self.function_entry(name)?)))
+            .collect();
+        let globals = global_symbols
+            .into_iter()
+            .filter_map(|name| {
+                let address = self.get_symbol(&name)?;
+                Some((name, address))
+            })
+            .collect();
+
+        DebugInfo::new(lines, functions, globals)
+    }
+
+    /// Registers a test defined with `deftest`, replacing
+    /// the previous one with the same name
+    pub fn add_test(&mut self, name: SmolStr, address: MemAddress) {
+        match self
+            .tests
+            .iter_mut()
+            .find(|(test_name, _)| test_name == &name)
+        {
+            Some(test) => test.1 = address,
+            None => self.tests.push((name, address)),
+        }
+    }
+
+    /// Returns the name and the global address of
+    /// the tests defined, in order of definition
+    pub fn tests(&self) -> &[(SmolStr, MemAddress)] {
+        &self.tests
+    }
+
     pub fn write_to<T: Write>(self, writer: &mut T) -> std::io::Result<()> {
         let mut constants = self.constants.into_iter().collect::<Vec<_>>();
         constants.sort_unstable_by_key(|(_, address)| address.idx());
@@ -181,6 +333,58 @@ impl CompilerState {
         Ok(())
     }
 
+    /// Returns the number of constants inserted until now
+    pub fn constants_len(&self) -> usize {
+        self.constants.len()
+    }
+
+    /// Returns the constants and instructions added after the first
+    /// `constants_len` constants and `instruction_ptr` instructions,
+    /// used to execute code while it's being compiled
+    pub fn parts_since(
+        &self,
+        constants_len: usize,
+        instruction_ptr: InstructionPtr,
+    ) -> (RustHashMap<MemAddress, Constant>, Vec<Instruction>) {
+        let constants = self
+            .constants
+            .iter()
+            .filter(|(_, address)| address.idx() >= constants_len)
+            .map(|(constant, address)| (*address, constant.clone()))
+            .collect();
+        let instructions = self.instructions[instruction_ptr..].to_vec();
+        (constants, instructions)
+    }
+
+    /// Saves the current state of the compiler, to be able
+    /// to return to it with `rollback`
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            constants_len: self.constants_len(),
+            instruction_ptr: self.instruction_ptr(),
+            globals: self.symbol_table.globals_snapshot(),
+            tests: self.tests.clone(),
+        }
+    }
+
+    /// Removes the constants, instructions, global symbols and tests added
+    /// after the checkpoint, and returns to the global scope. Used to
+    /// recover from an expression that couldn't be compiled
+    pub fn rollback(&mut self, checkpoint: Checkpoint) {
+        self.constants
+            .retain(|_, address| address.idx() < checkpoint.constants_len);
+        self.instructions.truncate(checkpoint.instruction_ptr);
+        self.instruction_offsets
+            .truncate(checkpoint.instruction_ptr);
+        self.offsets_stack.clear();
+        while let Some(parent_table) = self.symbol_table.parent_table() {
+            self.symbol_table = parent_table;
+        }
+        self.symbol_table.restore_globals(checkpoint.globals);
+        self.tests = checkpoint.tests;
+        self.loop_jumps_stack.clear();
+    }
+
     pub fn into_parts(self) -> (RustHashMap<Constant, MemAddress>, Vec<Instruction>) {
         (self.constants, self.instructions)
     }
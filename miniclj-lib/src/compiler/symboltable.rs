@@ -6,6 +6,7 @@ use crate::memaddress::{Lifetime, MemAddress};
 
 type Table = RefCell<HashMap<SmolStr, MemAddress>>;
 type Counter = RefCell<usize>;
+type GlobalsSnapshot = (HashMap<SmolStr, MemAddress>, usize);
 
 /// Stores the symbols and memory addresses for
 /// the current scope
@@ -113,6 +114,55 @@ impl SymbolTable {
         symbols.borrow_mut().remove(symbol);
     }
 
+    /// Returns the local variables of the current scope, sorted by address
+    pub fn local_symbols(&self) -> Vec<(SmolStr, MemAddress)> {
+        let mut local_symbols = self
+            .get_symbols_table(Lifetime::LocalVar)
+            .borrow()
+            .iter()
+            .filter(|(_, address)| address.lifetime() == Lifetime::LocalVar)
+            .map(|(symbol, address)| (symbol.clone(), *address))
+            .collect::<Vec<_>>();
+        local_symbols.sort_unstable_by_key(|(_, address)| address.idx());
+        local_symbols
+    }
+
+    /// Returns the symbols defined in the global scope
+    pub fn global_symbols(&self) -> Vec<SmolStr> {
+        match self {
+            SymbolTable::Global { symbols, .. } => symbols.borrow().keys().cloned().collect(),
+            SymbolTable::Local { parent_table, .. } => parent_table.global_symbols(),
+        }
+    }
+
+    /// Returns a copy of the global symbols and the global variables counter
+    pub fn globals_snapshot(&self) -> GlobalsSnapshot {
+        match self {
+            SymbolTable::Global {
+                symbols,
+                var_counter,
+                ..
+            } => (symbols.borrow().clone(), *var_counter.borrow()),
+            SymbolTable::Local { parent_table, .. } => parent_table.globals_snapshot(),
+        }
+    }
+
+    /// Replaces the global symbols and the global variables counter
+    /// with the ones of a snapshot
+    pub fn restore_globals(&self, snapshot: GlobalsSnapshot) {
+        match self {
+            SymbolTable::Global {
+                symbols,
+                var_counter,
+                ..
+            } => {
+                *symbols.borrow_mut() = snapshot.0;
+                *var_counter.borrow_mut() = snapshot.1;
+            }
+            SymbolTable::Local { parent_table, .. } => parent_table.restore_globals(snapshot),
+        }
+    }
+
     pub fn parent_table(&self) -> Option<Rc<SymbolTable>> {
         match self {
             SymbolTable::Local { parent_table, .. } => Some(parent_table.clone()),
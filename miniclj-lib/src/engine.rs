@@ -0,0 +1,352 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    fs::read_to_string,
+    path::Path,
+};
+
+use smol_str::SmolStr;
+
+use crate::{
+    callables::{Callable, NativeFn},
+    compiler::{CompilationError, CompilerState, SExpr},
+    instruction::InstructionPtr,
+    memaddress::{Lifetime, MemAddress},
+    parsers::SExprsParser,
+    vm::{RunStatus, RuntimeError, RuntimeResult, VMState, Value},
+};
+
+/// Represents the errors returned by the `Engine`
+#[derive(Debug)]
+pub enum EngineError {
+    /// Returned when a source file couldn't be read
+    IOError(std::io::Error),
+    /// Returned when the source code couldn't be parsed
+    ParseError(String),
+    CompilationError(CompilationError),
+    RuntimeError(RuntimeError),
+    /// Returned when a function or a global requested by
+    /// the application isn't defined
+    UndefinedSymbol(String),
+}
+
+impl Display for EngineError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineError::IOError(err) => write!(f, "Couldn't read file: {}", err),
+            EngineError::ParseError(err) => write!(f, "{}", err),
+            EngineError::CompilationError(err) => write!(f, "Compilation error: {}", err),
+            EngineError::RuntimeError(err) => write!(f, "Runtime error: {}", err),
+            EngineError::UndefinedSymbol(symbol) => {
+                write!(f, "Symbol {} not defined in the global scope", symbol)
+            }
+        }
+    }
+}
+
+impl From<CompilationError> for EngineError {
+    fn from(err: CompilationError) -> EngineError {
+        EngineError::CompilationError(err)
+    }
+}
+
+impl From<RuntimeError> for EngineError {
+    fn from(err: RuntimeError) -> EngineError {
+        EngineError::RuntimeError(err)
+    }
+}
+
+/// Compiles and executes code incrementally, keeping the definitions
+/// between evaluations. Used by applications that embed the language,
+/// which can also expose their own functions to it
+#[derive(Debug, Default)]
+pub struct Engine {
+    compiler_state: CompilerState,
+    vm_state: VMState,
+}
+
+impl Engine {
+    pub fn new() -> Engine {
+        Engine::default()
+    }
+
+    /// Exposes a Rust function to the code evaluated after this call,
+    /// see `NativeFn::new` for the meaning of `params`
+    pub fn register_fn<F>(&mut self, name: &str, params: &[&str], function: F)
+    where
+        F: Fn(&VMState, Vec<Value>) -> RuntimeResult<Value> + 'static,
+    {
+        self.register_callable(Box::new(NativeFn::new(name, params, function)));
+    }
+
+    /// Exposes a callable to the code evaluated after this call,
+    /// replacing the language callable with the same name, if any
+    pub fn register_callable(&mut self, callable: Box<dyn Callable>) {
+        self.compiler_state.register_callable(callable);
+    }
+
+    /// Evaluates every expression in the source code, returning the value
+    /// of the last one. Only that value is realized, so the rest of them
+    /// can be infinite lazy sequences
+    pub fn eval_str(&mut self, source: &str) -> Result<Value, EngineError> {
+        let tree =
+            SExprsParser::parse(source).map_err(|e| EngineError::ParseError(e.to_string()))?;
+        let mut value = Value::Nil;
+        for expr in tree {
+            value = self.execute_expr(expr)?;
+        }
+        self.with_compiler(|vm_state| value.realize(vm_state))
+            .map_err(EngineError::from)
+    }
+
+    /// Evaluates every expression in a source file,
+    /// returning the value of the last one
+    pub fn eval_file<P: AsRef<Path>>(&mut self, path: P) -> Result<Value, EngineError> {
+        let source = read_to_string(path).map_err(EngineError::IOError)?;
+        self.eval_str(&source)
+    }
+
+    /// Compiles and executes an expression, returning its realized value.
+    /// If the expression doesn't compile the engine is left as it was before
+    pub fn eval_expr(&mut self, expr: SExpr) -> Result<Value, EngineError> {
+        let value = self.execute_expr(expr)?;
+        self.with_compiler(|vm_state| value.realize(vm_state))
+            .map_err(EngineError::from)
+    }
+
+    /// Compiles every expression in the source code and loads them into
+    /// the virtual machine without executing them, returning their first
+    /// instruction. Lets the application configure the virtual machine
+    /// with the compiled code, before running it with `execute_from`
+    pub fn load_str(&mut self, source: &str) -> Result<InstructionPtr, EngineError> {
+        let tree =
+            SExprsParser::parse(source).map_err(|e| EngineError::ParseError(e.to_string()))?;
+        let (instruction_ptr, _) = self.load(tree)?;
+        Ok(instruction_ptr)
+    }
+
+    /// Executes the code loaded into the virtual machine,
+    /// starting from the instruction `instruction_ptr`
+    pub fn execute_from(&mut self, instruction_ptr: InstructionPtr) -> Result<(), EngineError> {
+        self.with_compiler(|vm_state| vm_state.execute_from(instruction_ptr))
+            .map_err(EngineError::from)
+    }
+
+    /// Executes the code loaded into the virtual machine in slices of
+    /// `instructions` instructions, see `VMState::run_for`
+    pub fn run_for(&mut self, instructions: u64) -> Result<RunStatus, EngineError> {
+        self.with_compiler(|vm_state| vm_state.run_for(instructions))
+            .map_err(EngineError::from)
+    }
+
+    fn execute_expr(&mut self, expr: SExpr) -> Result<Value, EngineError> {
+        let (instruction_ptr, result_addr) = self.load(vec![expr])?;
+        self.with_compiler(|vm_state| {
+            vm_state.execute_from(instruction_ptr)?;
+            result_addr.map_or(Ok(Value::Nil), |addr| vm_state.get_global(&addr))
+        })
+        .map_err(EngineError::from)
+    }
+
+    /// Compiles the expressions and appends them to the code of the virtual
+    /// machine, returning their first instruction and the address of the
+    /// value of the last one. If an expression doesn't compile the engine
+    /// is left as it was before
+    fn load(
+        &mut self,
+        exprs: Vec<SExpr>,
+    ) -> Result<(InstructionPtr, Option<MemAddress>), EngineError> {
+        let checkpoint = self.compiler_state.checkpoint();
+        let constants_len = checkpoint.constants_len();
+        let instruction_ptr = checkpoint.instruction_ptr();
+        let mut result_addr = None;
+        for expr in exprs {
+            match self.compiler_state.compile(expr) {
+                Ok(address) => result_addr = Some(address),
+                Err(err) => {
+                    self.compiler_state.rollback(checkpoint);
+                    return Err(err.into());
+                }
+            }
+        }
+
+        let (constants, instructions) = self
+            .compiler_state
+            .parts_since(constants_len, instruction_ptr);
+        self.vm_state.append(constants, instructions);
+        Ok((instruction_ptr, result_addr))
+    }
+
+    /// Calls a function defined in the global scope, or a callable of the
+    /// language, with the arguments passed, returning its value
+    pub fn call_function(&mut self, name: &str, args: Vec<Value>) -> Result<Value, EngineError> {
+        let function = self
+            .get_global(name)
+            .or_else(|| self.compiler_state.callable(name).map(Value::Callable))
+            .ok_or_else(|| EngineError::UndefinedSymbol(name.to_string()))?;
+        self.with_compiler(|vm_state| {
+            vm_state
+                .execute_callable(&function, args)?
+                .realize(vm_state)
+        })
+        .map_err(EngineError::from)
+    }
+
+    /// Lends the compiler to the virtual machine while it executes
+    /// code, so that the code can compile expressions with `eval`
+    fn with_compiler<T, F>(&mut self, execute: F) -> T
+    where
+        F: FnOnce(&VMState) -> T,
+    {
+        let compiler_state = std::mem::take(&mut self.compiler_state);
+        self.vm_state.set_compiler(Some(compiler_state));
+        let result = execute(&self.vm_state);
+        self.compiler_state = self.vm_state.set_compiler(None).unwrap_or_default();
+        result
+    }
+
+    /// Defines a symbol in the global scope with a value, like `def`,
+    /// so that the code evaluated after this call can use it
+    pub fn set_global(&mut self, name: &str, value: Value) -> Result<(), EngineError> {
+        let address = match self.compiler_state.get_symbol(name) {
+            Some(address) if address.lifetime() == Lifetime::GlobalVar => address,
+            _ => {
+                let address = self.compiler_state.new_address(Lifetime::GlobalVar);
+                self.compiler_state
+                    .insert_symbol(SmolStr::from(name), address);
+                address
+            }
+        };
+        Ok(self.vm_state.store_global(address, value)?)
+    }
+
+    /// Returns the value of a symbol defined in the global
+    /// scope, if it was already assigned
+    pub fn get_global(&self, name: &str) -> Option<Value> {
+        let address = self.compiler_state.get_symbol(name)?;
+        self.vm_state.get_global(&address).ok()
+    }
+
+    pub fn compiler_state(&self) -> &CompilerState {
+        &self.compiler_state
+    }
+
+    pub fn vm_state(&self) -> &VMState {
+        &self.vm_state
+    }
+
+    /// Gives access to the virtual machine to configure it,
+    /// for example to set its limits or its output
+    pub fn vm_state_mut(&mut self) -> &mut VMState {
+        &mut self.vm_state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num::Rational64;
+
+    use crate::vm::{BufferIO, IntoValue};
+
+    fn v(n: i64) -> Value {
+        Value::from(n)
+    }
+
+    #[test]
+    fn test_eval_and_call() {
+        let mut engine = Engine::new();
+        let value = engine.eval_str("(def x 20) (defn add [a b] (+ a b)) (add x 2)");
+        assert_eq!(value.unwrap(), v(22));
+        assert_eq!(engine.get_global("x"), Some(v(20)));
+        let value = engine.eval_str("(eval (read-string \"(do (def z 5) (add x z))\"))");
+        assert_eq!(value.unwrap(), v(25));
+        assert_eq!(engine.eval_str("(+ z 1)").unwrap(), v(6));
+        assert_eq!(engine.get_global("y"), None);
+        assert_eq!(engine.call_function("add", vec![v(1), v(2)]).unwrap(), v(3));
+        assert_eq!(engine.call_function("*", vec![v(3), v(4)]).unwrap(), v(12));
+        assert!(engine.call_function("sub", vec![]).is_err());
+
+        engine.set_global("*y*", v(5)).unwrap();
+        assert_eq!(engine.eval_str("(add x *y*)").unwrap(), v(25));
+    }
+
+    #[test]
+    fn test_register_fn() {
+        let mut engine = Engine::new();
+        engine.register_fn("twice", &["f", "x"], |state, mut args| {
+            let x = args.pop().unwrap();
+            let f = args.pop().unwrap();
+            let once = state.execute_callable(&f, vec![x])?;
+            state.execute_callable(&f, vec![once])
+        });
+        engine.register_fn("fail", &["...args"], |_, args| {
+            Err(RuntimeError::Custom(format!("failed with {}", args.len())))
+        });
+
+        let value = engine.eval_str("(twice (fn [n] (* n 3)) 2)");
+        assert_eq!(value.unwrap(), v(18));
+        assert!(matches!(
+            engine.eval_str("(twice 1)"),
+            Err(EngineError::CompilationError(CompilationError::WrongArity(callable, _)))
+                if callable == "twice"
+        ));
+        let err = engine.eval_str("(fail 1 2)").unwrap_err();
+        assert_eq!(err.to_string(), "Runtime error: failed with 2");
+        let err = engine.call_function("twice", vec![v(1)]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Runtime error: twice called with wrong number of arguments, expected <f> <x>, got 1"
+        );
+    }
+
+    #[test]
+    fn test_load_and_execute() {
+        let mut engine = Engine::new();
+        let entry_ptr = engine.load_str("(def x 2) (def y (* x 3))").unwrap();
+        assert_eq!(engine.get_global("y"), None);
+        engine.execute_from(entry_ptr).unwrap();
+        assert_eq!(engine.get_global("y"), Some(v(6)));
+
+        // Nothing is loaded when an expression doesn't compile
+        assert!(engine.load_str("(def z 1) (undefined z)").is_err());
+        let entry_ptr = engine.load_str("(def x (+ y 1))").unwrap();
+        engine.execute_from(entry_ptr).unwrap();
+        assert_eq!(engine.get_global("x"), Some(v(7)));
+        assert_eq!(engine.get_global("z"), None);
+    }
+
+    #[test]
+    fn test_run_for() {
+        let mut engine = Engine::new();
+        engine
+            .load_str("(def x (eval '(+ 1 2))) (def y (* x 3))")
+            .unwrap();
+        assert!(matches!(engine.run_for(1), Ok(RunStatus::Yielded)));
+        assert_eq!(engine.get_global("y"), None);
+        assert!(matches!(engine.run_for(100), Ok(RunStatus::Finished)));
+        assert_eq!(engine.get_global("y"), Some(v(9)));
+    }
+
+    #[test]
+    fn test_buffer_io() {
+        let mut engine = Engine::new();
+        let io = BufferIO::new("first line\n  second line  ");
+        engine.vm_state().set_io(Box::new(io.clone()));
+
+        engine.eval_str("(println (read) 1) (prn \"a\")").unwrap();
+        assert_eq!(io.take_output(), "first line 1\n\"a\"\n");
+        engine.eval_str("(print (read) (read))").unwrap();
+        assert_eq!(io.take_output(), "second line ");
+
+        let io = BufferIO::new(" 3/4 \n\nrest\nof input");
+        engine.vm_state().set_io(Box::new(io.clone()));
+        let value = engine.eval_str("[(read-number) (read-line) (read-all) (read-line)]");
+        let expected = Value::Vector(vec![
+            Value::Number(Rational64::new(3, 4)),
+            "".into_value(),
+            "rest\nof input\n".into_value(),
+            Value::Nil,
+        ]);
+        assert_eq!(value.unwrap(), expected);
+    }
+}
@@ -0,0 +1,98 @@
+use std::fmt::{self, Display, Formatter};
+
+/// Prints the syntax tree read by the `Reader` with canonical indentation
+mod printer;
+/// Reads source code into a syntax tree that keeps comments and line breaks
+mod reader;
+
+use printer::Printer;
+use reader::Reader;
+
+/// Represents the errors found while reading the code to format
+#[derive(Debug, PartialEq, Eq)]
+pub enum FormatError {
+    /// Returned when a collection wasn't closed before the end of the code
+    UnclosedCollection(usize),
+    /// Returned when a string wasn't closed before the end of the code
+    UnclosedString(usize),
+    /// Returned when a closing delimiter doesn't match the last
+    /// opened collection, or when there isn't an opened collection
+    UnexpectedDelimiter(char, usize),
+}
+
+impl Display for FormatError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::UnclosedCollection(offset) => {
+                write!(f, "Collection opened at {} was never closed", offset)
+            }
+            FormatError::UnclosedString(offset) => {
+                write!(f, "String started at {} was never closed", offset)
+            }
+            FormatError::UnexpectedDelimiter(delimiter, offset) => {
+                write!(f, "Unexpected delimiter '{}' at {}", delimiter, offset)
+            }
+        }
+    }
+}
+
+/// Formats source code with a canonical layout, keeping its comments
+/// and the line breaks between expressions, but replacing the
+/// indentation and the spaces between the elements of a line
+pub struct CodeFormatter;
+
+impl CodeFormatter {
+    pub fn format(input: &str) -> Result<String, FormatError> {
+        let nodes = Reader::new(input).read()?;
+        Ok(Printer::default().print(&nodes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_indentation() {
+        let input = "(defn  add [a b]\n(+ a\nb))\n\n\n(println   (add 1 2))";
+        let expected = "(defn add [a b]\n  (+ a\n     b))\n\n(println (add 1 2))\n";
+        assert_eq!(CodeFormatter::format(input), Ok(String::from(expected)));
+    }
+
+    #[test]
+    fn test_bindings() {
+        let input = "(let [a 1\nb\n(+ a 1)]\n(loop [i 0]\n(println i)))";
+        let expected = "(let [a 1\n      b\n        (+ a 1)]\n  (loop [i 0]\n    (println i)))\n";
+        assert_eq!(CodeFormatter::format(input), Ok(String::from(expected)));
+    }
+
+    #[test]
+    fn test_comments() {
+        let input = "#!/usr/bin/env miniclj\n;; header\n(def a ; the value\n1)   \n(println @a \"; not a comment\")";
+        let expected = "#!/usr/bin/env miniclj\n;; header\n(def a ; the value\n  1)\n(println @a \"; not a comment\")\n";
+        assert_eq!(CodeFormatter::format(input), Ok(String::from(expected)));
+    }
+
+    #[test]
+    fn test_idempotence() {
+        let input = include_str!("../../../examples/sort_list.clj");
+        let formatted = CodeFormatter::format(input).unwrap();
+        assert_eq!(CodeFormatter::format(&formatted), Ok(formatted));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            CodeFormatter::format("(println [1 2)"),
+            Err(FormatError::UnexpectedDelimiter(')', 13))
+        );
+        assert_eq!(
+            CodeFormatter::format("(println \"abc"),
+            Err(FormatError::UnclosedString(9))
+        );
+        assert_eq!(
+            CodeFormatter::format("(println"),
+            Err(FormatError::UnclosedCollection(0))
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use crate::formatter::reader::{Child, Node};
+
+/// Forms whose arguments after the first line are indented by two
+/// spaces from the opening parenthesis, instead of being aligned
+/// with the first argument
+const BODY_FORMS: [&str; 10] = [
+    "def", "defn", "deftest", "do", "fn", "if", "lazy-seq", "let", "loop", "testing",
+];
+
+/// Forms whose first argument is a vector of bindings
+const BINDING_FORMS: [&str; 2] = ["let", "loop"];
+
+/// Number of line breaks kept between two expressions,
+/// which leaves at most one blank line between them
+const MAX_NEWLINES: usize = 2;
+
+#[derive(Default)]
+pub struct Printer {
+    output: String,
+    column: usize,
+}
+
+impl Printer {
+    pub fn print(mut self, nodes: &[Child]) -> String {
+        for (idx, child) in nodes.iter().enumerate() {
+            if idx != 0 {
+                let is_trailing_comment =
+                    child.newlines_before == 0 && matches!(child.node, Node::Comment(_));
+                if is_trailing_comment && !matches!(nodes[idx - 1].node, Node::Comment(_)) {
+                    self.write(" ");
+                } else {
+                    self.newline(child.newlines_before.max(1), 0);
+                }
+            }
+            self.print_node(&child.node, false);
+        }
+        self.trim_line_end();
+        if !self.output.is_empty() {
+            self.output.push('\n');
+        }
+        self.output
+    }
+
+    fn write(&mut self, text: &str) {
+        self.output.push_str(text);
+        match text.rfind('\n') {
+            Some(idx) => self.column = text[idx + 1..].chars().count(),
+            None => self.column += text.chars().count(),
+        }
+    }
+
+    fn trim_line_end(&mut self) {
+        let trimmed_len = self.output.trim_end_matches(' ').len();
+        self.output.truncate(trimmed_len);
+    }
+
+    fn newline(&mut self, newlines: usize, indent: usize) {
+        self.trim_line_end();
+        for _ in 0..newlines.min(MAX_NEWLINES) {
+            self.output.push('\n');
+        }
+        self.output.push_str(&" ".repeat(indent));
+        self.column = indent;
+    }
+
+    fn print_node(&mut self, node: &Node, is_bindings: bool) {
+        match node {
+            Node::Atom(text) | Node::Comment(text) => self.write(text),
+            Node::Deref(node) => {
+                self.write("@");
+                self.print_node(node, false);
+            }
+            Node::Coll {
+                open,
+                close,
+                children,
+            } => self.print_coll(open, *close, children, is_bindings),
+        }
+    }
+
+    fn print_coll(&mut self, open: &str, close: char, children: &[Child], is_bindings: bool) {
+        let start_column = self.column;
+        self.write(open);
+        let first_column = self.column;
+
+        let is_call = matches!(open, "(" | "#(");
+        let head = match children.first() {
+            Some(Child {
+                node: Node::Atom(symbol),
+                ..
+            }) if is_call => Some(symbol.as_str()),
+            _ => None,
+        };
+        let is_body_form = head.is_some_and(|head| BODY_FORMS.contains(&head));
+        let is_binding_form = head.is_some_and(|head| BINDING_FORMS.contains(&head));
+        let mut indent = if is_call {
+            start_column + 2
+        } else {
+            first_column
+        };
+        for (idx, child) in children.iter().enumerate() {
+            let is_comment = matches!(child.node, Node::Comment(_));
+            if idx != 0 {
+                let after_comment = matches!(children[idx - 1].node, Node::Comment(_));
+                if after_comment || (child.newlines_before > 0 && !is_comment) {
+                    let child_indent = if is_bindings && idx % 2 == 1 {
+                        // Values of bindings written in their own line
+                        indent + 2
+                    } else {
+                        indent
+                    };
+                    self.newline(child.newlines_before.max(1), child_indent);
+                } else if child.newlines_before > 0 {
+                    // Comments in their own line
+                    self.newline(child.newlines_before, indent);
+                } else {
+                    self.write(" ");
+                    // Function calls align the arguments with
+                    // the first one, if it's in the same line
+                    if idx == 1 && is_call && !is_body_form && head.is_some() {
+                        indent = self.column;
+                    }
+                }
+            }
+            self.print_node(&child.node, is_binding_form && idx == 1);
+        }
+
+        if matches!(
+            children.last(),
+            Some(Child {
+                node: Node::Comment(_),
+                ..
+            })
+        ) {
+            self.newline(1, if is_call { indent } else { first_column });
+        }
+        self.write(&close.to_string());
+    }
+}
@@ -0,0 +1,168 @@
+use std::{iter::Peekable, str::CharIndices};
+
+use crate::formatter::FormatError;
+
+/// Node of the syntax tree used by the formatter
+#[derive(Debug)]
+pub enum Node {
+    /// A symbol, a number, a string or `nil`, stored as it was written
+    Atom(String),
+    /// A comment, including the starting semicolons
+    Comment(String),
+    /// A list, vector, map, set or short lambda
+    Coll {
+        open: &'static str,
+        close: char,
+        children: Vec<Child>,
+    },
+    /// An expression preceded by the `@` shorthand for `deref`
+    Deref(Box<Node>),
+}
+
+/// A node with the number of line breaks before it in the source code
+#[derive(Debug)]
+pub struct Child {
+    pub node: Node,
+    pub newlines_before: usize,
+}
+
+pub struct Reader<'a> {
+    input: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(input: &'a str) -> Reader<'a> {
+        Reader {
+            input,
+            chars: input.char_indices().peekable(),
+        }
+    }
+
+    pub fn read(mut self) -> Result<Vec<Child>, FormatError> {
+        self.read_children(None)
+    }
+
+    fn peek(&mut self) -> Option<(usize, char)> {
+        self.chars.peek().copied()
+    }
+
+    /// Skips the whitespace, returning the number of line breaks found
+    fn skip_whitespace(&mut self) -> usize {
+        let mut newlines = 0;
+        while let Some((_, c)) = self.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+            if c == '\n' {
+                newlines += 1;
+            }
+            self.chars.next();
+        }
+        newlines
+    }
+
+    /// Reads nodes until the closing delimiter of the
+    /// collection, or until the end of the input
+    fn read_children(&mut self, close: Option<(char, usize)>) -> Result<Vec<Child>, FormatError> {
+        let mut children = Vec::new();
+        loop {
+            let newlines_before = self.skip_whitespace();
+            match (self.peek(), close) {
+                (None, None) => return Ok(children),
+                (None, Some((_, open_offset))) => {
+                    return Err(FormatError::UnclosedCollection(open_offset))
+                }
+                (Some((_, c)), Some((close, _))) if c == close => {
+                    self.chars.next();
+                    return Ok(children);
+                }
+                (Some((offset, c @ (')' | ']' | '}'))), _) => {
+                    return Err(FormatError::UnexpectedDelimiter(c, offset))
+                }
+                (Some(_), _) => {
+                    let node = self.read_node()?;
+                    children.push(Child {
+                        node,
+                        newlines_before,
+                    });
+                }
+            }
+        }
+    }
+
+    fn read_node(&mut self) -> Result<Node, FormatError> {
+        let (offset, c) = match self.peek() {
+            Some(next) => next,
+            None => return Err(FormatError::UnclosedCollection(self.input.len())),
+        };
+        let next_c = self.input[offset + c.len_utf8()..].chars().next();
+        match (c, next_c) {
+            (';', _) | ('#', Some('!')) => {
+                let end = self.input[offset..]
+                    .find('\n')
+                    .map_or(self.input.len(), |idx| offset + idx);
+                while matches!(self.peek(), Some((idx, _)) if idx < end) {
+                    self.chars.next();
+                }
+                Ok(Node::Comment(String::from(
+                    self.input[offset..end].trim_end(),
+                )))
+            }
+            ('"', _) => {
+                self.chars.next();
+                loop {
+                    match self.chars.next() {
+                        Some((_, '\\')) => {
+                            self.chars.next();
+                        }
+                        Some((end, '"')) => {
+                            return Ok(Node::Atom(String::from(&self.input[offset..=end])))
+                        }
+                        Some(_) => {}
+                        None => return Err(FormatError::UnclosedString(offset)),
+                    }
+                }
+            }
+            ('(', _) => self.read_coll("(", ')', offset),
+            ('[', _) => self.read_coll("[", ']', offset),
+            ('{', _) => self.read_coll("{", '}', offset),
+            ('#', Some('(')) => self.read_coll("#(", ')', offset),
+            ('#', Some('{')) => self.read_coll("#{", '}', offset),
+            ('\'', Some('(')) => self.read_coll("'(", ')', offset),
+            ('@', _) => {
+                self.chars.next();
+                Ok(Node::Deref(Box::new(self.read_node()?)))
+            }
+            (')' | ']' | '}', _) => Err(FormatError::UnexpectedDelimiter(c, offset)),
+            _ => {
+                let mut end = offset;
+                while let Some((idx, c)) = self.peek() {
+                    if c.is_whitespace() || "()[]{}\";".contains(c) {
+                        break;
+                    }
+                    end = idx + c.len_utf8();
+                    self.chars.next();
+                }
+                Ok(Node::Atom(String::from(&self.input[offset..end])))
+            }
+        }
+    }
+
+    fn read_coll(
+        &mut self,
+        open: &'static str,
+        close: char,
+        offset: usize,
+    ) -> Result<Node, FormatError> {
+        for _ in 0..open.len() {
+            self.chars.next();
+        }
+        let children = self.read_children(Some((close, offset)))?;
+        Ok(Node::Coll {
+            open,
+            close,
+            children,
+        })
+    }
+}
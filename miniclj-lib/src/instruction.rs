@@ -6,7 +6,7 @@ pub type InstructionPtr = usize;
 
 /// Represents the different type of instructions
 /// produced by the compiler and ran by the virtual machine
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Instruction {
     Call {
         callable: MemAddress,
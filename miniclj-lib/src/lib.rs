@@ -4,6 +4,10 @@ mod callables;
 mod compiler;
 /// Stores the implementation of the `Constant` enum
 mod constant;
+/// Stores the `Engine` used by applications to embed the language
+mod engine;
+/// Stores the formatter used to give source code a canonical layout
+mod formatter;
 /// Stores the implementation of the `Instruction` enum
 mod instruction;
 /// Stores the implementation of the `MemAddress` struct
@@ -13,7 +17,18 @@ mod parsers;
 /// Stores the mechanisms and structures used specifically during the execution
 mod vm;
 
-pub use compiler::CompilerState;
+pub use callables::{Callable, NativeFn};
+pub use compiler::{CompilationError, CompilerState, DebugInfo, Literal, SExpr};
+pub use engine::{Engine, EngineError};
+pub use formatter::{CodeFormatter, FormatError};
+pub use memaddress::MemAddress;
 pub use parsers::BytecodeParser;
-pub use parsers::SExprsParser;
-pub use vm::VMState;
+pub use parsers::{SExprsParser, SyntaxError};
+#[cfg(target_arch = "wasm32")]
+pub use vm::BrowserIO;
+pub use vm::{
+    Assertion, BufferIO, DebugCommand, DebugHandler, Debugger, EnvPermissions, Frame, FromValue,
+    FsPermissions, IOHandler, IntoValue, Limits, PauseReason, PausedState, ProfileFrame,
+    ProfileStats, Profiler, RunStatus, RuntimeError, RuntimeResult, Tracer, VMState, Value,
+    ValueMap, ValueSet,
+};
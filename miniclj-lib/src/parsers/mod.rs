@@ -1,4 +1,8 @@
-use std::collections::HashMap;
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fmt::{self, Display},
+};
 
 use lalrpop_util::{lalrpop_mod, ParseError};
 use num::Rational64;
@@ -21,7 +25,33 @@ lalrpop_mod!(
 
 type ConstantsInstructionsTuple = (HashMap<MemAddress, Constant>, Vec<Instruction>);
 type BytecodeParseError<'a> = ParseError<usize, bytecodeparser::Token<'a>, String>;
-type LispParseError<'a> = ParseError<usize, lispparser::Token<'a>, &'static str>;
+type LispParseError<'a> = ParseError<usize, lispparser::Token<'a>, SyntaxError>;
+
+/// An error found while parsing s-expressions that isn't caused by
+/// an unexpected token, along with the byte offsets of the text
+/// that caused it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxError {
+    pub start: usize,
+    pub message: &'static str,
+    pub end: usize,
+}
+
+impl SyntaxError {
+    pub fn new(start: usize, message: &'static str, end: usize) -> SyntaxError {
+        SyntaxError {
+            start,
+            message,
+            end,
+        }
+    }
+}
+
+impl Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
 
 /// Encloses the parser generated by `lalrpop`, used to parse bytecode
 pub struct BytecodeParser;
@@ -47,6 +77,86 @@ pub struct SExprsParser;
 
 impl SExprsParser {
     pub fn parse(input: &str) -> Result<Vec<SExpr>, LispParseError<'_>> {
-        lispparser::SExprsParser::new().parse(input)
+        lispparser::SExprsParser::new()
+            .parse(&SExprsParser::blank_shebang(input))
+            .map_err(|err| SExprsParser::borrow_from(input, err))
+    }
+
+    /// Parses the input like `parse`, but also returns the starting
+    /// and ending byte offsets of each top-level expression
+    pub fn parse_spanned(input: &str) -> Result<Vec<(usize, SExpr, usize)>, LispParseError<'_>> {
+        lispparser::SpannedSExprsParser::new()
+            .parse(&SExprsParser::blank_shebang(input))
+            .map_err(|err| SExprsParser::borrow_from(input, err))
+    }
+
+    /// Replaces the shebang line at the start of a script, like
+    /// `#!/usr/bin/env miniclj`, with spaces, so that it's skipped
+    /// without changing the offsets of the rest of the code
+    fn blank_shebang(input: &str) -> Cow<'_, str> {
+        if !input.starts_with("#!") {
+            return Cow::Borrowed(input);
+        }
+        let end = input.find(['\n', '\r']).unwrap_or(input.len());
+        Cow::Owned(" ".repeat(end) + &input[end..])
+    }
+
+    /// Makes the tokens of an error point to the original input,
+    /// which has the same offsets as the parsed one
+    fn borrow_from<'a>(input: &'a str, err: LispParseError<'_>) -> LispParseError<'a> {
+        let token = |(start, lispparser::Token(idx, _), end)| {
+            (start, lispparser::Token(idx, &input[start..end]), end)
+        };
+        match err {
+            ParseError::InvalidToken { location } => ParseError::InvalidToken { location },
+            ParseError::UnrecognizedEOF { location, expected } => {
+                ParseError::UnrecognizedEOF { location, expected }
+            }
+            ParseError::UnrecognizedToken { token: t, expected } => ParseError::UnrecognizedToken {
+                token: token(t),
+                expected,
+            },
+            ParseError::ExtraToken { token: t } => ParseError::ExtraToken { token: token(t) },
+            ParseError::User { error } => ParseError::User { error },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shebang() {
+        let spanned = SExprsParser::parse_spanned("#!/usr/bin/env miniclj\n(+ 1 2)").unwrap();
+        assert_eq!(spanned.len(), 1);
+        assert_eq!((spanned[0].0, spanned[0].2), (23, 30));
+        assert_eq!(SExprsParser::parse("#!\r\nnil").unwrap().len(), 1);
+
+        // Only the first line can be a shebang
+        assert!(SExprsParser::parse("(+ 1 2)\n#!/usr/bin/env miniclj").is_err());
+        assert!(SExprsParser::parse(" #!/usr/bin/env miniclj").is_err());
+
+        match SExprsParser::parse("#!/usr/bin/env miniclj\n)") {
+            Err(ParseError::UnrecognizedToken {
+                token: (start, lispparser::Token(_, text), _),
+                ..
+            }) => assert_eq!((start, text), (23, ")")),
+            result => panic!("Unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_syntax_error() {
+        match SExprsParser::parse("(+ 1\n  3/0)") {
+            Err(ParseError::User { error }) => {
+                assert_eq!(
+                    error,
+                    SyntaxError::new(7, "Fraction with a zero denominator", 10)
+                );
+                assert_eq!(error.to_string(), "Fraction with a zero denominator");
+            }
+            result => panic!("Unexpected result {:?}", result),
+        }
     }
 }
@@ -0,0 +1,130 @@
+use std::{
+    cell::RefCell,
+    fmt::{self, Debug, Formatter},
+    mem,
+    rc::Rc,
+};
+
+use crate::vm::{RuntimeResult, VMState, Value};
+
+/// Atom type from Clojure, a reference to a value that can be replaced.
+/// Cloning an atom doesn't clone its value, so every copy of an atom
+/// observes the changes made through the other ones
+#[derive(Clone)]
+pub struct Atom(Rc<RefCell<AtomState>>);
+
+struct AtomState {
+    value: Value,
+    watches: Vec<(Value, Value)>,
+    /// Whether the atom is being written, to avoid writing
+    /// forever an atom that contains itself
+    writing: bool,
+}
+
+impl Atom {
+    pub fn new(value: Value) -> Atom {
+        Atom(Rc::new(RefCell::new(AtomState {
+            value,
+            watches: Vec::new(),
+            writing: false,
+        })))
+    }
+
+    pub fn deref(&self) -> Value {
+        self.0.borrow().value.clone()
+    }
+
+    /// Replaces the value of the atom and calls its watches
+    /// with the key, the atom, the old value and the new value
+    pub fn reset(&self, state: &VMState, new_value: Value) -> RuntimeResult<Value> {
+        let old_value = mem::replace(&mut self.0.borrow_mut().value, new_value.clone());
+
+        let watches = self.0.borrow().watches.clone();
+        for (key, callable) in watches {
+            let args = vec![
+                key,
+                Value::Atom(self.clone()),
+                old_value.clone(),
+                new_value.clone(),
+            ];
+            state.execute_callable(&callable, args)?;
+        }
+
+        Ok(new_value)
+    }
+
+    /// Replaces the value of the atom with the result of calling
+    /// `callable` with the current value and `args`
+    pub fn swap(
+        &self,
+        state: &VMState,
+        callable: &Value,
+        args: Vec<Value>,
+    ) -> RuntimeResult<Value> {
+        let mut callable_args = vec![self.deref()];
+        callable_args.extend(args);
+        let new_value = state.execute_callable(callable, callable_args)?;
+        self.reset(state, new_value)
+    }
+
+    /// Replaces the value of the atom only if its current value
+    /// is equal to `old_value`, and returns if it was replaced
+    pub fn compare_and_set(
+        &self,
+        state: &VMState,
+        old_value: &Value,
+        new_value: Value,
+    ) -> RuntimeResult<bool> {
+        if &self.deref() == old_value {
+            self.reset(state, new_value)?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Adds a function that will be called every time the value of the
+    /// atom changes, replacing the previous one with the same key
+    pub fn add_watch(&self, key: Value, callable: Value) {
+        let watches = &mut self.0.borrow_mut().watches;
+        match watches.iter_mut().find(|(k, _)| k == &key) {
+            Some(watch) => watch.1 = callable,
+            None => watches.push((key, callable)),
+        }
+    }
+
+    pub fn remove_watch(&self, key: &Value) {
+        self.0.borrow_mut().watches.retain(|(k, _)| k != key);
+    }
+
+    /// Writes the atom as `#<atom value>`, using `write_value` to write its
+    /// value. If the value contains the atom, it's written as `#<atom ...>`
+    pub fn write_with<F>(&self, f: &mut Formatter<'_>, write_value: F) -> fmt::Result
+    where
+        F: FnOnce(&mut Formatter<'_>, &Value) -> fmt::Result,
+    {
+        if mem::replace(&mut self.0.borrow_mut().writing, true) {
+            return write!(f, "#<atom ...>");
+        }
+        let value = self.deref();
+        let result = write!(f, "#<atom ")
+            .and_then(|_| write_value(f, &value))
+            .and_then(|_| write!(f, ">"));
+        self.0.borrow_mut().writing = false;
+        result
+    }
+
+    pub fn ptr_eq(&self, other: &Atom) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+
+    pub fn as_ptr(&self) -> usize {
+        Rc::as_ptr(&self.0) as usize
+    }
+}
+
+impl Debug for Atom {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.write_with(f, |f, value| write!(f, "{:?}", value))
+    }
+}
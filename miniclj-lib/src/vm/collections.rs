@@ -0,0 +1,415 @@
+use std::{
+    collections::{btree_map, btree_set, BTreeMap, BTreeSet},
+    hash::{Hash, Hasher},
+};
+
+use indexmap::{IndexMap, IndexSet};
+
+use crate::vm::Value;
+
+/// Map type from Clojure. Its entries keep the order in which they were
+/// inserted, so a map is always printed the same way, unless it was
+/// created by `sorted-map`, which keeps them sorted by their keys
+#[derive(Debug, Clone, Default)]
+pub struct ValueMap {
+    entries: MapEntries,
+}
+
+#[derive(Debug, Clone)]
+enum MapEntries {
+    Indexed(IndexMap<Value, Value>),
+    Sorted(BTreeMap<Value, Value>),
+}
+
+impl Default for MapEntries {
+    fn default() -> MapEntries {
+        MapEntries::Indexed(IndexMap::new())
+    }
+}
+
+impl ValueMap {
+    pub fn new() -> ValueMap {
+        ValueMap::default()
+    }
+
+    /// Creates an empty map that keeps its entries sorted by their keys
+    pub fn sorted() -> ValueMap {
+        ValueMap {
+            entries: MapEntries::Sorted(BTreeMap::new()),
+        }
+    }
+
+    pub fn is_sorted(&self) -> bool {
+        matches!(self.entries, MapEntries::Sorted(_))
+    }
+
+    /// Creates an empty map of the same kind
+    pub fn empty_like(&self) -> ValueMap {
+        if self.is_sorted() {
+            ValueMap::sorted()
+        } else {
+            ValueMap::new()
+        }
+    }
+
+    /// Inserts an entry, replacing the value of the key in its original
+    /// position if it was already in the map, and returning the old value
+    pub fn insert(&mut self, key: Value, val: Value) -> Option<Value> {
+        match &mut self.entries {
+            MapEntries::Indexed(entries) => entries.insert(key, val),
+            MapEntries::Sorted(entries) => entries.insert(key, val),
+        }
+    }
+
+    /// Removes an entry, keeping the order of the rest
+    pub fn remove(&mut self, key: &Value) -> Option<Value> {
+        match &mut self.entries {
+            MapEntries::Indexed(entries) => entries.shift_remove(key),
+            MapEntries::Sorted(entries) => entries.remove(key),
+        }
+    }
+
+    pub fn get(&self, key: &Value) -> Option<&Value> {
+        match &self.entries {
+            MapEntries::Indexed(entries) => entries.get(key),
+            MapEntries::Sorted(entries) => entries.get(key),
+        }
+    }
+
+    pub fn contains_key(&self, key: &Value) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.entries {
+            MapEntries::Indexed(entries) => entries.len(),
+            MapEntries::Sorted(entries) => entries.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn iter(&self) -> MapIter<'_> {
+        match &self.entries {
+            MapEntries::Indexed(entries) => EitherIter::Indexed(entries.iter()),
+            MapEntries::Sorted(entries) => EitherIter::Sorted(entries.iter()),
+        }
+    }
+
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &Value> + ExactSizeIterator {
+        self.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &Value> + ExactSizeIterator {
+        self.iter().map(|(_, val)| val)
+    }
+}
+
+impl FromIterator<(Value, Value)> for ValueMap {
+    fn from_iter<T: IntoIterator<Item = (Value, Value)>>(iter: T) -> ValueMap {
+        ValueMap {
+            entries: MapEntries::Indexed(iter.into_iter().collect()),
+        }
+    }
+}
+
+impl Extend<(Value, Value)> for ValueMap {
+    fn extend<T: IntoIterator<Item = (Value, Value)>>(&mut self, iter: T) {
+        for (key, val) in iter {
+            self.insert(key, val);
+        }
+    }
+}
+
+impl<const N: usize> From<[(Value, Value); N]> for ValueMap {
+    fn from(entries: [(Value, Value); N]) -> ValueMap {
+        entries.into_iter().collect()
+    }
+}
+
+impl IntoIterator for ValueMap {
+    type Item = (Value, Value);
+    type IntoIter =
+        EitherIter<indexmap::map::IntoIter<Value, Value>, btree_map::IntoIter<Value, Value>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        match self.entries {
+            MapEntries::Indexed(entries) => EitherIter::Indexed(entries.into_iter()),
+            MapEntries::Sorted(entries) => EitherIter::Sorted(entries.into_iter()),
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a ValueMap {
+    type Item = (&'a Value, &'a Value);
+    type IntoIter = MapIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Two maps are equal if they have the same entries, in any order
+impl PartialEq for ValueMap {
+    fn eq(&self, other: &ValueMap) -> bool {
+        self.len() == other.len() && self.iter().all(|(key, val)| other.get(key) == Some(val))
+    }
+}
+impl Eq for ValueMap {}
+
+impl Hash for ValueMap {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(unordered_hash(self.iter()));
+    }
+}
+
+/// Set type from Clojure. Like `ValueMap`, its values keep the order in
+/// which they were inserted, unless it was created by `sorted-set`
+#[derive(Debug, Clone, Default)]
+pub struct ValueSet {
+    values: SetValues,
+}
+
+#[derive(Debug, Clone)]
+enum SetValues {
+    Indexed(IndexSet<Value>),
+    Sorted(BTreeSet<Value>),
+}
+
+impl Default for SetValues {
+    fn default() -> SetValues {
+        SetValues::Indexed(IndexSet::new())
+    }
+}
+
+impl ValueSet {
+    pub fn new() -> ValueSet {
+        ValueSet::default()
+    }
+
+    /// Creates an empty set that keeps its values sorted
+    pub fn sorted() -> ValueSet {
+        ValueSet {
+            values: SetValues::Sorted(BTreeSet::new()),
+        }
+    }
+
+    pub fn is_sorted(&self) -> bool {
+        matches!(self.values, SetValues::Sorted(_))
+    }
+
+    /// Creates an empty set of the same kind
+    pub fn empty_like(&self) -> ValueSet {
+        if self.is_sorted() {
+            ValueSet::sorted()
+        } else {
+            ValueSet::new()
+        }
+    }
+
+    /// Inserts a value, returning whether it wasn't already in the set
+    pub fn insert(&mut self, value: Value) -> bool {
+        match &mut self.values {
+            SetValues::Indexed(values) => values.insert(value),
+            SetValues::Sorted(values) => values.insert(value),
+        }
+    }
+
+    /// Removes a value, keeping the order of the rest
+    pub fn remove(&mut self, value: &Value) -> bool {
+        match &mut self.values {
+            SetValues::Indexed(values) => values.shift_remove(value),
+            SetValues::Sorted(values) => values.remove(value),
+        }
+    }
+
+    pub fn get(&self, value: &Value) -> Option<&Value> {
+        match &self.values {
+            SetValues::Indexed(values) => values.get(value),
+            SetValues::Sorted(values) => values.get(value),
+        }
+    }
+
+    pub fn contains(&self, value: &Value) -> bool {
+        self.get(value).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.values {
+            SetValues::Indexed(values) => values.len(),
+            SetValues::Sorted(values) => values.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn iter(&self) -> SetIter<'_> {
+        match &self.values {
+            SetValues::Indexed(values) => EitherIter::Indexed(values.iter()),
+            SetValues::Sorted(values) => EitherIter::Sorted(values.iter()),
+        }
+    }
+}
+
+impl FromIterator<Value> for ValueSet {
+    fn from_iter<T: IntoIterator<Item = Value>>(iter: T) -> ValueSet {
+        ValueSet {
+            values: SetValues::Indexed(iter.into_iter().collect()),
+        }
+    }
+}
+
+impl Extend<Value> for ValueSet {
+    fn extend<T: IntoIterator<Item = Value>>(&mut self, iter: T) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+impl<const N: usize> From<[Value; N]> for ValueSet {
+    fn from(values: [Value; N]) -> ValueSet {
+        values.into_iter().collect()
+    }
+}
+
+impl IntoIterator for ValueSet {
+    type Item = Value;
+    type IntoIter = EitherIter<indexmap::set::IntoIter<Value>, btree_set::IntoIter<Value>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        match self.values {
+            SetValues::Indexed(values) => EitherIter::Indexed(values.into_iter()),
+            SetValues::Sorted(values) => EitherIter::Sorted(values.into_iter()),
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a ValueSet {
+    type Item = &'a Value;
+    type IntoIter = SetIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Two sets are equal if they have the same values, in any order
+impl PartialEq for ValueSet {
+    fn eq(&self, other: &ValueSet) -> bool {
+        self.len() == other.len() && self.iter().all(|value| other.contains(value))
+    }
+}
+impl Eq for ValueSet {}
+
+impl Hash for ValueSet {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(unordered_hash(self.iter()));
+    }
+}
+
+pub type MapIter<'a> =
+    EitherIter<indexmap::map::Iter<'a, Value, Value>, btree_map::Iter<'a, Value, Value>>;
+pub type SetIter<'a> = EitherIter<indexmap::set::Iter<'a, Value>, btree_set::Iter<'a, Value>>;
+
+/// Iterator over a map or a set, which can keep the insertion order
+/// of its elements or sort them, each with a different backing collection
+pub enum EitherIter<I, S> {
+    Indexed(I),
+    Sorted(S),
+}
+
+impl<T, I: Iterator<Item = T>, S: Iterator<Item = T>> Iterator for EitherIter<I, S> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        match self {
+            EitherIter::Indexed(iter) => iter.next(),
+            EitherIter::Sorted(iter) => iter.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            EitherIter::Indexed(iter) => iter.size_hint(),
+            EitherIter::Sorted(iter) => iter.size_hint(),
+        }
+    }
+}
+
+impl<T, I, S> DoubleEndedIterator for EitherIter<I, S>
+where
+    I: DoubleEndedIterator<Item = T>,
+    S: DoubleEndedIterator<Item = T>,
+{
+    fn next_back(&mut self) -> Option<T> {
+        match self {
+            EitherIter::Indexed(iter) => iter.next_back(),
+            EitherIter::Sorted(iter) => iter.next_back(),
+        }
+    }
+}
+
+impl<T, I, S> ExactSizeIterator for EitherIter<I, S>
+where
+    I: ExactSizeIterator<Item = T>,
+    S: ExactSizeIterator<Item = T>,
+{
+}
+
+/// Combines the hashes of some elements without depending on their order,
+/// so that equal collections have the same hash
+fn unordered_hash<T: Hash, I: Iterator<Item = T>>(elements: I) -> u64 {
+    use std::collections::hash_map::DefaultHasher;
+
+    elements
+        .map(|element| {
+            let mut inner_state = DefaultHasher::new();
+            element.hash(&mut inner_state);
+            inner_state.finish()
+        })
+        .fold(0, u64::wrapping_add)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_order() {
+        let keys = [Value::from(3), Value::Nil, Value::String(String::from("a"))];
+
+        let mut map = keys
+            .iter()
+            .map(|key| (key.clone(), Value::Nil))
+            .collect::<ValueMap>();
+        map.remove(&Value::Nil);
+        map.insert(Value::from(1), Value::Nil);
+        let order = map.keys().cloned().collect::<Vec<Value>>();
+        assert_eq!(order, [keys[0].clone(), keys[2].clone(), Value::from(1)]);
+
+        let mut set = ValueSet::sorted();
+        set.extend(keys.iter().cloned());
+        set.insert(Value::from(1));
+        let order = set.iter().cloned().collect::<Vec<Value>>();
+        assert_eq!(
+            order,
+            [Value::Nil, Value::from(1), keys[0].clone(), keys[2].clone()]
+        );
+        assert_eq!(set, keys.into_iter().chain([Value::from(1)]).collect());
+
+        let mut map = ValueMap::sorted();
+        map.extend((0..1000).rev().map(|n| (Value::from(n), Value::Nil)));
+        map.insert(Value::from(500), Value::from(1));
+        assert_eq!(map.len(), 1000);
+        assert_eq!(map.get(&Value::from(500)), Some(&Value::from(1)));
+        assert!(map.keys().cloned().eq((0..1000).map(Value::from)));
+        let unsorted = map.clone().into_iter().rev().collect::<ValueMap>();
+        assert!(!unsorted.is_sorted());
+        assert_eq!(map, unsorted);
+    }
+}
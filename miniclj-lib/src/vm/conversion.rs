@@ -0,0 +1,339 @@
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+};
+
+use escape8259::{escape, unescape};
+use num::Rational64;
+
+use crate::vm::{RuntimeError, RuntimeResult, Value};
+
+/// Converts a Rust value into a `Value` that can be passed to `miniclj` code
+pub trait IntoValue {
+    fn into_value(self) -> Value;
+}
+
+/// Converts a `Value` returned by `miniclj` code into a Rust value,
+/// failing with a `RuntimeError::ConversionError` if the value
+/// doesn't have the expected type
+pub trait FromValue: Sized {
+    fn from_value(value: Value) -> RuntimeResult<Self>;
+}
+
+impl IntoValue for Value {
+    fn into_value(self) -> Value {
+        self
+    }
+}
+
+impl FromValue for Value {
+    fn from_value(value: Value) -> RuntimeResult<Value> {
+        Ok(value)
+    }
+}
+
+impl IntoValue for Rational64 {
+    fn into_value(self) -> Value {
+        Value::Number(self)
+    }
+}
+
+impl FromValue for Rational64 {
+    fn from_value(value: Value) -> RuntimeResult<Rational64> {
+        match value {
+            Value::Number(n) => Ok(n),
+            value => Err(RuntimeError::ConversionError("a number", value.type_str())),
+        }
+    }
+}
+
+/// Implements `FromValue` for integer types, and `IntoValue`
+/// for the ones that always fit in the numbers of the language
+macro_rules! integer_conversions {
+    ($($into_and_from:ty),*; $($only_from:ty),*) => {
+        $(
+            impl IntoValue for $into_and_from {
+                fn into_value(self) -> Value {
+                    Value::from(i64::from(self))
+                }
+            }
+        )*
+        $(
+            impl FromValue for $into_and_from {
+                fn from_value(value: Value) -> RuntimeResult<$into_and_from> {
+                    integer_from_value(value)
+                }
+            }
+        )*
+        $(
+            impl FromValue for $only_from {
+                fn from_value(value: Value) -> RuntimeResult<$only_from> {
+                    integer_from_value(value)
+                }
+            }
+        )*
+    };
+}
+
+integer_conversions!(i8, i16, i32, i64, u8, u16, u32; isize, u64, usize);
+
+fn integer_from_value<T: TryFrom<i64>>(value: Value) -> RuntimeResult<T> {
+    let n = value
+        .as_i64()
+        .map_err(|type_str| RuntimeError::ConversionError("an integer", type_str))?;
+    T::try_from(n).map_err(|_| RuntimeError::ConversionError("an integer", "a number out of range"))
+}
+
+impl IntoValue for bool {
+    fn into_value(self) -> Value {
+        Value::from(self)
+    }
+}
+
+impl FromValue for bool {
+    fn from_value(value: Value) -> RuntimeResult<bool> {
+        value
+            .as_bool()
+            .map_err(|type_str| RuntimeError::ConversionError("a boolean", type_str))
+    }
+}
+
+// Strings keep the escape sequences written in the source code,
+// so they are escaped and unescaped when crossing to Rust
+impl IntoValue for String {
+    fn into_value(self) -> Value {
+        Value::String(escape(self))
+    }
+}
+
+impl IntoValue for &str {
+    fn into_value(self) -> Value {
+        Value::String(escape(self))
+    }
+}
+
+impl FromValue for String {
+    fn from_value(value: Value) -> RuntimeResult<String> {
+        match value {
+            Value::String(s) => Ok(unescape(&s).unwrap_or(s)),
+            value => Err(RuntimeError::ConversionError("a string", value.type_str())),
+        }
+    }
+}
+
+impl<T: IntoValue> IntoValue for Option<T> {
+    fn into_value(self) -> Value {
+        match self {
+            Some(value) => value.into_value(),
+            None => Value::Nil,
+        }
+    }
+}
+
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(value: Value) -> RuntimeResult<Option<T>> {
+        match value {
+            Value::Nil => Ok(None),
+            value => T::from_value(value).map(Some),
+        }
+    }
+}
+
+impl<T: IntoValue> IntoValue for Vec<T> {
+    fn into_value(self) -> Value {
+        Value::Vector(self.into_iter().map(IntoValue::into_value).collect())
+    }
+}
+
+impl<T: FromValue> FromValue for Vec<T> {
+    fn from_value(value: Value) -> RuntimeResult<Vec<T>> {
+        let values = match value {
+            Value::Vector(vector) => vector,
+            Value::List(list) => list.into_iter().collect(),
+            Value::Set(set) => set.into_iter().collect(),
+            value => {
+                return Err(RuntimeError::ConversionError(
+                    "a sequence",
+                    value.type_str(),
+                ))
+            }
+        };
+        values.into_iter().map(T::from_value).collect()
+    }
+}
+
+impl<T: IntoValue> IntoValue for HashSet<T> {
+    fn into_value(self) -> Value {
+        Value::Set(self.into_iter().map(IntoValue::into_value).collect())
+    }
+}
+
+impl<T: FromValue + Eq + Hash> FromValue for HashSet<T> {
+    fn from_value(value: Value) -> RuntimeResult<HashSet<T>> {
+        match value {
+            Value::Set(set) => set.into_iter().map(T::from_value).collect(),
+            value => Err(RuntimeError::ConversionError("a set", value.type_str())),
+        }
+    }
+}
+
+impl<K: IntoValue, V: IntoValue> IntoValue for HashMap<K, V> {
+    fn into_value(self) -> Value {
+        Value::Map(
+            self.into_iter()
+                .map(|(key, value)| (key.into_value(), value.into_value()))
+                .collect(),
+        )
+    }
+}
+
+impl<K: FromValue + Eq + Hash, V: FromValue> FromValue for HashMap<K, V> {
+    fn from_value(value: Value) -> RuntimeResult<HashMap<K, V>> {
+        match value {
+            Value::Map(map) => map
+                .into_iter()
+                .map(|(key, value)| Ok((K::from_value(key)?, V::from_value(value)?)))
+                .collect(),
+            value => Err(RuntimeError::ConversionError("a map", value.type_str())),
+        }
+    }
+}
+
+/// Implements the conversions of tuples, which are represented as vectors
+macro_rules! tuple_conversions {
+    ($($len:literal => ($($name:ident),+)),+) => {
+        $(
+            impl<$($name: IntoValue),+> IntoValue for ($($name,)+) {
+                #[allow(non_snake_case)]
+                fn into_value(self) -> Value {
+                    let ($($name,)+) = self;
+                    Value::Vector(vec![$($name.into_value()),+])
+                }
+            }
+
+            impl<$($name: FromValue),+> FromValue for ($($name,)+) {
+                fn from_value(value: Value) -> RuntimeResult<($($name,)+)> {
+                    let expected = concat!("a vector of ", $len, " values");
+                    match value {
+                        Value::Vector(vector) if vector.len() == $len => {
+                            let mut values = vector.into_iter();
+                            Ok(($($name::from_value(values.next().unwrap())?,)+))
+                        }
+                        Value::Vector(_) => {
+                            Err(RuntimeError::ConversionError(expected, "a vector of other length"))
+                        }
+                        value => Err(RuntimeError::ConversionError(expected, value.type_str())),
+                    }
+                }
+            }
+        )+
+    };
+}
+
+tuple_conversions!(
+    1 => (A),
+    2 => (A, B),
+    3 => (A, B, C),
+    4 => (A, B, C, D)
+);
+
+/// Implements `IntoValue` and `FromValue` for a struct, which is
+/// represented as a map from the names of its fields, as strings,
+/// to their values. Every field has to implement both traits
+///
+/// ```
+/// use miniclj_lib::{value_struct, FromValue, IntoValue};
+///
+/// struct Point {
+///     x: i64,
+///     y: i64,
+/// }
+///
+/// value_struct!(Point { x, y });
+///
+/// let point = Point::from_value(Point { x: 1, y: 2 }.into_value()).unwrap();
+/// assert_eq!((point.x, point.y), (1, 2));
+/// ```
+#[macro_export]
+macro_rules! value_struct {
+    ($struct:ident { $($field:ident),* $(,)? }) => {
+        impl $crate::IntoValue for $struct {
+            fn into_value(self) -> $crate::Value {
+                let mut map = $crate::ValueMap::new();
+                $(
+                    map.insert(
+                        $crate::IntoValue::into_value(stringify!($field)),
+                        $crate::IntoValue::into_value(self.$field),
+                    );
+                )*
+                $crate::Value::Map(map)
+            }
+        }
+
+        impl $crate::FromValue for $struct {
+            fn from_value(value: $crate::Value) -> $crate::RuntimeResult<$struct> {
+                let mut map = match value {
+                    $crate::Value::Map(map) => map,
+                    value => {
+                        return Err($crate::RuntimeError::ConversionError(
+                            concat!("a map for ", stringify!($struct)),
+                            value.type_str(),
+                        ))
+                    }
+                };
+                Ok($struct {
+                    $(
+                        $field: $crate::FromValue::from_value(
+                            map.remove(&$crate::IntoValue::into_value(stringify!($field)))
+                                .unwrap_or($crate::Value::Nil),
+                        )?,
+                    )*
+                })
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip<T: IntoValue + FromValue>(value: T) -> T {
+        T::from_value(value.into_value()).unwrap()
+    }
+
+    #[test]
+    fn test_round_trips() {
+        assert_eq!(round_trip(-5i32), -5);
+        assert_eq!(
+            round_trip(String::from("a \"quoted\"\nline")),
+            "a \"quoted\"\nline"
+        );
+        assert_eq!(round_trip(Some(true)), Some(true));
+        assert_eq!(round_trip(None::<i64>), None);
+        assert_eq!(
+            round_trip(vec![(1u8, String::from("a"))]),
+            vec![(1, String::from("a"))]
+        );
+
+        let map = HashMap::from([(String::from("a"), HashSet::from([1i64, 2]))]);
+        assert_eq!(round_trip(map.clone()), map);
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = i64::from_value("1".into_value()).unwrap_err();
+        assert_eq!(err.to_string(), "Couldn't convert a string to an integer");
+        let err = u8::from_value(300i64.into_value()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Couldn't convert a number out of range to an integer"
+        );
+        let err = <(i64, i64)>::from_value(vec![1i64].into_value()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Couldn't convert a vector of other length to a vector of 2 values"
+        );
+        assert!(Vec::<String>::from_value(vec![1i64].into_value()).is_err());
+    }
+}
@@ -0,0 +1,378 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::BTreeSet,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use crate::{
+    compiler::DebugInfo,
+    instruction::InstructionPtr,
+    memaddress::MemAddress,
+    vm::{trace, Scope, VMState, Value},
+};
+
+/// The reason why the execution was paused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PauseReason {
+    /// The execution paused before its first instruction
+    Entry,
+    Breakpoint,
+    /// A step requested by the `DebugHandler` finished
+    Step,
+    /// The execution was paused from another thread
+    /// through the handle returned by `Debugger::interrupt_handle`
+    Interrupt,
+}
+
+/// The way the execution continues after a pause
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugCommand {
+    /// Runs until the next breakpoint
+    Continue,
+    /// Executes a single instruction, entering the called functions
+    Step,
+    /// Executes the instructions until the next one in the same
+    /// function, without pausing inside the called functions
+    StepOver,
+    /// Executes the instructions until the current function returns
+    StepOut,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Mode {
+    Run,
+    Step,
+    StepOver(usize),
+    StepOut(usize),
+}
+
+/// A function being executed, with the instruction where it's paused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame {
+    /// The first instruction of the function, `None` for the root scope
+    pub entry_ptr: Option<InstructionPtr>,
+    pub instruction_ptr: InstructionPtr,
+}
+
+/// The state of the virtual machine while the execution is paused
+pub struct PausedState<'a> {
+    vm_state: &'a VMState,
+    scope: &'a Scope,
+    reason: PauseReason,
+    frames: Vec<Frame>,
+}
+
+impl<'a> PausedState<'a> {
+    pub fn reason(&self) -> PauseReason {
+        self.reason
+    }
+
+    /// Returns the instruction that will be executed next
+    pub fn instruction_ptr(&self) -> InstructionPtr {
+        self.frames.last().unwrap().instruction_ptr
+    }
+
+    /// Returns the functions being executed, the innermost one last
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    /// Returns the variables of the current scope with their indexes
+    pub fn vars(&self) -> Vec<(usize, Value)> {
+        self.scope.vars()
+    }
+
+    /// Returns the temporal values of the current scope with their indexes
+    pub fn temps(&self) -> Vec<(usize, Value)> {
+        self.scope.temps()
+    }
+
+    /// Returns the value stored in an address of the
+    /// global scope, if it was already assigned
+    pub fn global(&self, address: &MemAddress) -> Option<Value> {
+        self.vm_state.get_global(address).ok()
+    }
+
+    /// Returns an instruction, with its addresses decoded
+    pub fn instruction(&self, instruction_ptr: InstructionPtr) -> Option<String> {
+        self.vm_state
+            .instruction(instruction_ptr)
+            .map(|instruction| trace::describe_instruction(&instruction))
+    }
+}
+
+/// Receives the control of the execution when it's paused
+pub trait DebugHandler {
+    /// Called before executing the instruction where the execution is
+    /// paused, returns the way the execution should continue. The
+    /// breakpoints can be changed through the `debugger`
+    fn paused(&mut self, debugger: &Debugger, state: &PausedState) -> DebugCommand;
+}
+
+/// Pauses the execution of the virtual machine in breakpoints and
+/// after steps, and gives the control to a `DebugHandler`
+pub struct Debugger {
+    handler: RefCell<Box<dyn DebugHandler>>,
+    debug_info: Option<DebugInfo>,
+    breakpoints: RefCell<BTreeSet<InstructionPtr>>,
+    mode: Cell<Mode>,
+    pause_on_entry: Cell<bool>,
+    frame_ptrs: RefCell<Vec<InstructionPtr>>,
+    interrupt: Arc<AtomicBool>,
+}
+
+impl Debugger {
+    pub fn new(handler: Box<dyn DebugHandler>, debug_info: Option<DebugInfo>) -> Debugger {
+        Debugger {
+            handler: RefCell::new(handler),
+            debug_info,
+            breakpoints: RefCell::new(BTreeSet::new()),
+            mode: Cell::new(Mode::Run),
+            pause_on_entry: Cell::new(false),
+            frame_ptrs: RefCell::new(Vec::new()),
+            interrupt: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Pauses the execution before the first instruction
+    pub fn pause_on_entry(&self) {
+        self.pause_on_entry.set(true);
+    }
+
+    pub fn debug_info(&self) -> Option<&DebugInfo> {
+        self.debug_info.as_ref()
+    }
+
+    pub fn set_breakpoint(&self, instruction_ptr: InstructionPtr) {
+        self.breakpoints.borrow_mut().insert(instruction_ptr);
+    }
+
+    pub fn remove_breakpoint(&self, instruction_ptr: InstructionPtr) {
+        self.breakpoints.borrow_mut().remove(&instruction_ptr);
+    }
+
+    pub fn clear_breakpoints(&self) {
+        self.breakpoints.borrow_mut().clear();
+    }
+
+    pub fn breakpoints(&self) -> Vec<InstructionPtr> {
+        self.breakpoints.borrow().iter().copied().collect()
+    }
+
+    /// Sets breakpoints in the instructions of a line, using the debug
+    /// information, and returns them. If the line doesn't have any
+    /// instruction no breakpoint is set
+    pub fn set_line_breakpoint(&self, line: usize) -> Vec<InstructionPtr> {
+        let instruction_ptrs = self
+            .debug_info
+            .as_ref()
+            .map(|debug_info| debug_info.line_instructions(line))
+            .unwrap_or_default();
+        for instruction_ptr in &instruction_ptrs {
+            self.set_breakpoint(*instruction_ptr);
+        }
+        instruction_ptrs
+    }
+
+    pub fn remove_line_breakpoint(&self, line: usize) {
+        if let Some(debug_info) = &self.debug_info {
+            for instruction_ptr in debug_info.line_instructions(line) {
+                self.remove_breakpoint(instruction_ptr);
+            }
+        }
+    }
+
+    /// Returns a flag that pauses the execution when set,
+    /// which can be shared with other threads
+    pub fn interrupt_handle(&self) -> Arc<AtomicBool> {
+        self.interrupt.clone()
+    }
+
+    /// Called by the virtual machine before executing every instruction
+    pub(crate) fn before_instruction(
+        &self,
+        vm_state: &VMState,
+        scope: &Scope,
+        call_stack: &[InstructionPtr],
+        instruction_ptr: InstructionPtr,
+    ) {
+        let depth = call_stack.len();
+        {
+            let mut frame_ptrs = self.frame_ptrs.borrow_mut();
+            frame_ptrs.resize(depth + 1, 0);
+            frame_ptrs[depth] = instruction_ptr;
+        }
+
+        let reason = if self.interrupt.swap(false, Ordering::Relaxed) {
+            Some(PauseReason::Interrupt)
+        } else if self.pause_on_entry.replace(false) {
+            Some(PauseReason::Entry)
+        } else if self.breakpoints.borrow().contains(&instruction_ptr) {
+            Some(PauseReason::Breakpoint)
+        } else {
+            match self.mode.get() {
+                Mode::Step => Some(PauseReason::Step),
+                Mode::StepOver(step_depth) if depth <= step_depth => Some(PauseReason::Step),
+                Mode::StepOut(step_depth) if depth < step_depth => Some(PauseReason::Step),
+                _ => None,
+            }
+        };
+
+        if let Some(reason) = reason {
+            let frames = std::iter::once(None)
+                .chain(call_stack.iter().copied().map(Some))
+                .zip(self.frame_ptrs.borrow().iter().copied())
+                .map(|(entry_ptr, instruction_ptr)| Frame {
+                    entry_ptr,
+                    instruction_ptr,
+                })
+                .collect();
+            let state = PausedState {
+                vm_state,
+                scope,
+                reason,
+                frames,
+            };
+            let command = self.handler.borrow_mut().paused(self, &state);
+            self.mode.set(match command {
+                DebugCommand::Continue => Mode::Run,
+                DebugCommand::Step => Mode::Step,
+                DebugCommand::StepOver => Mode::StepOver(depth),
+                DebugCommand::StepOut => Mode::StepOut(depth),
+            });
+        }
+    }
+}
+
+impl std::fmt::Debug for Debugger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Debugger({} breakpoints)",
+            self.breakpoints.borrow().len()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::{compiler::CompilerState, parsers::SExprsParser};
+
+    type Pauses = Rc<RefCell<Vec<(PauseReason, InstructionPtr, Option<InstructionPtr>)>>>;
+
+    /// Records where the execution pauses, and
+    /// continues with the given commands in order
+    struct ScriptedHandler {
+        commands: Rc<RefCell<Vec<DebugCommand>>>,
+        pauses: Pauses,
+    }
+
+    impl DebugHandler for ScriptedHandler {
+        fn paused(&mut self, _: &Debugger, state: &PausedState) -> DebugCommand {
+            let entry_ptr = state.frames().last().unwrap().entry_ptr;
+            self.pauses
+                .borrow_mut()
+                .push((state.reason(), state.instruction_ptr(), entry_ptr));
+            if entry_ptr.is_some() {
+                assert_eq!(state.vars(), vec![(0, Value::from(3))]);
+            }
+            self.commands.borrow_mut().remove(0)
+        }
+    }
+
+    /// Executes a program that calls the function `f`, which starts at
+    /// instruction 2, from instruction 4. `setup` is called to set the
+    /// breakpoints, and returns the commands used after every pause
+    fn debug<F>(setup: F) -> Vec<(PauseReason, InstructionPtr, Option<InstructionPtr>)>
+    where
+        F: FnOnce(&Debugger) -> Vec<DebugCommand>,
+    {
+        let source = "(defn f [x]\n  (* x 2))\n(def y (f 3))\n(+ y 1)";
+        let mut compiler_state = CompilerState::default();
+        for expr in SExprsParser::parse(source).unwrap() {
+            compiler_state.compile(expr).unwrap();
+        }
+        let debug_info = compiler_state.debug_info(source);
+
+        let commands = Rc::new(RefCell::new(Vec::new()));
+        let pauses = Pauses::default();
+        let handler = ScriptedHandler {
+            commands: commands.clone(),
+            pauses: pauses.clone(),
+        };
+        let debugger = Debugger::new(Box::new(handler), Some(debug_info));
+        *commands.borrow_mut() = setup(&debugger);
+        let (constants_rev, instructions) = compiler_state.into_parts();
+        let constants = constants_rev
+            .into_iter()
+            .map(|(constant, address)| (address, constant))
+            .collect();
+        let mut vm_state = VMState::new(constants, instructions);
+        vm_state.set_debugger(Some(debugger));
+        vm_state.execute().unwrap();
+        let pauses = pauses.borrow().clone();
+        pauses
+    }
+
+    #[test]
+    fn test_steps() {
+        let pauses = debug(|debugger| {
+            debugger.pause_on_entry();
+            vec![
+                DebugCommand::Step,
+                DebugCommand::Step,
+                DebugCommand::Step,
+                DebugCommand::StepOut,
+                DebugCommand::StepOver,
+                DebugCommand::Continue,
+            ]
+        });
+        assert_eq!(
+            pauses,
+            vec![
+                (PauseReason::Entry, 0, None),
+                (PauseReason::Step, 1, None),
+                (PauseReason::Step, 4, None),
+                (PauseReason::Step, 2, Some(2)),
+                (PauseReason::Step, 5, None),
+                (PauseReason::Step, 6, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_breakpoints() {
+        let pauses = debug(|debugger| {
+            debugger.set_breakpoint(4);
+            vec![DebugCommand::StepOver, DebugCommand::Continue]
+        });
+        assert_eq!(
+            pauses,
+            vec![
+                (PauseReason::Breakpoint, 4, None),
+                (PauseReason::Step, 5, None)
+            ]
+        );
+
+        let pauses = debug(|debugger| {
+            assert_eq!(debugger.set_line_breakpoint(2), vec![2]);
+            assert!(debugger.set_line_breakpoint(10).is_empty());
+            vec![DebugCommand::Continue]
+        });
+        assert_eq!(pauses, vec![(PauseReason::Breakpoint, 2, Some(2))]);
+
+        let pauses = debug(|debugger| {
+            debugger.set_line_breakpoint(2);
+            debugger.remove_line_breakpoint(2);
+            debugger.interrupt_handle().store(true, Ordering::Relaxed);
+            vec![DebugCommand::Continue]
+        });
+        assert_eq!(pauses, vec![(PauseReason::Interrupt, 0, None)]);
+    }
+}
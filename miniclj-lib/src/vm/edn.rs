@@ -0,0 +1,359 @@
+use std::{fmt::Write, iter::Peekable, str::CharIndices};
+
+use escape8259::{escape, unescape};
+use num::{CheckedMul, Rational64};
+use smol_str::SmolStr;
+
+use crate::vm::{Value, ValueMap, ValueSet};
+
+impl Value {
+    /// Writes the value as EDN, so that it can be read back exactly by
+    /// `Value::from_edn`. Fails with the type of the first value that can't
+    /// be written: functions, atoms and lazy sequences that aren't realized
+    pub fn to_edn(&self) -> Result<String, &'static str> {
+        let mut out = String::new();
+        write_edn(&mut out, self)?;
+        Ok(out)
+    }
+
+    /// Reads the first EDN element of the input. Keywords are read as symbols
+    /// that keep their colon, booleans as 1 and 0, characters as strings and
+    /// decimal numbers as exact rationals, since the language doesn't have
+    /// those types. Tagged literals aren't supported
+    pub fn from_edn(input: &str) -> Result<Value, String> {
+        let mut reader = EdnReader {
+            input,
+            chars: input.char_indices().peekable(),
+        };
+        match reader.read_element()? {
+            Some(value) => Ok(value),
+            None => Err(String::from("expected an element but the input ended")),
+        }
+    }
+}
+
+fn write_edn(out: &mut String, value: &Value) -> Result<(), &'static str> {
+    let write_seq = |out: &mut String,
+                     open: &str,
+                     close: char,
+                     values: Vec<&Value>|
+     -> Result<(), &'static str> {
+        out.push_str(open);
+        for (idx, value) in values.into_iter().enumerate() {
+            if idx != 0 {
+                out.push(' ');
+            }
+            write_edn(out, value)?;
+        }
+        out.push(close);
+        Ok(())
+    };
+
+    match value {
+        Value::List(list) => write_seq(out, "(", ')', list.iter().collect())?,
+        Value::LazySeq(seq) => match seq.realized_values() {
+            (values, true) => write_seq(out, "(", ')', values.iter().collect())?,
+            (_, false) => return Err(value.type_str()),
+        },
+        Value::Vector(vector) => write_seq(out, "[", ']', vector.iter().collect())?,
+        Value::Set(set) => write_seq(out, "#{", '}', set.iter().collect())?,
+        Value::Map(map) => write_seq(
+            out,
+            "{",
+            '}',
+            map.iter().flat_map(|(key, val)| [key, val]).collect(),
+        )?,
+        Value::Symbol(symbol) => out.push_str(symbol),
+        Value::String(s) => write_string(out, &unescape(s).unwrap_or_else(|_| s.clone())),
+        Value::Number(n) if n.is_integer() => write!(out, "{}", n.numer()).unwrap(),
+        Value::Number(n) => write!(out, "{}/{}", n.numer(), n.denom()).unwrap(),
+        Value::Nil => out.push_str("nil"),
+        value => return Err(value.type_str()),
+    }
+    Ok(())
+}
+
+fn write_string(out: &mut String, string: &str) {
+    out.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// The characters that end a symbol, a number or any other token
+fn is_delimiter(c: char) -> bool {
+    c.is_whitespace() || matches!(c, ',' | '(' | ')' | '[' | ']' | '{' | '}' | '"' | ';')
+}
+
+struct EdnReader<'a> {
+    input: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl EdnReader<'_> {
+    /// Skips whitespace, commas, comments and discarded elements
+    fn skip_whitespace(&mut self) -> Result<(), String> {
+        while let Some((_, c)) = self.chars.peek().copied() {
+            if c.is_whitespace() || c == ',' {
+                self.chars.next();
+            } else if c == ';' {
+                while !matches!(self.chars.next(), Some((_, '\n')) | None) {}
+            } else if self.rest().starts_with("#_") {
+                self.chars.next();
+                self.chars.next();
+                self.read_element()?;
+            } else {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn rest(&mut self) -> &str {
+        match self.chars.peek() {
+            Some((offset, _)) => &self.input[*offset..],
+            None => "",
+        }
+    }
+
+    fn read_token(&mut self) -> (usize, &str) {
+        let start = self
+            .chars
+            .peek()
+            .map_or(self.input.len(), |(offset, _)| *offset);
+        let mut end = start;
+        while let Some((offset, c)) = self.chars.peek().copied() {
+            if is_delimiter(c) && offset != start {
+                break;
+            }
+            end = offset + c.len_utf8();
+            self.chars.next();
+        }
+        (start, &self.input[start..end])
+    }
+
+    /// Reads the next element, or returns `None` if the input ended
+    fn read_element(&mut self) -> Result<Option<Value>, String> {
+        self.skip_whitespace()?;
+        let (offset, c) = match self.chars.peek().copied() {
+            Some(next) => next,
+            None => return Ok(None),
+        };
+        let value = match c {
+            '(' => Value::List(self.read_seq(')')?.into_iter().rev().collect()),
+            '[' => Value::Vector(self.read_seq(']')?),
+            '{' => {
+                let values = self.read_seq('}')?;
+                if values.len() % 2 != 0 {
+                    return Err(format!("map at byte {} has an odd number of forms", offset));
+                }
+                let mut values = values.into_iter();
+                let mut map = ValueMap::new();
+                while let (Some(key), Some(val)) = (values.next(), values.next()) {
+                    map.insert(key, val);
+                }
+                Value::Map(map)
+            }
+            '#' if self.rest().starts_with("#{") => {
+                self.chars.next();
+                Value::Set(self.read_seq('}')?.into_iter().collect::<ValueSet>())
+            }
+            '#' => return Err(format!("tagged literal at byte {} isn't supported", offset)),
+            '"' => Value::String(escape(self.read_string()?)),
+            '\\' => Value::String(escape(self.read_char()?)),
+            ')' | ']' | '}' => return Err(format!("unexpected {} at byte {}", c, offset)),
+            _ => {
+                let (offset, token) = self.read_token();
+                read_atom(token)
+                    .ok_or_else(|| format!("invalid element {} at byte {}", token, offset))?
+            }
+        };
+        Ok(Some(value))
+    }
+
+    fn read_seq(&mut self, close: char) -> Result<Vec<Value>, String> {
+        let (start, _) = self.chars.next().unwrap();
+        let mut values = Vec::new();
+        loop {
+            self.skip_whitespace()?;
+            match self.chars.peek() {
+                Some((_, c)) if *c == close => {
+                    self.chars.next();
+                    return Ok(values);
+                }
+                Some(_) => values.extend(self.read_element()?),
+                None => return Err(format!("unclosed collection at byte {}", start)),
+            }
+        }
+    }
+
+    /// Reads a string, returning it without its escape sequences
+    fn read_string(&mut self) -> Result<String, String> {
+        let (start, _) = self.chars.next().unwrap();
+        let mut string = String::new();
+        loop {
+            match self.chars.next() {
+                Some((_, '"')) => return Ok(string),
+                Some((offset, '\\')) => match self.chars.next() {
+                    Some((_, 't')) => string.push('\t'),
+                    Some((_, 'r')) => string.push('\r'),
+                    Some((_, 'n')) => string.push('\n'),
+                    Some((_, 'b')) => string.push('\u{8}'),
+                    Some((_, 'f')) => string.push('\u{c}'),
+                    Some((_, c @ ('\\' | '"'))) => string.push(c),
+                    Some((_, 'u')) => {
+                        let code = self.input.get(offset + 2..offset + 6);
+                        let c = code
+                            .and_then(|code| u32::from_str_radix(code, 16).ok())
+                            .and_then(char::from_u32)
+                            .ok_or_else(|| format!("invalid unicode escape at byte {}", offset))?;
+                        string.push(c);
+                        for _ in 0..4 {
+                            self.chars.next();
+                        }
+                    }
+                    _ => return Err(format!("invalid escape sequence at byte {}", offset)),
+                },
+                Some((_, c)) => string.push(c),
+                None => return Err(format!("unclosed string at byte {}", start)),
+            }
+        }
+    }
+
+    fn read_char(&mut self) -> Result<String, String> {
+        let (offset, token) = self.read_token();
+        let c = match &token[1..] {
+            "newline" => '\n',
+            "return" => '\r',
+            "space" => ' ',
+            "tab" => '\t',
+            name if name.chars().count() == 1 => name.chars().next().unwrap(),
+            name => name
+                .strip_prefix('u')
+                .filter(|code| code.len() == 4)
+                .and_then(|code| u32::from_str_radix(code, 16).ok())
+                .and_then(char::from_u32)
+                .ok_or_else(|| format!("invalid character {} at byte {}", token, offset))?,
+        };
+        Ok(String::from(c))
+    }
+}
+
+/// Reads a token that isn't a collection, a string or a character
+fn read_atom(token: &str) -> Option<Value> {
+    match token {
+        "nil" => return Some(Value::Nil),
+        "true" => return Some(Value::from(true)),
+        "false" => return Some(Value::from(false)),
+        _ => {}
+    }
+    let mut chars = token.chars();
+    let first = chars.next()?;
+    let second = chars.next();
+    let is_number = first.is_ascii_digit()
+        || (matches!(first, '+' | '-') && second.is_some_and(|c| c.is_ascii_digit()));
+    if is_number {
+        read_number(token).map(Value::Number)
+    } else {
+        Some(Value::Symbol(SmolStr::from(token)))
+    }
+}
+
+/// Reads an integer, a ratio or a decimal number exactly
+fn read_number(token: &str) -> Option<Rational64> {
+    let (negative, token) = match token.strip_prefix('-') {
+        Some(token) => (true, token),
+        None => (false, token.strip_prefix('+').unwrap_or(token)),
+    };
+    let number = if let Some((numer, denom)) = token.split_once('/') {
+        let denom = denom.parse::<i64>().ok().filter(|denom| *denom != 0)?;
+        Rational64::new(numer.parse::<i64>().ok()?, denom)
+    } else {
+        let token = token.strip_suffix(['N', 'M']).unwrap_or(token);
+        let (mantissa, exponent) = match token.find(['e', 'E']) {
+            Some(idx) => (&token[..idx], token[idx + 1..].parse::<i32>().ok()?),
+            None => (token, 0),
+        };
+        let (integer, decimals) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        if !(integer.chars().chain(decimals.chars())).all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let digits = format!("{}{}", integer, decimals).parse::<i64>().ok()?;
+        let scale = exponent - decimals.len() as i32;
+        let power = Rational64::from(10i64.checked_pow(scale.unsigned_abs())?);
+        if scale >= 0 {
+            Rational64::from(digits).checked_mul(&power)?
+        } else {
+            Rational64::from(digits) / power
+        }
+    };
+    Some(if negative { -number } else { number })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let source = r#"{"a\n" [1 -1/3 nil], sym #{"b"}, (1 (2)) :key}"#;
+        let value = Value::from_edn(source).unwrap();
+        assert_eq!(Value::from_edn(&value.to_edn().unwrap()), Ok(value.clone()));
+
+        let list = |values: Vec<Value>| Value::List(values.into_iter().rev().collect());
+        let expected = ValueMap::from([
+            (
+                Value::String(String::from("a\\n")),
+                Value::Vector(vec![
+                    Value::from(1),
+                    Value::Number(Rational64::new(-1, 3)),
+                    Value::Nil,
+                ]),
+            ),
+            (
+                Value::Symbol(SmolStr::from("sym")),
+                Value::Set(ValueSet::from([Value::String(String::from("b"))])),
+            ),
+            (
+                list(vec![Value::from(1), list(vec![Value::from(2)])]),
+                Value::Symbol(SmolStr::from(":key")),
+            ),
+        ]);
+        assert_eq!(value, Value::Map(expected));
+    }
+
+    #[test]
+    fn test_read() {
+        let read = |source| Value::from_edn(source).unwrap();
+        assert_eq!(read("2.5"), Value::Number(Rational64::new(5, 2)));
+        assert_eq!(read("-1e2 ; comment"), Value::from(-100));
+        assert_eq!(read("#_ 1 [true false]"), read("[1 0]"));
+        assert_eq!(read(r#"\a"#), Value::String(String::from("a")));
+        assert_eq!(read(r#""A\t""#), Value::String(String::from("A\\t")));
+
+        assert!(Value::from_edn("[1 2").is_err());
+        assert!(Value::from_edn("{1}").is_err());
+        assert!(Value::from_edn("#inst \"2020-01-01\"").is_err());
+        assert!(Value::from_edn("").is_err());
+    }
+
+    #[test]
+    fn test_write() {
+        let value = Value::Vector(vec![
+            Value::String(String::from("q\\\"")),
+            Value::Nil,
+            Value::List([Value::from(1)].into_iter().collect()),
+        ]);
+        assert_eq!(value.to_edn(), Ok(String::from(r#"["q\"" nil (1)]"#)));
+        assert_eq!(Value::Lambda(0, 0).to_edn(), Err("a function"));
+    }
+}
@@ -1,5 +1,9 @@
 use std::fmt::{self, Display, Formatter};
 
+use smol_str::SmolStr;
+
+use crate::compiler::CompilationError;
+
 pub type RuntimeResult<T> = Result<T, RuntimeError>;
 
 /// Represents the different errors that can happen during runtime
@@ -10,12 +14,31 @@ pub enum RuntimeError {
     /// encountered by the user if the compiler has a bug or
     /// if the bytecode was modified
     CompilerError(String),
+    /// Returned by `eval` when the expression couldn't be compiled
+    CompilationError(CompilationError),
+    /// Returned when the execution was stopped through
+    /// the handle returned by `VMState::cancel_handle`
+    Cancelled,
+    /// Returned by the callables registered by applications
+    /// that embed the language, describing what went wrong
+    Custom(String),
+    /// Returned by the callable `exit` with the exit status
+    /// requested by the program, it stops the execution and
+    /// should be handled by the application that executes it
+    Exit(i32),
+    /// Returned when a value couldn't be converted to a Rust
+    /// type through `FromValue`, with the expected and the
+    /// received types
+    ConversionError(&'static str, &'static str),
     /// This variant is returned when a value that was passed
     /// to a parsing function (like `num` and `chr`) couldn't
     /// be correctly processed
     CouldntParse(String, &'static str),
     /// Returned when the user tries to divide a number by zero
     DivisionByZero,
+    /// Returned by `sort` when the function used to compare the
+    /// values didn't order them consistently
+    InconsistentComparator(&'static str),
     /// Returned when the user tries to get a value from
     /// an indexed collection using the callable `nth`
     /// and the collection is shorter than the index
@@ -24,6 +47,10 @@ pub enum RuntimeError {
     /// casted to a map entry, but the value isn't a vector
     /// with two elements
     InvalidMapEntry,
+    /// Returned when the program exceeded one of the
+    /// `Limits` of the virtual machine, described by
+    /// the resource and the maximum allowed
+    LimitExceeded(&'static str, u64),
     /// Returned when a input/output function returned an error
     /// instead of correctly printing/reading strings
     IOError(&'static str, std::io::Error),
@@ -34,24 +61,36 @@ pub enum RuntimeError {
     /// Returned when the user tried to call a callable
     /// with the wrong number of arguments, variant for functions
     /// with a specific arity
-    WrongArityN(&'static str, usize, usize),
+    WrongArityN(SmolStr, usize, usize),
     /// Returned when the user tried to call a callable
     /// with the wrong number of arguments, variant for functions
     /// that can be called with different numbers of arguments
-    WrongArityS(&'static str, &'static str, usize),
+    WrongArityS(SmolStr, SmolStr, usize),
     /// Returned when a callable receives a value with an incorrect
     /// datatype, that the callable didn't expect
-    WrongDataType(&'static str, &'static str, &'static str),
+    WrongDataType(SmolStr, &'static str, &'static str),
 }
 
 impl Display for RuntimeError {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
             RuntimeError::CompilerError(err) => write!(f, "Compiler error: {}", err),
+            RuntimeError::Cancelled => write!(f, "Execution cancelled"),
+            RuntimeError::CompilationError(err) => write!(f, "Compilation error: {}", err),
+            RuntimeError::ConversionError(expected, got) => {
+                write!(f, "Couldn't convert {} to {}", got, expected)
+            }
             RuntimeError::CouldntParse(string, expected) => {
                 write!(f, "Couldn't parse the value {} to {}", string, expected)
             }
+            RuntimeError::Custom(err) => write!(f, "{}", err),
+            RuntimeError::Exit(status) => write!(f, "Exited with status {}", status),
             RuntimeError::DivisionByZero => write!(f, "Division by zero"),
+            RuntimeError::InconsistentComparator(callable) => write!(
+                f,
+                "The comparator passed to {} doesn't define a consistent order",
+                callable
+            ),
             RuntimeError::IndexOutOfBounds(value_type) => {
                 write!(f, "Index of out bounds while indexing {}", value_type)
             }
@@ -62,6 +101,9 @@ impl Display for RuntimeError {
             RuntimeError::IOError(context, error) => {
                 write!(f, "Error trying to {}: {}", context, error)
             }
+            RuntimeError::LimitExceeded(resource, max) => {
+                write!(f, "Exceeded the limit of {} {}", max, resource)
+            }
             RuntimeError::NotACallable(value_type) => {
                 write!(f, "Couldn't execute {} as a callable", value_type)
             }
@@ -0,0 +1,133 @@
+use std::{cell::RefCell, collections::VecDeque, io, rc::Rc};
+
+/// Performs the input and output of the language callables, supplied
+/// by the application that executes the code through `VMState::set_io`
+pub trait IOHandler {
+    /// Writes the text printed by `print`, `println`, `pr` and `prn`
+    fn print(&mut self, text: &str) -> io::Result<()>;
+
+    /// Reads a line of input for `read`, without its line ending,
+    /// returning `None` at the end of the input. By default the
+    /// line is read from the standard input
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        let mut line = String::new();
+        if io::stdin().read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let len = line.trim_end_matches(['\n', '\r']).len();
+        line.truncate(len);
+        Ok(Some(line))
+    }
+
+    /// Reads the rest of the input for `read-all`, by
+    /// default joining the lines returned by `read_line`
+    fn read_all(&mut self) -> io::Result<String> {
+        let mut input = String::new();
+        while let Some(line) = self.read_line()? {
+            input.push_str(&line);
+            input.push('\n');
+        }
+        Ok(input)
+    }
+}
+
+/// Any writer can receive the printed text, like `std::io::stdout()`
+impl<W: io::Write> IOHandler for W {
+    fn print(&mut self, text: &str) -> io::Result<()> {
+        self.write_all(text.as_bytes())?;
+        self.flush()
+    }
+}
+
+/// Keeps the printed text in memory, and reads the input from a
+/// list of lines. Clones share their buffers, so a clone can be kept
+/// to read the output after passing the handler to the `VMState`
+#[derive(Debug, Clone, Default)]
+pub struct BufferIO {
+    output: Rc<RefCell<String>>,
+    input: Rc<RefCell<VecDeque<String>>>,
+}
+
+impl BufferIO {
+    /// Creates a handler whose input are the lines of `input`
+    pub fn new(input: &str) -> BufferIO {
+        BufferIO {
+            output: Rc::default(),
+            input: Rc::new(RefCell::new(input.lines().map(String::from).collect())),
+        }
+    }
+
+    /// Adds a line at the end of the input
+    pub fn push_input(&self, line: &str) {
+        self.input.borrow_mut().push_back(String::from(line));
+    }
+
+    pub fn output(&self) -> String {
+        self.output.borrow().clone()
+    }
+
+    /// Returns the text printed until now, clearing it
+    pub fn take_output(&self) -> String {
+        self.output.take()
+    }
+}
+
+impl IOHandler for BufferIO {
+    fn print(&mut self, text: &str) -> io::Result<()> {
+        self.output.borrow_mut().push_str(text);
+        Ok(())
+    }
+
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        Ok(self.input.borrow_mut().pop_front())
+    }
+}
+
+/// Appends the printed text to `window.minicljoutput`,
+/// and reads the input through `window.prompt`
+#[cfg(target_arch = "wasm32")]
+#[derive(Debug, Default)]
+pub struct BrowserIO;
+
+#[cfg(target_arch = "wasm32")]
+#[derive(serde::Serialize)]
+struct MinicljOutputWindow {
+    pub minicljoutput: String,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl IOHandler for BrowserIO {
+    fn print(&mut self, text: &str) -> io::Result<()> {
+        use js_sys::{JsString, Object};
+        use wasm_bindgen::prelude::*;
+
+        let window = web_sys::window().expect("not running in a browser environment");
+
+        let prev_output_obj = window
+            .get("minicljoutput")
+            .unwrap_or_else(|| JsString::from(String::new()).into());
+        let prev_output_jsstr = prev_output_obj.to_string();
+        let prev_output_string = String::from(prev_output_jsstr);
+        let minicljoutput = prev_output_string + text;
+        let output = MinicljOutputWindow { minicljoutput };
+
+        let output_obj = Object::from(JsValue::from_serde(&output).unwrap());
+        Object::assign(&window, &output_obj);
+        Ok(())
+    }
+
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        let window = web_sys::window().expect("not running in a browser environment");
+        window
+            .prompt_with_message("Input:")
+            .map_err(|_| io::Error::from(io::ErrorKind::Unsupported))
+    }
+}
+
+/// Returns the handler used when the application doesn't supply one
+pub(crate) fn default_io() -> Box<dyn IOHandler> {
+    #[cfg(not(target_arch = "wasm32"))]
+    return Box::new(io::stdout());
+    #[cfg(target_arch = "wasm32")]
+    return Box::new(BrowserIO);
+}
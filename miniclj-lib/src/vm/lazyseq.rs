@@ -0,0 +1,404 @@
+use std::{
+    cell::RefCell,
+    fmt::{self, Debug, Display, Formatter},
+    rc::Rc,
+};
+
+use crate::vm::{List, RuntimeError, RuntimeResult, VMState, Value};
+
+/// Lazy sequence type from Clojure. Its elements are computed
+/// by a `Thunk` only when they are needed, and cached after that
+#[derive(Clone)]
+pub struct LazySeq(Rc<RefCell<LazySeqState>>);
+
+enum LazySeqState {
+    Unrealized(Thunk),
+    Realizing,
+    Realized(Option<(Value, LazySeq)>),
+}
+
+/// Represents the pending computation of the first element
+/// (and the rest) of a `LazySeq`
+#[derive(Debug, Clone)]
+pub enum Thunk {
+    /// Calls a function with the captured values, and uses
+    /// its result (which should be a collection) as the sequence
+    Call(Value, Vec<Value>),
+    /// Applies a function to the previous element of the sequence
+    Iterate(Value, Value),
+    /// Repeats a value forever, or a number of times
+    Repeat(Value, Option<usize>),
+    /// Repeats the elements of a collection forever
+    Cycle(Rc<Vec<Value>>, usize),
+    /// Generates the numbers from a starting point, with a step,
+    /// until an optional stop value
+    Range(usize, Option<usize>, usize),
+    /// Applies a function to the elements of one or more sequences
+    Map(Value, Vec<LazySeq>),
+    /// Keeps the elements of a sequence that satisfy a predicate
+    Filter(Value, LazySeq),
+    /// Takes a number of elements from a sequence
+    Take(usize, LazySeq),
+    /// Takes elements from a sequence while they satisfy a predicate
+    TakeWhile(Value, LazySeq),
+    /// Passes the elements of a sequence through the reducing function
+    /// of a transducer, which collects the results of each element
+    /// into a vector
+    Sequence(Value, LazySeq),
+}
+
+impl Thunk {
+    fn step(&self, state: &VMState) -> RuntimeResult<Option<(Value, LazySeq)>> {
+        match self {
+            Thunk::Call(callable, captured) => {
+                let result = state.execute_callable(callable, captured.clone())?;
+                let seq = LazySeq::try_from(result).map_err(|type_str| {
+                    RuntimeError::WrongDataType("lazy-seq".into(), "a collection", type_str)
+                })?;
+                seq.force(state)
+            }
+            Thunk::Iterate(callable, prev) => {
+                let next = state.execute_callable(callable, vec![prev.clone()])?;
+                let rest = LazySeq::new(Thunk::Iterate(callable.clone(), next.clone()));
+                Ok(Some((next, rest)))
+            }
+            Thunk::Repeat(_, Some(0)) => Ok(None),
+            Thunk::Repeat(value, times) => {
+                let rest = LazySeq::new(Thunk::Repeat(value.clone(), times.map(|n| n - 1)));
+                Ok(Some((value.clone(), rest)))
+            }
+            Thunk::Cycle(values, idx) => match values.get(*idx) {
+                Some(value) => {
+                    let next_idx = (idx + 1) % values.len();
+                    let rest = LazySeq::new(Thunk::Cycle(values.clone(), next_idx));
+                    Ok(Some((value.clone(), rest)))
+                }
+                None => Ok(None),
+            },
+            Thunk::Range(next, Some(stop), _) if next >= stop => Ok(None),
+            Thunk::Range(next, stop, step) => {
+                let rest = LazySeq::new(Thunk::Range(next + step, *stop, *step));
+                Ok(Some((Value::from(*next as i64), rest)))
+            }
+            Thunk::Map(callable, seqs) => {
+                let mut args = Vec::new();
+                let mut rests = Vec::new();
+                for seq in seqs {
+                    match seq.force(state)? {
+                        Some((first, rest)) => {
+                            args.push(first);
+                            rests.push(rest);
+                        }
+                        None => return Ok(None),
+                    }
+                }
+                let result = state.execute_callable(callable, args)?;
+                let rest = LazySeq::new(Thunk::Map(callable.clone(), rests));
+                Ok(Some((result, rest)))
+            }
+            Thunk::Filter(predicate, seq) => {
+                let mut seq = seq.clone();
+                while let Some((first, rest)) = seq.force(state)? {
+                    if state
+                        .execute_callable(predicate, vec![first.clone()])?
+                        .is_truthy()
+                    {
+                        let rest = LazySeq::new(Thunk::Filter(predicate.clone(), rest));
+                        return Ok(Some((first, rest)));
+                    }
+                    seq = rest;
+                }
+                Ok(None)
+            }
+            Thunk::Take(0, _) => Ok(None),
+            Thunk::Take(n, seq) => Ok(seq
+                .force(state)?
+                .map(|(first, rest)| (first, LazySeq::new(Thunk::Take(n - 1, rest))))),
+            Thunk::TakeWhile(predicate, seq) => match seq.force(state)? {
+                Some((first, rest))
+                    if state
+                        .execute_callable(predicate, vec![first.clone()])?
+                        .is_truthy() =>
+                {
+                    let rest = LazySeq::new(Thunk::TakeWhile(predicate.clone(), rest));
+                    Ok(Some((first, rest)))
+                }
+                _ => Ok(None),
+            },
+            Thunk::Sequence(reducer, seq) => {
+                let mut seq = seq.clone();
+                while let Some((first, rest)) = seq.force(state)? {
+                    let results = match state
+                        .execute_callable(reducer, vec![Value::Vector(Vec::new()), first])?
+                    {
+                        Value::Vector(results) => results,
+                        value => {
+                            return Err(RuntimeError::WrongDataType(
+                                "sequence".into(),
+                                "a vector",
+                                value.type_str(),
+                            ))
+                        }
+                    };
+                    if !results.is_empty() {
+                        let rest = LazySeq::new(Thunk::Sequence(reducer.clone(), rest));
+                        return results
+                            .into_iter()
+                            .rev()
+                            .fold(rest, |rest, first| LazySeq::cons(first, rest))
+                            .force(state);
+                    }
+                    seq = rest;
+                }
+                Ok(None)
+            }
+        }
+    }
+}
+
+impl LazySeq {
+    pub fn new(thunk: Thunk) -> LazySeq {
+        LazySeq(Rc::new(RefCell::new(LazySeqState::Unrealized(thunk))))
+    }
+
+    pub fn empty() -> LazySeq {
+        LazySeq(Rc::new(RefCell::new(LazySeqState::Realized(None))))
+    }
+
+    pub fn cons(first: Value, rest: LazySeq) -> LazySeq {
+        LazySeq(Rc::new(RefCell::new(LazySeqState::Realized(Some((
+            first, rest,
+        ))))))
+    }
+
+    /// Returns the first element and the rest of the sequence,
+    /// or `None` if the sequence is empty. The first call
+    /// executes the thunk of the sequence, the next ones
+    /// return the cached result
+    pub fn force(&self, state: &VMState) -> RuntimeResult<Option<(Value, LazySeq)>> {
+        if let LazySeqState::Realized(result) = &*self.0.borrow() {
+            return Ok(result.clone());
+        }
+
+        let thunk = match self.0.replace(LazySeqState::Realizing) {
+            LazySeqState::Unrealized(thunk) => thunk,
+            _ => {
+                return Err(RuntimeError::CompilerError(String::from(
+                    "Lazy sequence depends on itself to be realized",
+                )))
+            }
+        };
+
+        match thunk.step(state) {
+            Ok(result) => {
+                self.0.replace(LazySeqState::Realized(result.clone()));
+                Ok(result)
+            }
+            Err(err) => {
+                self.0.replace(LazySeqState::Unrealized(thunk));
+                Err(err)
+            }
+        }
+    }
+
+    /// Realizes every element of the sequence and collects them into a vector.
+    /// Doesn't return if the sequence is infinite
+    pub fn realize(&self, state: &VMState) -> RuntimeResult<Vec<Value>> {
+        let mut values = Vec::new();
+        let mut seq = self.clone();
+        while let Some((first, rest)) = seq.force(state)? {
+            values.push(first);
+            seq = rest;
+        }
+        Ok(values)
+    }
+
+    /// Returns the elements that were already realized, and
+    /// whether they are all the elements of the sequence
+    pub fn realized_values(&self) -> (Vec<Value>, bool) {
+        let mut values = Vec::new();
+        let mut seq = self.clone();
+        loop {
+            let next = match &*seq.0.borrow() {
+                LazySeqState::Realized(Some((first, rest))) => {
+                    values.push(first.clone());
+                    rest.clone()
+                }
+                LazySeqState::Realized(None) => return (values, true),
+                _ => return (values, false),
+            };
+            seq = next;
+        }
+    }
+
+    pub fn ptr_eq(&self, other: &LazySeq) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+
+    pub fn as_ptr(&self) -> usize {
+        Rc::as_ptr(&self.0) as usize
+    }
+
+    fn take_rest_if_unique(&mut self) -> Option<LazySeq> {
+        if Rc::strong_count(&self.0) != 1 {
+            return None;
+        }
+        match self.0.replace(LazySeqState::Realized(None)) {
+            LazySeqState::Realized(Some((_, rest))) => Some(rest),
+            _ => None,
+        }
+    }
+}
+
+impl Drop for LazySeq {
+    fn drop(&mut self) {
+        // Unlink long chains of realized elements one by one,
+        // instead of recursively dropping them
+        let mut next = self.take_rest_if_unique();
+        while let Some(mut seq) = next {
+            next = seq.take_rest_if_unique();
+        }
+    }
+}
+
+impl Debug for LazySeq {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl Display for LazySeq {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let (values, complete) = self.realized_values();
+        let mut strings = values
+            .iter()
+            .map(|v| format!("{}", v))
+            .collect::<Vec<String>>();
+        if !complete {
+            strings.push(String::from("..."));
+        }
+        write!(f, "({})", strings.join(" "))
+    }
+}
+
+impl From<List> for LazySeq {
+    fn from(list: List) -> LazySeq {
+        let values = list.into_iter().collect::<Vec<Value>>();
+        values
+            .into_iter()
+            .rev()
+            .fold(LazySeq::empty(), |rest, first| LazySeq::cons(first, rest))
+    }
+}
+
+impl TryFrom<Value> for LazySeq {
+    type Error = &'static str;
+
+    fn try_from(value: Value) -> Result<LazySeq, Self::Error> {
+        match value {
+            Value::LazySeq(seq) => Ok(seq),
+            Value::Nil => Ok(LazySeq::empty()),
+            _ => List::try_from(value).map(LazySeq::from),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::hash_map::DefaultHasher,
+        hash::{Hash, Hasher},
+    };
+
+    use super::*;
+    use crate::engine::Engine;
+
+    fn list(values: &[i64]) -> Value {
+        Value::List(values.iter().rev().map(|n| Value::from(*n)).collect())
+    }
+
+    #[test]
+    fn test_infinite_sequences() {
+        let mut engine = Engine::new();
+        let cases = [
+            ("(take 4 (range))", list(&[0, 1, 2, 3])),
+            ("(take 3 (iterate #(* % 2) 1))", list(&[1, 2, 4])),
+            ("(take 5 (cycle [1 2]))", list(&[1, 2, 1, 2, 1])),
+            ("(take 2 (repeat 7))", list(&[7, 7])),
+            ("(take-while #(< % 3) (range))", list(&[0, 1, 2])),
+            ("(range 1 6 2)", list(&[1, 3, 5])),
+            // Finite ranges are lazy too, only the elements needed are realized
+            (
+                "(first (filter #(> % 5) (range 1000000000)))",
+                Value::from(6),
+            ),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(engine.eval_str(source).unwrap(), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_realized_once() {
+        let mut engine = Engine::new();
+        let source = "(def calls (atom 0))
+            (def s (map (fn [x] (do (swap! calls + 1) x)) (range)))
+            (doall (take 3 s))";
+        assert_eq!(engine.eval_str(source).unwrap(), list(&[0, 1, 2]));
+        assert_eq!(
+            engine.eval_str("(doall (take 2 s))").unwrap(),
+            list(&[0, 1])
+        );
+        assert_eq!(engine.eval_str("@calls").unwrap(), Value::from(3));
+    }
+
+    #[test]
+    fn test_equality() {
+        let hash = |value: &Value| {
+            let mut hasher = DefaultHasher::new();
+            value.hash(&mut hasher);
+            hasher.finish()
+        };
+        let vm = VMState::default();
+        let seq = |n| {
+            Value::LazySeq(LazySeq::new(Thunk::Take(
+                n,
+                LazySeq::new(Thunk::Range(0, None, 1)),
+            )))
+        };
+
+        // The identity of a lazy sequence doesn't change when it's realized
+        let (s1, s2) = (seq(2), seq(2));
+        let hashes = (hash(&s1), hash(&s2));
+        assert_ne!(s1, s2);
+        for s in [&s1, &s2] {
+            LazySeq::try_from(s.clone()).unwrap().realize(&vm).unwrap();
+        }
+        assert_ne!(s1, s2);
+        assert_eq!(s1, s1.clone());
+        assert_eq!((hash(&s1), hash(&s2)), hashes);
+        // Realizing the values converts lazy sequences into lists
+        let realized = s1.realize(&vm).unwrap();
+        assert_eq!(realized, s2.realize(&vm).unwrap());
+        assert_eq!(hash(&realized), hash(&list(&[0, 1])));
+        assert!(realized < list(&[0, 2]));
+
+        let mut engine = Engine::new();
+        assert_eq!(
+            engine
+                .eval_str("(get {(take 2 (range)) 1} (take 2 (range)))")
+                .unwrap(),
+            Value::from(1)
+        );
+        assert_eq!(
+            engine
+                .eval_str("(count (conj #{(take 2 (range))} (take 2 (range)) '(0 1)))")
+                .unwrap(),
+            Value::from(1)
+        );
+        let value = engine.eval_str("(= (range 2) '(0 1))");
+        assert_eq!(value.unwrap(), Value::from(true));
+        let value = engine.eval_str("(sort [(range 1 3) (range 2)])");
+        assert_eq!(value.unwrap().to_string(), "((0 1) (1 2))");
+    }
+}
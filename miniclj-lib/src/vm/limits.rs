@@ -0,0 +1,189 @@
+use std::{
+    cell::Cell,
+    time::{Duration, Instant},
+};
+
+use crate::vm::{RuntimeError, RuntimeResult, Value};
+
+/// How often the deadline is checked, in executed instructions,
+/// so that the clock isn't read before every instruction
+const DEADLINE_CHECK_INTERVAL: u64 = 1024;
+
+/// Bounds to the resources used by a program, every limit
+/// set to `None` is disabled
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Limits {
+    /// Maximum number of instructions executed
+    pub max_instructions: Option<u64>,
+    /// Maximum number of user defined functions being
+    /// executed at the same time
+    pub max_call_depth: Option<usize>,
+    /// Maximum number of elements in a collection, or
+    /// of bytes in a string, returned by a function
+    pub max_collection_size: Option<usize>,
+    /// Maximum time spent executing the program. The clock isn't
+    /// available in every platform, like in WebAssembly, where the
+    /// other limits should be used instead
+    pub timeout: Option<Duration>,
+}
+
+/// Keeps the resources used by a program and
+/// checks them against the `Limits`
+#[derive(Debug, Default)]
+pub(crate) struct LimitsState {
+    limits: Limits,
+    executed_instructions: Cell<u64>,
+    deadline: Option<Instant>,
+}
+
+impl LimitsState {
+    pub fn new(limits: Limits) -> LimitsState {
+        LimitsState {
+            limits,
+            executed_instructions: Cell::new(0),
+            deadline: limits.timeout.map(|timeout| Instant::now() + timeout),
+        }
+    }
+
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    /// Called by the virtual machine before executing every instruction
+    pub fn count_instruction(&self) -> RuntimeResult<()> {
+        let executed_instructions = self.executed_instructions.get() + 1;
+        self.executed_instructions.set(executed_instructions);
+        if let Some(max_instructions) = self.limits.max_instructions {
+            if executed_instructions > max_instructions {
+                return Err(RuntimeError::LimitExceeded(
+                    "executed instructions",
+                    max_instructions,
+                ));
+            }
+        }
+        if let (Some(deadline), Some(timeout)) = (self.deadline, self.limits.timeout) {
+            if executed_instructions % DEADLINE_CHECK_INTERVAL == 0 && Instant::now() > deadline {
+                return Err(RuntimeError::LimitExceeded(
+                    "milliseconds of execution",
+                    timeout.as_millis() as u64,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Called by the virtual machine before entering a user defined
+    /// function, with the number of functions already being executed
+    pub fn check_call_depth(&self, depth: usize) -> RuntimeResult<()> {
+        match self.limits.max_call_depth {
+            Some(max_call_depth) if depth >= max_call_depth => Err(RuntimeError::LimitExceeded(
+                "nested calls",
+                max_call_depth as u64,
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Called by the virtual machine with the values returned by functions
+    pub fn check_size(&self, value: &Value) -> RuntimeResult<()> {
+        let max_size = match self.limits.max_collection_size {
+            Some(max_size) => max_size,
+            None => return Ok(()),
+        };
+        let size = match value {
+            // Lists don't know their length, so they're
+            // traversed only until they exceed the limit
+            Value::List(list) => list.iter().take(max_size + 1).count(),
+            Value::Vector(vector) => vector.len(),
+            Value::Set(set) => set.len(),
+            Value::Map(map) => map.len(),
+            Value::String(string) => string.len(),
+            _ => 0,
+        };
+        if size > max_size {
+            Err(RuntimeError::LimitExceeded(
+                "elements in a collection",
+                max_size as u64,
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{compiler::CompilerState, parsers::SExprsParser, vm::VMState};
+
+    fn execute_with(limits: Limits, source: &str) -> RuntimeResult<()> {
+        let mut compiler_state = CompilerState::default();
+        for expr in SExprsParser::parse(source).unwrap() {
+            compiler_state.compile(expr).unwrap();
+        }
+        let (constants_rev, instructions) = compiler_state.into_parts();
+        let constants = constants_rev
+            .into_iter()
+            .map(|(constant, address)| (address, constant))
+            .collect();
+        let mut vm_state = VMState::new(constants, instructions);
+        vm_state.set_limits(limits);
+        vm_state.execute()
+    }
+
+    fn exceeded(result: RuntimeResult<()>) -> Option<(&'static str, u64)> {
+        match result {
+            Err(RuntimeError::LimitExceeded(resource, max)) => Some((resource, max)),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_limits() {
+        let infinite_loop = "(loop [i 0] (recur (+ i 1)))";
+        let limits = Limits {
+            max_instructions: Some(1000),
+            ..Limits::default()
+        };
+        assert_eq!(
+            exceeded(execute_with(limits, infinite_loop)),
+            Some(("executed instructions", 1000))
+        );
+        assert!(execute_with(limits, "(+ 1 2)").is_ok());
+
+        let countdown = "(defn f [n] (if (= n 0) 0 (f (- n 1))))";
+        let limits = Limits {
+            max_call_depth: Some(50),
+            ..Limits::default()
+        };
+        assert_eq!(
+            exceeded(execute_with(limits, &format!("{} (f 100)", countdown))),
+            Some(("nested calls", 50))
+        );
+        assert!(execute_with(limits, &format!("{} (f 10)", countdown)).is_ok());
+
+        let limits = Limits {
+            max_collection_size: Some(10),
+            ..Limits::default()
+        };
+        assert_eq!(
+            exceeded(execute_with(limits, "(into [] (range 20))")),
+            Some(("elements in a collection", 10))
+        );
+        assert_eq!(
+            exceeded(execute_with(limits, "(str \"abcdef\" \"ghijkl\")")),
+            Some(("elements in a collection", 10))
+        );
+        assert!(execute_with(limits, "(into [] (range 10))").is_ok());
+
+        let limits = Limits {
+            timeout: Some(Duration::from_millis(20)),
+            ..Limits::default()
+        };
+        let err = execute_with(limits, infinite_loop).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Exceeded the limit of 20 milliseconds of execution"
+        );
+    }
+}
@@ -37,6 +37,10 @@ impl List {
         len
     }
 
+    pub fn iter(&self) -> ListIter<'_> {
+        ListIter(self)
+    }
+
     fn inner_display(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
             List::Cons(first, rest) => {
@@ -89,6 +93,49 @@ impl Hash for List {
     }
 }
 
+/// Iterator over the references of the values of a `List`
+pub struct ListIter<'a>(&'a List);
+
+impl<'a> Iterator for ListIter<'a> {
+    type Item = &'a Value;
+
+    fn next(&mut self) -> Option<&'a Value> {
+        match self.0 {
+            List::Cons(first, rest) => {
+                self.0 = rest;
+                Some(first)
+            }
+            List::EmptyList => None,
+        }
+    }
+}
+
+/// Iterator over the values of a `List`, consuming it
+pub struct ListIntoIter(List);
+
+impl Iterator for ListIntoIter {
+    type Item = Value;
+
+    fn next(&mut self) -> Option<Value> {
+        match std::mem::replace(&mut self.0, List::EmptyList) {
+            List::Cons(first, rest) => {
+                self.0 = *rest;
+                Some(*first)
+            }
+            List::EmptyList => None,
+        }
+    }
+}
+
+impl IntoIterator for List {
+    type Item = Value;
+    type IntoIter = ListIntoIter;
+
+    fn into_iter(self) -> ListIntoIter {
+        ListIntoIter(self)
+    }
+}
+
 impl FromIterator<Value> for List {
     fn from_iter<T: IntoIterator<Item = Value>>(iter: T) -> List {
         let mut list = List::EmptyList;
@@ -106,9 +153,10 @@ impl TryFrom<Value> for List {
         match value {
             Value::List(list) => Ok(list),
             Value::Vector(vector) => Ok(vector.into_iter().rev().collect()),
-            Value::Set(set) => Ok(set.into_iter().collect()),
+            Value::Set(set) => Ok(set.into_iter().rev().collect()),
             Value::Map(map) => Ok(map
                 .into_iter()
+                .rev()
                 .map(|(key, val)| Value::Vector(vec![key, val]))
                 .collect()),
             Value::String(string) => Ok(string
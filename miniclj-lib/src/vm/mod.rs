@@ -1,11 +1,36 @@
+pub mod atom;
+pub mod collections;
+pub mod conversion;
+pub mod debugger;
+pub mod edn;
 pub mod error;
+pub mod io;
+pub mod lazyseq;
+pub mod limits;
 pub mod list;
+pub mod permissions;
+pub mod profiler;
 pub mod scope;
 pub mod state;
+pub mod testreport;
+pub mod trace;
 pub mod value;
 
+pub use atom::Atom;
+pub use collections::{ValueMap, ValueSet};
+pub use conversion::{FromValue, IntoValue};
+pub use debugger::{DebugCommand, DebugHandler, Debugger, Frame, PauseReason, PausedState};
 pub use error::{RuntimeError, RuntimeResult};
+#[cfg(target_arch = "wasm32")]
+pub use io::BrowserIO;
+pub use io::{BufferIO, IOHandler};
+pub use lazyseq::{LazySeq, Thunk};
+pub use limits::Limits;
 pub use list::List;
+pub use permissions::{EnvPermissions, FsPermissions};
+pub use profiler::{ProfileFrame, ProfileStats, Profiler};
 pub use scope::Scope;
-pub use state::VMState;
+pub use state::{RunStatus, VMState};
+pub use testreport::{Assertion, TestReport};
+pub use trace::Tracer;
 pub use value::Value;
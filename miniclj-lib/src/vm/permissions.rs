@@ -0,0 +1,142 @@
+use std::{
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+/// The directories whose files can be accessed by the file system
+/// callables, like `slurp` and `spit`. By default no file can be
+/// accessed, so that untrusted code can be executed safely
+#[derive(Debug, Clone, Default)]
+pub struct FsPermissions {
+    unrestricted: bool,
+    read: Vec<PathBuf>,
+    write: Vec<PathBuf>,
+}
+
+impl FsPermissions {
+    /// Permissions that allow reading and writing every file
+    pub fn unrestricted() -> FsPermissions {
+        FsPermissions {
+            unrestricted: true,
+            ..FsPermissions::default()
+        }
+    }
+
+    /// Allows reading the files inside a directory and its subdirectories
+    pub fn allow_read<P: AsRef<Path>>(&mut self, dir: P) {
+        self.read.push(resolve(dir.as_ref()));
+    }
+
+    /// Allows creating, writing and deleting the files inside
+    /// a directory and its subdirectories
+    pub fn allow_write<P: AsRef<Path>>(&mut self, dir: P) {
+        self.write.push(resolve(dir.as_ref()));
+    }
+
+    /// Returns the absolute path of a file that can be read,
+    /// or a `PermissionDenied` error
+    pub fn check_read<P: AsRef<Path>>(&self, path: P) -> io::Result<PathBuf> {
+        self.check(path.as_ref(), &self.read, "read")
+    }
+
+    /// Returns the absolute path of a file that can be written,
+    /// or a `PermissionDenied` error
+    pub fn check_write<P: AsRef<Path>>(&self, path: P) -> io::Result<PathBuf> {
+        self.check(path.as_ref(), &self.write, "written")
+    }
+
+    fn check(&self, path: &Path, allowed: &[PathBuf], action: &str) -> io::Result<PathBuf> {
+        let resolved = resolve(path);
+        if self.unrestricted || allowed.iter().any(|dir| resolved.starts_with(dir)) {
+            Ok(resolved)
+        } else {
+            Err(io::Error::new(
+                ErrorKind::PermissionDenied,
+                format!("{} isn't allowed to be {}", path.display(), action),
+            ))
+        }
+    }
+}
+
+/// The environment variables that can be read by `getenv`. By default
+/// none of them can, so that untrusted code can't read the secrets
+/// usually stored in them
+#[derive(Debug, Clone, Default)]
+pub struct EnvPermissions {
+    unrestricted: bool,
+    allowed: Vec<String>,
+}
+
+impl EnvPermissions {
+    /// Permissions that allow reading every environment variable
+    pub fn unrestricted() -> EnvPermissions {
+        EnvPermissions {
+            unrestricted: true,
+            ..EnvPermissions::default()
+        }
+    }
+
+    /// Allows reading an environment variable
+    pub fn allow(&mut self, name: &str) {
+        self.allowed.push(String::from(name));
+    }
+
+    /// Returns a `PermissionDenied` error if the variable can't be read
+    pub fn check(&self, name: &str) -> io::Result<()> {
+        if self.unrestricted || self.allowed.iter().any(|allowed| allowed == name) {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                ErrorKind::PermissionDenied,
+                format!("the environment variable {} isn't allowed to be read", name),
+            ))
+        }
+    }
+}
+
+/// Makes a path absolute, following its symbolic links so that they
+/// can't point outside of the allowed directories. Files that don't
+/// exist yet are resolved through their parent directory
+fn resolve(path: &Path) -> PathBuf {
+    if let Ok(resolved) = path.canonicalize() {
+        return resolved;
+    }
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    match (parent.canonicalize(), path.file_name()) {
+        (Ok(parent), Some(file_name)) => parent.join(file_name),
+        _ => std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_permissions() {
+        let dir = std::env::temp_dir().join("miniclj-permissions-test");
+        std::fs::create_dir_all(dir.join("inner")).unwrap();
+
+        let mut permissions = FsPermissions::default();
+        assert!(permissions.check_read(&dir).is_err());
+        permissions.allow_read(&dir);
+        assert!(permissions.check_read(dir.join("inner/new.txt")).is_ok());
+        assert!(permissions
+            .check_read(dir.join("inner/../../other"))
+            .is_err());
+        assert!(permissions.check_write(dir.join("file.txt")).is_err());
+        permissions.allow_write(dir.join("inner"));
+        assert!(permissions.check_write(dir.join("inner/file.txt")).is_ok());
+        assert!(FsPermissions::unrestricted().check_write("/").is_ok());
+
+        let mut permissions = EnvPermissions::default();
+        assert!(permissions.check("HOME").is_err());
+        permissions.allow("HOME");
+        assert!(permissions.check("HOME").is_ok());
+        assert!(permissions.check("PATH").is_err());
+        assert!(EnvPermissions::unrestricted().check("PATH").is_ok());
+    }
+}
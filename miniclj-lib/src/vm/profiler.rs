@@ -0,0 +1,258 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt::Write,
+    time::{Duration, Instant},
+};
+
+use smol_str::SmolStr;
+
+use crate::{compiler::DebugInfo, instruction::InstructionPtr};
+
+/// A function that can be measured by the profiler
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ProfileFrame {
+    /// The instructions executed outside of any function
+    TopLevel,
+    /// A user defined function, identified by its first instruction
+    Lambda(InstructionPtr),
+    /// A callable exposed by the language
+    Callable(SmolStr),
+}
+
+impl ProfileFrame {
+    fn name(&self, debug_info: Option<&DebugInfo>) -> String {
+        match self {
+            ProfileFrame::TopLevel => String::from("<top level>"),
+            ProfileFrame::Lambda(entry_ptr) => debug_info
+                .and_then(|debug_info| debug_info.function_name(*entry_ptr))
+                .map_or_else(|| format!("fn@{}", entry_ptr), String::from),
+            ProfileFrame::Callable(name) => name.to_string(),
+        }
+    }
+}
+
+/// The measurements of a function
+#[derive(Debug, Clone, Default)]
+pub struct ProfileStats {
+    pub calls: u64,
+    /// The instructions executed directly by the function
+    pub instructions: u64,
+    /// The time spent in the function, including the functions called by it
+    pub inclusive_time: Duration,
+    /// The time spent in the function, excluding the functions called by it
+    pub exclusive_time: Duration,
+}
+
+struct StackEntry {
+    frame: ProfileFrame,
+    start: Instant,
+    children_time: Duration,
+}
+
+/// Counts the instructions executed and measures the time spent in
+/// every function, and in every stack of calls that led to them
+pub struct Profiler {
+    stats: RefCell<HashMap<ProfileFrame, ProfileStats>>,
+    stacks: RefCell<HashMap<Vec<ProfileFrame>, Duration>>,
+    stack: RefCell<Vec<StackEntry>>,
+}
+
+impl Default for Profiler {
+    fn default() -> Profiler {
+        let profiler = Profiler {
+            stats: RefCell::new(HashMap::new()),
+            stacks: RefCell::new(HashMap::new()),
+            stack: RefCell::new(Vec::new()),
+        };
+        profiler.enter(ProfileFrame::TopLevel);
+        profiler
+    }
+}
+
+impl Profiler {
+    pub fn enter(&self, frame: ProfileFrame) {
+        self.stats
+            .borrow_mut()
+            .entry(frame.clone())
+            .or_default()
+            .calls += 1;
+        self.stack.borrow_mut().push(StackEntry {
+            frame,
+            start: Instant::now(),
+            children_time: Duration::ZERO,
+        });
+    }
+
+    pub fn exit(&self) {
+        let mut stack = self.stack.borrow_mut();
+        let entry = match stack.pop() {
+            Some(entry) => entry,
+            None => return,
+        };
+        let elapsed = entry.start.elapsed();
+        let exclusive_time = elapsed.saturating_sub(entry.children_time);
+
+        let mut stats = self.stats.borrow_mut();
+        let frame_stats = stats.entry(entry.frame.clone()).or_default();
+        frame_stats.exclusive_time += exclusive_time;
+        // Recursive calls are already included in the time of the outer call
+        if stack.iter().all(|outer| outer.frame != entry.frame) {
+            frame_stats.inclusive_time += elapsed;
+        }
+
+        let mut path = stack
+            .iter()
+            .map(|outer| outer.frame.clone())
+            .collect::<Vec<_>>();
+        path.push(entry.frame);
+        *self.stacks.borrow_mut().entry(path).or_default() += exclusive_time;
+
+        if let Some(parent) = stack.last_mut() {
+            parent.children_time += elapsed;
+        }
+    }
+
+    /// Counts an instruction executed by the current function
+    pub fn count_instruction(&self) {
+        if let Some(entry) = self.stack.borrow().last() {
+            self.stats
+                .borrow_mut()
+                .entry(entry.frame.clone())
+                .or_default()
+                .instructions += 1;
+        }
+    }
+
+    /// Stops measuring the functions that didn't return,
+    /// including the top level
+    pub fn finish(&self) {
+        while !self.stack.borrow().is_empty() {
+            self.exit();
+        }
+    }
+
+    /// Returns the measurements of every function, sorted
+    /// by the time spent in them, excluding their calls
+    pub fn stats(&self) -> Vec<(ProfileFrame, ProfileStats)> {
+        let mut stats = self
+            .stats
+            .borrow()
+            .iter()
+            .map(|(frame, stats)| (frame.clone(), stats.clone()))
+            .collect::<Vec<_>>();
+        stats.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.exclusive_time));
+        stats
+    }
+
+    /// Returns a table with the measurements of every function,
+    /// the names of the user defined functions are read from `debug_info`
+    pub fn report(&self, debug_info: Option<&DebugInfo>) -> String {
+        let mut report = format!(
+            "{:>10} {:>14} {:>14} {:>14}  function\n",
+            "calls", "instructions", "inclusive ms", "exclusive ms"
+        );
+        for (frame, stats) in self.stats() {
+            let _ = writeln!(
+                report,
+                "{:>10} {:>14} {:>14.3} {:>14.3}  {}",
+                stats.calls,
+                stats.instructions,
+                stats.inclusive_time.as_secs_f64() * 1000.0,
+                stats.exclusive_time.as_secs_f64() * 1000.0,
+                frame.name(debug_info)
+            );
+        }
+        report
+    }
+
+    /// Returns the stacks of calls in the folded format read by
+    /// flamegraph tools, one stack per line followed by the
+    /// microseconds spent in its innermost function
+    pub fn folded_stacks(&self, debug_info: Option<&DebugInfo>) -> String {
+        let mut stacks = self
+            .stacks
+            .borrow()
+            .iter()
+            .map(|(path, time)| {
+                let path = path
+                    .iter()
+                    .map(|frame| frame.name(debug_info))
+                    .collect::<Vec<String>>()
+                    .join(";");
+                (path, time.as_micros())
+            })
+            .collect::<Vec<_>>();
+        stacks.sort();
+        stacks
+            .into_iter()
+            .map(|(path, micros)| format!("{} {}\n", path, micros))
+            .collect()
+    }
+}
+
+impl std::fmt::Debug for Profiler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Profiler({} functions)", self.stats.borrow().len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{compiler::CompilerState, parsers::SExprsParser, vm::VMState};
+
+    #[test]
+    fn test_counts() {
+        let source = "(defn fact [n] (if (<= n 1) 1 (* n (fact (- n 1))))) (fact 5)";
+        let mut compiler_state = CompilerState::default();
+        for expr in SExprsParser::parse(source).unwrap() {
+            compiler_state.compile(expr).unwrap();
+        }
+        let debug_info = compiler_state.debug_info(source);
+        let (constants_rev, instructions) = compiler_state.into_parts();
+        let constants = constants_rev
+            .into_iter()
+            .map(|(constant, address)| (address, constant))
+            .collect();
+        let mut vm_state = VMState::new(constants, instructions);
+        vm_state.set_profiler(Some(Profiler::default()));
+        vm_state.execute().unwrap();
+        let profiler = vm_state.profiler().unwrap();
+        profiler.finish();
+
+        let stats = profiler
+            .stats()
+            .into_iter()
+            .map(|(frame, stats)| (frame.name(Some(&debug_info)), stats))
+            .collect::<HashMap<_, _>>();
+        let counts = |name: &str| (stats[name].calls, stats[name].instructions);
+        assert_eq!(stats.len(), 6);
+        assert_eq!(counts("<top level>"), (1, 3));
+        // Four calls execute 8 instructions, and the last one 6
+        assert_eq!(counts("fact"), (5, 38));
+        assert_eq!(counts("<="), (5, 0));
+        assert_eq!(counts("true?"), (5, 0));
+        assert_eq!(counts("-"), (4, 0));
+        assert_eq!(counts("*"), (4, 0));
+        // The recursive calls are only measured once
+        assert!(stats["fact"].inclusive_time <= stats["<top level>"].inclusive_time);
+        assert!(stats["fact"].exclusive_time <= stats["fact"].inclusive_time);
+
+        let folded_stacks = profiler.folded_stacks(Some(&debug_info));
+        let paths = folded_stacks
+            .lines()
+            .map(|line| line.rsplit_once(' ').unwrap().0)
+            .collect::<Vec<_>>();
+        assert_eq!(paths.len(), 24);
+        assert_eq!(paths[0], "<top level>");
+        assert!(paths.contains(&"<top level>;fact;fact;fact;fact;fact;<="));
+        assert!(!paths.contains(&"<top level>;fact;fact;fact;fact;fact;*"));
+
+        let report = profiler.report(Some(&debug_info));
+        assert_eq!(report.lines().count(), 7);
+        assert!(report.lines().any(
+            |line| line.ends_with("  fact") && line.split_whitespace().take(2).eq(["5", "38"])
+        ));
+    }
+}
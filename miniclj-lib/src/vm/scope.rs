@@ -35,6 +35,16 @@ impl Scope {
             })
     }
 
+    /// Returns the variables stored in the scope with their indexes
+    pub fn vars(&self) -> Vec<(usize, Value)> {
+        stored_values(&self.vars)
+    }
+
+    /// Returns the temporal values stored in the scope with their indexes
+    pub fn temps(&self) -> Vec<(usize, Value)> {
+        stored_values(&self.temps)
+    }
+
     pub fn store_var(&self, index: usize, value: Value) {
         inner_store(&self.vars, index, value);
     }
@@ -51,3 +61,12 @@ fn inner_store(table: &ValuesTable, index: usize, value: Value) {
     }
     let _ = table.borrow_mut().get_mut(index).unwrap().insert(value);
 }
+
+fn stored_values(table: &ValuesTable) -> Vec<(usize, Value)> {
+    table
+        .borrow()
+        .iter()
+        .enumerate()
+        .filter_map(|(index, value)| Some((index, value.clone()?)))
+        .collect()
+}
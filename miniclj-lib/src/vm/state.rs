@@ -1,18 +1,154 @@
-use std::collections::HashMap;
+use std::{
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    fmt::{self, Debug, Formatter},
+    io,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use smol_str::SmolStr;
 
 use crate::{
+    compiler::{CompilerState, SExpr},
     constant::Constant,
     instruction::{Instruction, InstructionPtr},
     memaddress::{Lifetime, MemAddress},
-    vm::{RuntimeError, RuntimeResult, Scope, Value},
+    vm::{
+        io::default_io, limits::LimitsState, trace::TraceEntry, Debugger, EnvPermissions,
+        FsPermissions, IOHandler, Limits, ProfileFrame, Profiler, RuntimeError, RuntimeResult,
+        Scope, TestReport, Tracer, Value,
+    },
 };
 
 /// Structure used to execute the bytecode produced by the compiler
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct VMState {
     constants: HashMap<MemAddress, Constant>,
     instructions: Vec<Instruction>,
+    /// The compiler used by `eval`, with the symbols of the program
+    compiler: RefCell<Option<CompilerState>>,
+    /// The code compiled by `eval` during the execution
+    evaluated: RefCell<EvaluatedCode>,
     global_scope: Scope,
+    io: IO,
+    fs_permissions: FsPermissions,
+    env_permissions: EnvPermissions,
+    test_report: TestReport,
+    /// The tools that observe or bound the execution, `None` when
+    /// all of them are disabled, so that the interpreter checks
+    /// a single value before every instruction
+    hooks: Option<Box<Hooks>>,
+    /// The first instruction of each function being executed
+    call_stack: RefCell<Vec<InstructionPtr>>,
+    executed_instructions: Cell<u64>,
+    cancel: Arc<AtomicBool>,
+    slice: RefCell<SliceState>,
+}
+
+/// How often the cancel flag is read, in executed instructions, so that
+/// it isn't accessed before every instruction. Must be a power of two
+const CANCEL_CHECK_INTERVAL: u64 = 1024;
+
+/// The optional tools called before every instruction
+#[derive(Debug, Default)]
+struct Hooks {
+    tracer: Option<Tracer>,
+    debugger: Option<Debugger>,
+    profiler: Option<Profiler>,
+    limits: LimitsState,
+}
+
+impl Hooks {
+    fn is_empty(&self) -> bool {
+        let limits = self.limits.limits();
+        self.tracer.is_none()
+            && self.debugger.is_none()
+            && self.profiler.is_none()
+            && limits.max_instructions.is_none()
+            && limits.max_call_depth.is_none()
+            && limits.max_collection_size.is_none()
+            && limits.timeout.is_none()
+    }
+}
+
+/// The state in which the execution of a slice ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunStatus {
+    /// The program executed all of its instructions
+    Finished,
+    /// The program reached the instructions limit of the slice,
+    /// and will continue in the next call to `run_for`
+    Yielded,
+}
+
+/// Progress of the execution in slices started by `run_for`
+#[derive(Debug, Default)]
+enum SliceState {
+    #[default]
+    NotStarted,
+    Suspended(Continuation),
+    Finished,
+}
+
+/// The point where an execution continues
+#[derive(Debug, Default)]
+struct Continuation {
+    instruction_ptr: InstructionPtr,
+    /// The user defined functions being executed, the innermost one last
+    frames: Vec<CallFrame>,
+}
+
+/// A user defined function called from the bytecode
+#[derive(Debug)]
+struct CallFrame {
+    scope: Scope,
+    /// The instruction of the caller executed after returning
+    return_ptr: InstructionPtr,
+    /// The address of the caller where the returned value is stored
+    result_addr: MemAddress,
+    /// The trace of the call, completed with the returned value
+    trace_entry: Option<TraceEntry>,
+}
+
+/// How the execution continues after an instruction
+enum Action {
+    Jump(InstructionPtr),
+    Call(CallFrame, InstructionPtr),
+    Return(MemAddress),
+}
+
+/// The reason why a continuation stopped executing
+enum Exit {
+    Returned(MemAddress),
+    Finished,
+    Yielded,
+}
+
+/// The constants and instructions compiled while the program runs,
+/// which are appended to the program once it stops
+#[derive(Debug, Default)]
+struct EvaluatedCode {
+    constants: HashMap<MemAddress, Constant>,
+    instructions: Vec<Instruction>,
+}
+
+/// The handler of the input and output of the language callables
+struct IO(RefCell<Box<dyn IOHandler>>);
+
+impl Default for IO {
+    fn default() -> IO {
+        IO(RefCell::new(default_io()))
+    }
+}
+
+impl Debug for IO {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "IO")
+    }
 }
 
 impl VMState {
@@ -23,12 +159,182 @@ impl VMState {
         VMState {
             constants,
             instructions,
+            compiler: RefCell::new(None),
+            evaluated: RefCell::new(EvaluatedCode::default()),
             global_scope: Scope::default(),
+            io: IO::default(),
+            fs_permissions: FsPermissions::default(),
+            env_permissions: EnvPermissions::default(),
+            test_report: TestReport::default(),
+            hooks: None,
+            call_stack: RefCell::new(Vec::new()),
+            executed_instructions: Cell::new(0),
+            cancel: Arc::new(AtomicBool::new(false)),
+            slice: RefCell::new(SliceState::NotStarted),
         }
     }
 
+    /// Creates a virtual machine that executes the code compiled until
+    /// now, keeping the compiler to compile the forms passed to `eval`
+    pub fn from_compiler(compiler_state: CompilerState) -> VMState {
+        let (constants, instructions) = compiler_state.parts_since(0, 0);
+        let vm_state = VMState::new(constants, instructions);
+        vm_state.set_compiler(Some(compiler_state));
+        vm_state
+    }
+
+    /// Replaces the compiler used by `eval`, returning the previous one.
+    /// It should be the compiler that produced the code being executed,
+    /// without it `eval` returns an error
+    pub fn set_compiler(&self, compiler: Option<CompilerState>) -> Option<CompilerState> {
+        self.compiler.replace(compiler)
+    }
+
+    /// Replaces the handler of the input and output of the language
+    /// callables, returning the previous one. By default the standard
+    /// input and output are used
+    pub fn set_io(&self, io: Box<dyn IOHandler>) -> Box<dyn IOHandler> {
+        self.io.0.replace(io)
+    }
+
+    /// Prints text through the input and output handler
+    pub fn print(&self, text: &str) -> io::Result<()> {
+        self.io.0.borrow_mut().print(text)
+    }
+
+    /// Reads a line through the input and output handler,
+    /// returning `None` at the end of the input
+    pub fn read_line(&self) -> io::Result<Option<String>> {
+        self.io.0.borrow_mut().read_line()
+    }
+
+    /// Reads the rest of the input through the input and output handler
+    pub fn read_all(&self) -> io::Result<String> {
+        self.io.0.borrow_mut().read_all()
+    }
+
+    /// Replaces the permissions of the file system callables,
+    /// by default they can't access any file
+    pub fn set_fs_permissions(&mut self, fs_permissions: FsPermissions) {
+        self.fs_permissions = fs_permissions;
+    }
+
+    pub fn fs_permissions(&self) -> &FsPermissions {
+        &self.fs_permissions
+    }
+
+    /// Replaces the permissions of `getenv`, by default
+    /// it can't read any environment variable
+    pub fn set_env_permissions(&mut self, env_permissions: EnvPermissions) {
+        self.env_permissions = env_permissions;
+    }
+
+    pub fn env_permissions(&self) -> &EnvPermissions {
+        &self.env_permissions
+    }
+
+    /// Modifies the hooks, removing them if all of them were disabled
+    fn update_hooks<F: FnOnce(&mut Hooks)>(&mut self, update: F) {
+        let mut hooks = self.hooks.take().unwrap_or_default();
+        update(&mut hooks);
+        if !hooks.is_empty() {
+            self.hooks = Some(hooks);
+        }
+    }
+
+    /// Replaces the tracer that logs the executed instructions,
+    /// `None` disables the tracing
+    pub fn set_tracer(&mut self, tracer: Option<Tracer>) {
+        self.update_hooks(|hooks| hooks.tracer = tracer);
+    }
+
+    fn tracer(&self) -> Option<&Tracer> {
+        self.hooks.as_ref().and_then(|hooks| hooks.tracer.as_ref())
+    }
+
+    /// Replaces the debugger that pauses the execution,
+    /// `None` disables the debugging
+    pub fn set_debugger(&mut self, debugger: Option<Debugger>) {
+        self.update_hooks(|hooks| hooks.debugger = debugger);
+    }
+
+    pub fn debugger(&self) -> Option<&Debugger> {
+        self.hooks
+            .as_ref()
+            .and_then(|hooks| hooks.debugger.as_ref())
+    }
+
+    /// Replaces the profiler that measures the executed functions,
+    /// `None` disables the profiling
+    pub fn set_profiler(&mut self, profiler: Option<Profiler>) {
+        self.update_hooks(|hooks| hooks.profiler = profiler);
+    }
+
+    pub fn profiler(&self) -> Option<&Profiler> {
+        self.hooks
+            .as_ref()
+            .and_then(|hooks| hooks.profiler.as_ref())
+    }
+
+    /// Replaces the limits to the resources used by the program,
+    /// the instructions and the time are counted from this call
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.update_hooks(|hooks| hooks.limits = LimitsState::new(limits));
+    }
+
+    pub fn limits(&self) -> Limits {
+        self.hooks
+            .as_ref()
+            .map(|hooks| hooks.limits.limits())
+            .unwrap_or_default()
+    }
+
+    /// Returns an instruction of the program, or one compiled by `eval`
+    pub(crate) fn instruction(
+        &self,
+        instruction_ptr: InstructionPtr,
+    ) -> Option<Cow<'_, Instruction>> {
+        match self.instructions.get(instruction_ptr) {
+            Some(instruction) => Some(Cow::Borrowed(instruction)),
+            None => self
+                .evaluated
+                .borrow()
+                .instructions
+                .get(instruction_ptr - self.instructions.len())
+                .cloned()
+                .map(Cow::Owned),
+        }
+    }
+
+    /// Returns the report where the test callables
+    /// store the results of their assertions
+    pub fn test_report(&self) -> &TestReport {
+        &self.test_report
+    }
+
     pub fn execute(&self) -> RuntimeResult<()> {
-        match self.inner_execute(0, &self.global_scope)? {
+        self.execute_from(0)
+    }
+
+    /// Appends constants and instructions to the ones being executed,
+    /// keeping the values stored in the global scope
+    pub fn append(
+        &mut self,
+        constants: HashMap<MemAddress, Constant>,
+        instructions: Vec<Instruction>,
+    ) {
+        // The code compiled by `eval` comes before the appended one
+        let evaluated = self.evaluated.take();
+        self.constants.extend(evaluated.constants);
+        self.instructions.extend(evaluated.instructions);
+        self.constants.extend(constants);
+        self.instructions.extend(instructions);
+    }
+
+    /// Executes the instructions in the global scope,
+    /// starting from `instruction_ptr`
+    pub fn execute_from(&self, instruction_ptr: InstructionPtr) -> RuntimeResult<()> {
+        match self.inner_execute(instruction_ptr, &self.global_scope)? {
             Some(addr) => Err(RuntimeError::CompilerError(format!(
                 "Trying to return address {} from the root scope",
                 addr
@@ -37,32 +343,181 @@ impl VMState {
         }
     }
 
+    /// Executes the program in slices, running until it finishes or until
+    /// `instructions` more instructions are executed, and continuing from
+    /// there in the next call. The instructions executed by callables like
+    /// `map` while calling user defined functions can't be suspended, so
+    /// a slice can run longer until those callables return
+    pub fn run_for(&self, instructions: u64) -> RuntimeResult<RunStatus> {
+        let mut continuation = match self.slice.replace(SliceState::Finished) {
+            SliceState::NotStarted => Continuation::default(),
+            SliceState::Suspended(continuation) => continuation,
+            SliceState::Finished => return Ok(RunStatus::Finished),
+        };
+        let stop_at = self
+            .executed_instructions
+            .get()
+            .saturating_add(instructions);
+
+        match self.run_frames(&self.global_scope, &mut continuation, Some(stop_at))? {
+            Exit::Yielded => {
+                self.slice.replace(SliceState::Suspended(continuation));
+                Ok(RunStatus::Yielded)
+            }
+            Exit::Finished => Ok(RunStatus::Finished),
+            Exit::Returned(addr) => Err(RuntimeError::CompilerError(format!(
+                "Trying to return address {} from the root scope",
+                addr
+            ))),
+        }
+    }
+
+    /// Returns the number of instructions executed by the virtual machine
+    pub fn executed_instructions(&self) -> u64 {
+        self.executed_instructions.get()
+    }
+
+    /// Returns a flag that stops the execution with an error when set,
+    /// which can be shared with other threads. The flag is cleared
+    /// once the execution stops
+    pub fn cancel_handle(&self) -> Arc<AtomicBool> {
+        self.cancel.clone()
+    }
+
+    fn check_cancelled(&self) -> RuntimeResult<()> {
+        if self.cancel.load(Ordering::Relaxed) {
+            self.cancel.store(false, Ordering::Relaxed);
+            return Err(RuntimeError::Cancelled);
+        }
+        Ok(())
+    }
+
     pub fn execute_lambda(
         &self,
         new_instruction_ptr: InstructionPtr,
         arity: usize,
         args: Vec<Value>,
     ) -> RuntimeResult<Value> {
+        let local_scope = self.enter_lambda(new_instruction_ptr, arity, args)?;
+        let result = self.inner_execute(new_instruction_ptr, &local_scope);
+        self.exit_lambda();
+
+        match result? {
+            Some(return_address) => self.get(&local_scope, &return_address),
+            None => Err(RuntimeError::CompilerError(format!(
+                "User defined callable at {} never returned",
+                new_instruction_ptr
+            ))),
+        }
+    }
+
+    /// Creates the scope of a user defined function with its arguments,
+    /// and registers the function as being executed
+    fn enter_lambda(
+        &self,
+        new_instruction_ptr: InstructionPtr,
+        arity: usize,
+        args: Vec<Value>,
+    ) -> RuntimeResult<Scope> {
         if args.len() != arity {
             return Err(RuntimeError::WrongArityN(
-                "User defined callable",
+                "User defined callable".into(),
                 arity,
                 args.len(),
             ));
         }
 
+        if let Some(hooks) = &self.hooks {
+            hooks
+                .limits
+                .check_call_depth(self.call_stack.borrow().len())?;
+        }
+
         let local_scope = Scope::default();
         for (idx, arg) in args.into_iter().enumerate() {
             self.store(&local_scope, MemAddress::new_local_var(idx), arg)?;
         }
 
-        match self.inner_execute(new_instruction_ptr, &local_scope)? {
-            Some(return_address) => self.get(&local_scope, &return_address),
-            None => Err(RuntimeError::CompilerError(format!(
-                "User defined callable at {} never returned",
-                new_instruction_ptr
-            ))),
+        self.call_stack.borrow_mut().push(new_instruction_ptr);
+        if let Some(profiler) = self.profiler() {
+            profiler.enter(ProfileFrame::Lambda(new_instruction_ptr));
+        }
+        Ok(local_scope)
+    }
+
+    fn exit_lambda(&self) {
+        if let Some(profiler) = self.profiler() {
+            profiler.exit();
         }
+        self.call_stack.borrow_mut().pop();
+    }
+
+    /// Compiles an expression with the compiler of the program and
+    /// executes it in the global scope, returning its value
+    pub fn eval(&self, expr: SExpr) -> RuntimeResult<Value> {
+        let entry_ptr = {
+            let mut compiler = self.compiler.borrow_mut();
+            let compiler = compiler.as_mut().ok_or_else(|| {
+                RuntimeError::Custom(String::from(
+                    "eval can only be used when the program is executed with its compiler",
+                ))
+            })?;
+            let checkpoint = compiler.checkpoint();
+            let constants_len = checkpoint.constants_len();
+            let instruction_ptr = checkpoint.instruction_ptr();
+            // The expression is compiled as a function without arguments,
+            // skipped by the program if it reaches its instructions
+            let jump_ptr = compiler.add_instruction(Instruction::new_jump(None));
+            let entry_ptr = compiler.instruction_ptr();
+            if let Err(err) = compiler.compile_lambda(Vec::new(), expr) {
+                compiler.rollback(checkpoint);
+                return Err(RuntimeError::CompilationError(err));
+            }
+            compiler.fill_jump(jump_ptr, compiler.instruction_ptr());
+
+            let (constants, instructions) = compiler.parts_since(constants_len, instruction_ptr);
+            let mut evaluated = self.evaluated.borrow_mut();
+            evaluated.constants.extend(constants);
+            evaluated.instructions.extend(instructions);
+            entry_ptr
+        };
+        self.execute_lambda(entry_ptr, 0, Vec::new())
+    }
+
+    /// Executes a value that should be either a language callable
+    /// or a user defined callable
+    pub fn execute_callable(&self, callable: &Value, args: Vec<Value>) -> RuntimeResult<Value> {
+        let result = match callable {
+            Value::Callable(language_callable) => {
+                if let Some(profiler) = self.profiler() {
+                    profiler.enter(ProfileFrame::Callable(SmolStr::from(
+                        language_callable.name(),
+                    )));
+                }
+                let result = language_callable.execute(self, args);
+                if let Some(profiler) = self.profiler() {
+                    profiler.exit();
+                }
+                result
+            }
+            Value::Lambda(instruction_ptr, arity) => {
+                self.execute_lambda(*instruction_ptr, *arity, args)
+            }
+            _ => Err(RuntimeError::NotACallable(callable.type_str())),
+        }?;
+        if let Some(hooks) = &self.hooks {
+            hooks.limits.check_size(&result)?;
+        }
+        Ok(result)
+    }
+
+    /// Realizes the lazy sequences in the values,
+    /// used before printing or comparing them
+    pub fn realize_all(&self, values: Vec<Value>) -> RuntimeResult<Vec<Value>> {
+        values
+            .into_iter()
+            .map(|value| value.realize(self))
+            .collect()
     }
 
     fn inner_execute(
@@ -70,50 +525,151 @@ impl VMState {
         starting_instruction_ptr: usize,
         current_scope: &Scope,
     ) -> RuntimeResult<Option<MemAddress>> {
-        let mut instruction_ptr = starting_instruction_ptr;
+        let mut continuation = Continuation {
+            instruction_ptr: starting_instruction_ptr,
+            frames: Vec::new(),
+        };
+        match self.run_frames(current_scope, &mut continuation, None)? {
+            Exit::Returned(return_addr) => Ok(Some(return_addr)),
+            Exit::Finished => Ok(None),
+            Exit::Yielded => unreachable!("Executions without a limit never yield"),
+        }
+    }
+
+    /// Executes the instructions from the continuation, calling the user
+    /// defined functions without recursion. If `stop_at` is reached the
+    /// execution yields, and can be resumed from the continuation
+    fn run_frames(
+        &self,
+        root_scope: &Scope,
+        continuation: &mut Continuation,
+        stop_at: Option<u64>,
+    ) -> RuntimeResult<Exit> {
+        let result = self.run_frames_inner(root_scope, continuation, stop_at);
+        if result.is_err() {
+            for _ in continuation.frames.drain(..) {
+                self.exit_lambda();
+            }
+        }
+        result
+    }
 
-        while let Some(instruction) = self.instructions.get(instruction_ptr) {
-            match instruction {
+    fn run_frames_inner(
+        &self,
+        root_scope: &Scope,
+        continuation: &mut Continuation,
+        stop_at: Option<u64>,
+    ) -> RuntimeResult<Exit> {
+        let hooks = self.hooks.as_deref();
+        let tracer = self.tracer();
+        self.check_cancelled()?;
+        loop {
+            if matches!(stop_at, Some(stop_at) if self.executed_instructions.get() >= stop_at) {
+                return Ok(Exit::Yielded);
+            }
+
+            let instruction_ptr = continuation.instruction_ptr;
+            let current_scope = continuation
+                .frames
+                .last()
+                .map_or(root_scope, |frame| &frame.scope);
+            let instruction = match self.instruction(instruction_ptr) {
+                Some(instruction) => instruction,
+                None if continuation.frames.is_empty() => return Ok(Exit::Finished),
+                None => {
+                    return Err(RuntimeError::CompilerError(format!(
+                        "User defined callable at {} never returned",
+                        self.call_stack.borrow().last().copied().unwrap_or_default()
+                    )))
+                }
+            };
+
+            let executed_instructions = self.executed_instructions.get() + 1;
+            self.executed_instructions.set(executed_instructions);
+            if executed_instructions & (CANCEL_CHECK_INTERVAL - 1) == 0 {
+                self.check_cancelled()?;
+            }
+
+            let mut trace_entry = None;
+            if let Some(hooks) = hooks {
+                hooks.limits.count_instruction()?;
+                if let Some(profiler) = &hooks.profiler {
+                    profiler.count_instruction();
+                }
+                if let Some(debugger) = &hooks.debugger {
+                    let call_stack = self.call_stack.borrow().clone();
+                    debugger.before_instruction(self, current_scope, &call_stack, instruction_ptr);
+                }
+                trace_entry = tracer.and_then(|tracer| {
+                    tracer.start_entry(instruction_ptr, &instruction, &self.call_stack.borrow())
+                });
+            }
+            let get_traced = |trace_entry: &mut Option<TraceEntry>, addr: &MemAddress| {
+                let value = self.get(current_scope, addr)?;
+                if let Some(entry) = trace_entry {
+                    entry.read(addr, &value);
+                }
+                Ok(value)
+            };
+            let log_reads = |trace_entry: &mut Option<TraceEntry>| {
+                if let (Some(tracer), Some(entry)) = (tracer, trace_entry) {
+                    tracer.log_reads(entry);
+                }
+            };
+            let store_traced =
+                |trace_entry: &Option<TraceEntry>, addr: MemAddress, value: Value| {
+                    if let (Some(tracer), Some(entry)) = (tracer, trace_entry) {
+                        tracer.log_write(entry, &addr, &value);
+                    }
+                    self.store(current_scope, addr, value)
+                };
+
+            let action = match &*instruction {
                 Instruction::Call {
                     callable: callable_addr,
                     args: arg_addrs,
                     result_addr,
                 } => {
-                    let callable = self.get(current_scope, callable_addr)?;
+                    let callable = get_traced(&mut trace_entry, callable_addr)?;
                     let args = arg_addrs
                         .iter()
-                        .map(|addr| self.get(current_scope, addr))
+                        .map(|addr| get_traced(&mut trace_entry, addr))
                         .collect::<RuntimeResult<Vec<Value>>>()?;
-                    match callable {
-                        Value::Callable(language_callable) => {
-                            let result = language_callable.execute(self, args)?;
-                            self.store(current_scope, *result_addr, result)?;
-                            instruction_ptr += 1;
-                            Ok(())
-                        }
-                        Value::Lambda(new_instruction_ptr, arity) => {
-                            let result = self.execute_lambda(new_instruction_ptr, arity, args)?;
-                            self.store(current_scope, *result_addr, result)?;
-                            instruction_ptr += 1;
-                            Ok(())
-                        }
-                        _ => Err(RuntimeError::NotACallable(callable.type_str())),
+                    // The call is logged before executing it, so that
+                    // it appears before the instructions of the callable
+                    log_reads(&mut trace_entry);
+                    if let Value::Lambda(entry_ptr, arity) = callable {
+                        let frame = CallFrame {
+                            scope: self.enter_lambda(entry_ptr, arity, args)?,
+                            return_ptr: instruction_ptr + 1,
+                            result_addr: *result_addr,
+                            trace_entry: trace_entry.take(),
+                        };
+                        Action::Call(frame, entry_ptr)
+                    } else {
+                        let result = self.execute_callable(&callable, args)?;
+                        store_traced(&trace_entry, *result_addr, result)?;
+                        Action::Jump(instruction_ptr + 1)
                     }
                 }
-                Instruction::Return(return_addr) => return Ok(Some(*return_addr)),
+                Instruction::Return(return_addr) => {
+                    get_traced(&mut trace_entry, return_addr)?;
+                    log_reads(&mut trace_entry);
+                    Action::Return(*return_addr)
+                }
                 Instruction::Assignment { src, dst } => {
-                    let value = self.get(current_scope, src)?;
-                    self.store(current_scope, *dst, value)?;
-                    instruction_ptr += 1;
-                    Ok(())
+                    let value = get_traced(&mut trace_entry, src)?;
+                    log_reads(&mut trace_entry);
+                    store_traced(&trace_entry, *dst, value)?;
+                    Action::Jump(instruction_ptr + 1)
                 }
                 Instruction::Jump(new_instr_ptr) => {
-                    instruction_ptr = *new_instr_ptr;
-                    Ok(())
+                    log_reads(&mut trace_entry);
+                    Action::Jump(*new_instr_ptr)
                 }
                 Instruction::JumpOnTrue(addr, new_instr_ptr) => {
                     let condition =
-                        self.get(current_scope, addr)?
+                        get_traced(&mut trace_entry, addr)?
                             .as_bool()
                             .map_err(|type_str| {
                                 RuntimeError::CompilerError(format!(
@@ -121,16 +677,16 @@ impl VMState {
                                     type_str
                                 ))
                             })?;
+                    log_reads(&mut trace_entry);
                     if condition {
-                        instruction_ptr = *new_instr_ptr;
+                        Action::Jump(*new_instr_ptr)
                     } else {
-                        instruction_ptr += 1;
+                        Action::Jump(instruction_ptr + 1)
                     }
-                    Ok(())
                 }
                 Instruction::JumpOnFalse(addr, new_instr_ptr) => {
                     let condition =
-                        self.get(current_scope, addr)?
+                        get_traced(&mut trace_entry, addr)?
                             .as_bool()
                             .map_err(|type_str| {
                                 RuntimeError::CompilerError(format!(
@@ -138,17 +694,55 @@ impl VMState {
                                     type_str
                                 ))
                             })?;
+                    log_reads(&mut trace_entry);
                     if condition {
-                        instruction_ptr += 1;
+                        Action::Jump(instruction_ptr + 1)
                     } else {
-                        instruction_ptr = *new_instr_ptr;
+                        Action::Jump(*new_instr_ptr)
+                    }
+                }
+            };
+
+            match action {
+                Action::Jump(new_instr_ptr) => continuation.instruction_ptr = new_instr_ptr,
+                Action::Call(frame, entry_ptr) => {
+                    continuation.frames.push(frame);
+                    continuation.instruction_ptr = entry_ptr;
+                }
+                Action::Return(return_addr) => {
+                    let frame = match continuation.frames.pop() {
+                        Some(frame) => frame,
+                        None => return Ok(Exit::Returned(return_addr)),
+                    };
+                    let value = self.get(&frame.scope, &return_addr);
+                    self.exit_lambda();
+                    let value = value?;
+                    if let Some(hooks) = hooks {
+                        hooks.limits.check_size(&value)?;
+                    }
+
+                    let caller_scope = continuation
+                        .frames
+                        .last()
+                        .map_or(root_scope, |frame| &frame.scope);
+                    if let (Some(tracer), Some(entry)) = (tracer, &frame.trace_entry) {
+                        tracer.log_write(entry, &frame.result_addr, &value);
                     }
-                    Ok(())
+                    self.store(caller_scope, frame.result_addr, value)?;
+                    continuation.instruction_ptr = frame.return_ptr;
                 }
-            }?;
+            }
         }
+    }
 
-        Ok(None)
+    /// Returns the value stored in an address of the global scope
+    pub fn get_global(&self, address: &MemAddress) -> RuntimeResult<Value> {
+        self.get(&self.global_scope, address)
+    }
+
+    /// Stores a value in an address of the global scope
+    pub fn store_global(&self, address: MemAddress, value: Value) -> RuntimeResult<()> {
+        self.store(&self.global_scope, address, value)
     }
 
     pub fn get(&self, current_scope: &Scope, address: &MemAddress) -> RuntimeResult<Value> {
@@ -156,12 +750,14 @@ impl VMState {
             Lifetime::Constant => self
                 .constants
                 .get(address)
+                .cloned()
+                .or_else(|| self.evaluated.borrow().constants.get(address).cloned())
                 .ok_or_else(|| {
                     RuntimeError::CompilerError(
                         "Memory address not found in constants table".to_string(),
                     )
                 })
-                .map(|constant| constant.clone().into()),
+                .map(Value::from),
             Lifetime::GlobalVar => self.global_scope.get_var(address.idx()),
             Lifetime::LocalVar => current_scope.get_var(address.idx()),
             Lifetime::Temporal => current_scope.get_temp(address.idx()),
@@ -194,3 +790,45 @@ impl VMState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{Engine, EngineError};
+
+    #[test]
+    fn test_cancel() {
+        let mut engine = Engine::new();
+        engine
+            .eval_str("(defn count-down [n] (if (> n 0) (count-down (- n 1)) n))")
+            .unwrap();
+        let cancel = engine.vm_state().cancel_handle();
+        cancel.store(true, Ordering::Relaxed);
+        assert!(matches!(
+            engine.eval_str("(count-down 5000)"),
+            Err(EngineError::RuntimeError(RuntimeError::Cancelled))
+        ));
+        assert!(!cancel.load(Ordering::Relaxed));
+        assert_eq!(
+            engine.eval_str("(count-down 5000)").unwrap(),
+            Value::from(0)
+        );
+    }
+
+    #[test]
+    fn test_hooks() {
+        let mut vm = VMState::default();
+        assert!(vm.hooks.is_none());
+        vm.set_limits(Limits {
+            max_instructions: Some(10),
+            ..Limits::default()
+        });
+        vm.set_profiler(Some(Profiler::default()));
+        assert!(vm.hooks.is_some());
+        vm.set_limits(Limits::default());
+        assert!(vm.profiler().is_some());
+        vm.set_profiler(None);
+        assert!(vm.hooks.is_none());
+        assert!(vm.limits().max_instructions.is_none());
+    }
+}
@@ -0,0 +1,64 @@
+use std::{cell::RefCell, mem};
+
+/// Result of an `is` assertion executed inside a test
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Assertion {
+    pub passed: bool,
+    /// The source code of the asserted expression
+    pub expr: String,
+    /// The readable representation of the expected value
+    pub expected: String,
+    /// The readable representation of the actual value
+    pub actual: String,
+    pub message: Option<String>,
+    /// The descriptions of the `testing` forms surrounding the assertion
+    pub contexts: Vec<String>,
+}
+
+/// Collects the assertions executed by the test callables
+#[derive(Debug, Default)]
+pub struct TestReport(RefCell<TestReportState>);
+
+#[derive(Debug, Default)]
+struct TestReportState {
+    assertions: Vec<Assertion>,
+    contexts: Vec<String>,
+}
+
+impl TestReport {
+    pub fn push_context(&self, description: String) {
+        self.0.borrow_mut().contexts.push(description);
+    }
+
+    pub fn pop_context(&self) {
+        self.0.borrow_mut().contexts.pop();
+    }
+
+    pub fn add_assertion(
+        &self,
+        passed: bool,
+        expr: String,
+        expected: String,
+        actual: String,
+        message: Option<String>,
+    ) {
+        let mut report = self.0.borrow_mut();
+        let contexts = report.contexts.clone();
+        report.assertions.push(Assertion {
+            passed,
+            expr,
+            expected,
+            actual,
+            message,
+            contexts,
+        });
+    }
+
+    /// Returns the assertions collected until now and clears
+    /// the report, including the `testing` contexts left open
+    pub fn take_assertions(&self) -> Vec<Assertion> {
+        let mut report = self.0.borrow_mut();
+        report.contexts.clear();
+        mem::take(&mut report.assertions)
+    }
+}
@@ -0,0 +1,273 @@
+use std::{cell::RefCell, io::Write};
+
+use crate::{
+    instruction::{Instruction, InstructionPtr},
+    memaddress::{Lifetime, MemAddress},
+    vm::Value,
+};
+
+/// Maximum length of the values printed in the trace
+const MAX_VALUE_LEN: usize = 60;
+
+/// Logs the instructions executed by the virtual machine, with the
+/// values read and written by each one of them. Instructions can be
+/// filtered by ranges of instruction pointers or by the functions
+/// they are executed in, if there aren't filters every instruction
+/// is logged
+pub struct Tracer {
+    writer: RefCell<Box<dyn Write>>,
+    ranges: Vec<(InstructionPtr, InstructionPtr)>,
+    functions: Vec<InstructionPtr>,
+}
+
+impl Tracer {
+    pub fn new(writer: Box<dyn Write>) -> Tracer {
+        Tracer {
+            writer: RefCell::new(writer),
+            ranges: Vec::new(),
+            functions: Vec::new(),
+        }
+    }
+
+    /// Logs the instructions from `start` until `end`, excluding it
+    pub fn add_range(&mut self, start: InstructionPtr, end: InstructionPtr) {
+        self.ranges.push((start, end));
+    }
+
+    /// Logs the instructions executed inside the function that starts at
+    /// `entry_ptr`, including the ones in the functions called by it
+    pub fn add_function(&mut self, entry_ptr: InstructionPtr) {
+        self.functions.push(entry_ptr);
+    }
+
+    /// Starts the entry of an instruction, if it passes the filters.
+    /// `call_stack` has the first instruction of every function
+    /// being executed
+    pub fn start_entry(
+        &self,
+        instruction_ptr: InstructionPtr,
+        instruction: &Instruction,
+        call_stack: &[InstructionPtr],
+    ) -> Option<TraceEntry> {
+        let in_range = self
+            .ranges
+            .iter()
+            .any(|(start, end)| (*start..*end).contains(&instruction_ptr));
+        let in_function = call_stack
+            .iter()
+            .any(|entry_ptr| self.functions.contains(entry_ptr));
+        let no_filters = self.ranges.is_empty() && self.functions.is_empty();
+
+        if no_filters || in_range || in_function {
+            Some(TraceEntry {
+                instruction_ptr,
+                depth: call_stack.len(),
+                line: describe_instruction(instruction),
+                reads: Vec::new(),
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Writes the instruction and the values it read
+    pub fn log_reads(&self, entry: &mut TraceEntry) {
+        let mut line = format!(
+            "{:>5} {}{}",
+            entry.instruction_ptr,
+            "  ".repeat(entry.depth),
+            entry.line
+        );
+        if !entry.reads.is_empty() {
+            line.push_str(" | ");
+            line.push_str(&entry.reads.join(", "));
+            entry.reads.clear();
+        }
+        self.write_line(&line);
+    }
+
+    /// Writes the value stored by the instruction
+    pub fn log_write(&self, entry: &TraceEntry, address: &MemAddress, value: &Value) {
+        let line = format!(
+            "{:>5} {}=> {} = {}",
+            entry.instruction_ptr,
+            "  ".repeat(entry.depth),
+            describe_address(address),
+            describe_value(value)
+        );
+        self.write_line(&line);
+    }
+
+    fn write_line(&self, line: &str) {
+        let mut writer = self.writer.borrow_mut();
+        let _ = writeln!(writer, "{}", line);
+    }
+}
+
+impl std::fmt::Debug for Tracer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Tracer({} ranges, {} functions)",
+            self.ranges.len(),
+            self.functions.len()
+        )
+    }
+}
+
+/// The instruction being traced, and the values it read
+#[derive(Debug)]
+pub struct TraceEntry {
+    instruction_ptr: InstructionPtr,
+    depth: usize,
+    line: String,
+    reads: Vec<String>,
+}
+
+impl TraceEntry {
+    pub fn read(&mut self, address: &MemAddress, value: &Value) {
+        self.reads.push(format!(
+            "{} = {}",
+            describe_address(address),
+            describe_value(value)
+        ));
+    }
+}
+
+fn describe_address(address: &MemAddress) -> String {
+    let lifetime = match address.lifetime() {
+        Lifetime::Constant => "const",
+        Lifetime::GlobalVar => "global",
+        Lifetime::LocalVar => "local",
+        Lifetime::Temporal => "temp",
+    };
+    format!("{}[{}]", lifetime, address.idx())
+}
+
+fn describe_value(value: &Value) -> String {
+    let value = format!("{}", value.readable());
+    if value.chars().count() > MAX_VALUE_LEN {
+        let truncated = value.chars().take(MAX_VALUE_LEN).collect::<String>();
+        format!("{}...", truncated)
+    } else {
+        value
+    }
+}
+
+pub(crate) fn describe_instruction(instruction: &Instruction) -> String {
+    match instruction {
+        Instruction::Call {
+            callable,
+            args,
+            result_addr,
+        } => {
+            let mut line = format!("call {}", describe_address(callable));
+            for arg in args {
+                line.push(' ');
+                line.push_str(&describe_address(arg));
+            }
+            line.push_str(" -> ");
+            line.push_str(&describe_address(result_addr));
+            line
+        }
+        Instruction::Return(addr) => format!("ret {}", describe_address(addr)),
+        Instruction::Assignment { src, dst } => {
+            format!("mov {} -> {}", describe_address(src), describe_address(dst))
+        }
+        Instruction::Jump(ins_ptr) => format!("jmp {}", ins_ptr),
+        Instruction::JumpOnTrue(addr, ins_ptr) => {
+            format!("jmpT {} {}", describe_address(addr), ins_ptr)
+        }
+        Instruction::JumpOnFalse(addr, ins_ptr) => {
+            format!("jmpF {} {}", describe_address(addr), ins_ptr)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::{compiler::CompilerState, parsers::SExprsParser, vm::VMState};
+
+    /// Writer that keeps what's written to it, shared with the test
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn trace<F: FnOnce(&mut Tracer, &CompilerState)>(source: &str, add_filters: F) -> String {
+        let mut compiler_state = CompilerState::default();
+        for expr in SExprsParser::parse(source).unwrap() {
+            compiler_state.compile(expr).unwrap();
+        }
+        let buffer = SharedBuffer::default();
+        let mut tracer = Tracer::new(Box::new(buffer.clone()));
+        add_filters(&mut tracer, &compiler_state);
+
+        let (constants_rev, instructions) = compiler_state.into_parts();
+        let constants = constants_rev
+            .into_iter()
+            .map(|(constant, address)| (address, constant))
+            .collect();
+        let mut vm_state = VMState::new(constants, instructions);
+        vm_state.set_tracer(Some(tracer));
+        vm_state.execute().unwrap();
+        let output = String::from_utf8(buffer.0.borrow().clone()).unwrap();
+        output
+    }
+
+    #[test]
+    fn test_filters() {
+        let source = "(defn f [x] (* x 2)) (f 3)";
+        assert_eq!(
+            trace(source, |_, _| {}),
+            "    0 mov const[0] -> global[0] | const[0] = fn@2
+    0 => global[0] = fn@2
+    1 jmp 4
+    4 call global[0] const[3] -> temp[0] | global[0] = fn@2, const[3] = 3
+    2   call const[1] local[0] const[2] -> temp[0] | const[1] = *, local[0] = 3, const[2] = 2
+    2   => temp[0] = 6
+    3   ret temp[0] | temp[0] = 6
+    4 => temp[0] = 6
+"
+        );
+        assert_eq!(
+            trace(source, |tracer, _| tracer.add_range(0, 2)),
+            "    0 mov const[0] -> global[0] | const[0] = fn@2
+    0 => global[0] = fn@2
+    1 jmp 4
+"
+        );
+        assert_eq!(
+            trace(source, |tracer, compiler_state| {
+                tracer.add_function(compiler_state.function_entry("f").unwrap())
+            }),
+            "    2   call const[1] local[0] const[2] -> temp[0] | const[1] = *, local[0] = 3, const[2] = 2
+    2   => temp[0] = 6
+    3   ret temp[0] | temp[0] = 6
+"
+        );
+    }
+
+    #[test]
+    fn test_long_values() {
+        let output = trace("(def v (into [] (range 40)))", |_, _| {});
+        let vector = (0..40).map(|n| n.to_string()).collect::<Vec<_>>().join(" ");
+        let value = format!("[{}]", vector);
+        let last_line = output.lines().last().unwrap();
+        assert_eq!(
+            last_line,
+            format!("    3 => global[0] = {}...", &value[..MAX_VALUE_LEN])
+        );
+    }
+}
@@ -1,16 +1,20 @@
 use std::{
-    collections::{hash_map::DefaultHasher, HashMap, HashSet},
+    cmp::Ordering,
     fmt::{self, Debug, Display, Formatter},
     hash::{Hash, Hasher},
 };
 
+use escape8259::unescape;
+
 use num::{Rational64, Zero};
+use smol_str::SmolStr;
 
 use crate::{
     callables::Callable,
+    compiler::{Literal, SExpr},
     constant::Constant,
     instruction::InstructionPtr,
-    vm::{List, RuntimeError, RuntimeResult},
+    vm::{Atom, LazySeq, List, RuntimeError, RuntimeResult, VMState, ValueMap, ValueSet},
 };
 
 /// Represents a value used during execution of `miniclj` code
@@ -20,10 +24,14 @@ pub enum Value {
     Lambda(InstructionPtr, usize),
 
     List(List),
+    LazySeq(LazySeq),
     Vector(Vec<Value>),
-    Set(HashSet<Value>),
-    Map(HashMap<Value, Value>),
+    Set(ValueSet),
+    Map(ValueMap),
 
+    Atom(Atom),
+    /// A symbol read as data by `read-string`, which can be evaluated
+    Symbol(SmolStr),
     String(String),
     Number(Rational64),
     Nil,
@@ -34,15 +42,37 @@ impl Value {
         match self {
             Value::Callable(_) | Value::Lambda(..) => "a function",
             Value::List(_) => "a list",
+            Value::LazySeq(_) => "a lazy sequence",
             Value::Vector(_) => "a vector",
             Value::Set(_) => "a set",
             Value::Map(_) => "a map",
+            Value::Atom(_) => "an atom",
+            Value::Symbol(_) => "a symbol",
             Value::String(_) => "a string",
             Value::Number(_) => "a number",
             Value::Nil => "nil",
         }
     }
 
+    /// The position of the type of the value in the total order of
+    /// `Value`, used to compare values of different types
+    fn type_rank(&self) -> u8 {
+        match self {
+            Value::Nil => 0,
+            Value::Number(_) => 1,
+            Value::String(_) => 2,
+            Value::Symbol(_) => 3,
+            Value::Vector(_) => 4,
+            Value::List(_) => 5,
+            Value::LazySeq(_) => 6,
+            Value::Set(_) => 7,
+            Value::Map(_) => 8,
+            Value::Atom(_) => 9,
+            Value::Callable(_) => 10,
+            Value::Lambda(..) => 11,
+        }
+    }
+
     pub fn as_i64(&self) -> Result<i64, &'static str> {
         if let Value::Number(n) = self {
             if n.is_integer() {
@@ -78,6 +108,79 @@ impl Value {
         }
     }
 
+    /// Realizes every lazy sequence in the value, including the ones
+    /// nested inside of collections, converting them into lists
+    pub fn realize(self, state: &VMState) -> RuntimeResult<Value> {
+        let realize_all = |values: Vec<Value>| {
+            values
+                .into_iter()
+                .map(|value| value.realize(state))
+                .collect::<RuntimeResult<Vec<Value>>>()
+        };
+
+        Ok(match self {
+            Value::LazySeq(seq) => Value::List(
+                realize_all(seq.realize(state)?)?
+                    .into_iter()
+                    .rev()
+                    .collect(),
+            ),
+            Value::List(list) => Value::List(
+                realize_all(list.into_iter().collect())?
+                    .into_iter()
+                    .rev()
+                    .collect(),
+            ),
+            Value::Vector(vector) => Value::Vector(realize_all(vector)?),
+            Value::Set(set) => {
+                let mut realized = set.empty_like();
+                realized.extend(realize_all(set.into_iter().collect())?);
+                Value::Set(realized)
+            }
+            Value::Map(map) => {
+                let mut realized = map.empty_like();
+                for (key, val) in map {
+                    realized.insert(key.realize(state)?, val.realize(state)?);
+                }
+                Value::Map(realized)
+            }
+            value => value,
+        })
+    }
+
+    /// Returns a wrapper that displays the value in a format
+    /// that can be read back by the `SExprsParser`
+    pub fn readable(&self) -> ReadableValue<'_> {
+        ReadableValue(self)
+    }
+
+    /// Converts the value into the expression that `eval` compiles.
+    /// Lists are calls, and functions are only valid if they're
+    /// language callables, which are referred by their names
+    pub fn into_sexpr(self) -> Result<SExpr, &'static str> {
+        let into_sexprs = |values: Vec<Value>| {
+            values
+                .into_iter()
+                .map(Value::into_sexpr)
+                .collect::<Result<Vec<SExpr>, &'static str>>()
+        };
+
+        Ok(match self {
+            Value::Callable(c) => SExpr::Literal(Literal::Symbol(SmolStr::from(c.name()))),
+            Value::List(list) => SExpr::Expr(into_sexprs(list.into_iter().collect())?, None),
+            Value::Vector(vector) => SExpr::Vector(into_sexprs(vector)?),
+            Value::Set(set) => SExpr::Set(into_sexprs(set.into_iter().collect())?),
+            Value::Map(map) => SExpr::Map(into_sexprs(
+                map.into_iter().flat_map(|(key, val)| [key, val]).collect(),
+            )?),
+            Value::Symbol(symbol) => SExpr::Literal(Literal::Symbol(symbol)),
+            Value::String(s) => SExpr::Literal(Literal::String(s)),
+            Value::Number(n) => SExpr::Literal(Literal::Number(n)),
+            Value::Nil => SExpr::Literal(Literal::Nil),
+            value => return Err(value.type_str()),
+        })
+    }
+
     pub fn into_map_entry(self) -> RuntimeResult<(Value, Value)> {
         match self {
             Value::Vector(v) if v.len() == 2 => {
@@ -103,6 +206,44 @@ impl From<Constant> for Value {
     }
 }
 
+/// Converts an expression read by the `SExprsParser` into data, used by
+/// `read-string`. Calls are read as lists, and the shorthand forms are
+/// expanded into the calls they represent
+impl From<SExpr> for Value {
+    fn from(expr: SExpr) -> Value {
+        let from_sexprs =
+            |exprs: Vec<SExpr>| exprs.into_iter().map(Value::from).collect::<Vec<Value>>();
+        let symbol = |name: &str| Value::Symbol(SmolStr::from(name));
+
+        match expr {
+            SExpr::Expr(exprs, _) => Value::List(from_sexprs(exprs).into_iter().rev().collect()),
+            SExpr::ShortLambda(exprs) => {
+                let body = Value::List(from_sexprs(exprs).into_iter().rev().collect());
+                let fn_form = vec![symbol("fn"), Value::Vector(vec![symbol("%")]), body];
+                Value::List(fn_form.into_iter().rev().collect())
+            }
+            SExpr::List(exprs) => {
+                let list_form = [vec![symbol("list")], from_sexprs(exprs)].concat();
+                Value::List(list_form.into_iter().rev().collect())
+            }
+            SExpr::Vector(exprs) => Value::Vector(from_sexprs(exprs)),
+            SExpr::Set(exprs) => Value::Set(from_sexprs(exprs).into_iter().collect()),
+            SExpr::Map(exprs) => {
+                let mut values = from_sexprs(exprs).into_iter();
+                let mut map = ValueMap::new();
+                while let (Some(key), Some(val)) = (values.next(), values.next()) {
+                    map.insert(key, val);
+                }
+                Value::Map(map)
+            }
+            SExpr::Literal(Literal::Symbol(s)) => Value::Symbol(s),
+            SExpr::Literal(Literal::String(s)) => Value::String(s),
+            SExpr::Literal(Literal::Number(n)) => Value::Number(n),
+            SExpr::Literal(Literal::Nil) => Value::Nil,
+        }
+    }
+}
+
 impl From<i64> for Value {
     fn from(n: i64) -> Value {
         Value::Number(Rational64::from(n))
@@ -125,6 +266,7 @@ impl Debug for Value {
             Value::Callable(c) => write!(f, "fn_{}", c.name()),
             Value::Lambda(ptr, _) => write!(f, "fn@{}", ptr),
             Value::List(l) => write!(f, "'{}", l),
+            Value::LazySeq(s) => write!(f, "'{}", s),
             Value::Vector(v) => {
                 let string = v
                     .iter()
@@ -149,6 +291,8 @@ impl Debug for Value {
                     .join(", ");
                 write!(f, "{{{}}}", string)
             }
+            Value::Atom(a) => write!(f, "{:?}", a),
+            Value::Symbol(s) => write!(f, "{}", s),
             Value::String(s) => write!(f, "\"{}\"", s),
             Value::Number(n) => write!(f, "{}", n),
             Value::Nil => write!(f, "nil"),
@@ -162,9 +306,12 @@ impl Display for Value {
             Value::Callable(c) => write!(f, "{}", c.name()),
             Value::Lambda(..) => write!(f, "{:?}", self),
             Value::List(l) => write!(f, "{}", l),
+            Value::LazySeq(s) => write!(f, "{}", s),
             Value::Vector(..) => write!(f, "{:?}", self),
             Value::Set(..) => write!(f, "{:?}", self),
             Value::Map(..) => write!(f, "{:?}", self),
+            Value::Atom(a) => a.write_with(f, |f, value| write!(f, "{}", value)),
+            Value::Symbol(s) => write!(f, "{}", s),
             Value::String(s) => write!(f, "{}", s),
             Value::Number(n) => {
                 if n.denom() == &1 {
@@ -178,15 +325,102 @@ impl Display for Value {
     }
 }
 
+/// Displays a `Value` as `miniclj` code, used by the `pr` family of callables.
+/// Unlike the `Display` implementation of `Value`, strings are quoted (even
+/// inside of collections), `nil` is printed, lists are quoted and maps are
+/// printed without commas. Functions can't be read back, so they are displayed
+/// the same way as with `Display`
+pub struct ReadableValue<'a>(&'a Value);
+
+impl ReadableValue<'_> {
+    fn write_seq<'b, I: Iterator<Item = &'b Value>>(
+        f: &mut Formatter<'_>,
+        values: I,
+    ) -> fmt::Result {
+        for (idx, value) in values.enumerate() {
+            if idx != 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", value.readable())?;
+        }
+        Ok(())
+    }
+
+    fn write_string(f: &mut Formatter<'_>, string: &str) -> fmt::Result {
+        write!(f, "\"")?;
+        let mut chars = string.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                // Strings keep the escape sequences written in the source code
+                '\\' => match chars.next() {
+                    Some(next) => write!(f, "\\{}", next)?,
+                    None => write!(f, "\\\\")?,
+                },
+                '"' => write!(f, "\\\"")?,
+                '\n' => write!(f, "\\n")?,
+                '\r' => write!(f, "\\r")?,
+                '\t' => write!(f, "\\t")?,
+                c => write!(f, "{}", c)?,
+            }
+        }
+        write!(f, "\"")
+    }
+}
+
+impl Display for ReadableValue<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Value::List(l) => {
+                write!(f, "'(")?;
+                ReadableValue::write_seq(f, l.iter())?;
+                write!(f, ")")
+            }
+            Value::LazySeq(s) => {
+                let (values, complete) = s.realized_values();
+                write!(f, "'(")?;
+                ReadableValue::write_seq(f, values.iter())?;
+                match (complete, values.is_empty()) {
+                    (false, true) => write!(f, "...")?,
+                    (false, false) => write!(f, " ...")?,
+                    (true, _) => {}
+                }
+                write!(f, ")")
+            }
+            Value::Vector(v) => {
+                write!(f, "[")?;
+                ReadableValue::write_seq(f, v.iter())?;
+                write!(f, "]")
+            }
+            Value::Set(s) => {
+                write!(f, "#{{")?;
+                ReadableValue::write_seq(f, s.iter())?;
+                write!(f, "}}")
+            }
+            Value::Map(m) => {
+                write!(f, "{{")?;
+                ReadableValue::write_seq(f, m.iter().flat_map(|(k, v)| [k, v]))?;
+                write!(f, "}}")
+            }
+            Value::Atom(a) => a.write_with(f, |f, value| write!(f, "{}", value.readable())),
+            Value::String(s) => ReadableValue::write_string(f, s),
+            Value::Nil => write!(f, "nil"),
+            value => write!(f, "{}", value),
+        }
+    }
+}
+
 impl PartialEq for Value {
     fn eq(&self, other: &Value) -> bool {
         match (self, other) {
             (Value::Callable(c1), Value::Callable(c2)) => c1.name() == c2.name(),
             (Value::Lambda(ptr1, _), Value::Lambda(ptr2, _)) => ptr1 == ptr2,
             (Value::List(l1), Value::List(l2)) => l1 == l2,
+            (Value::LazySeq(s1), Value::LazySeq(s2)) => s1.ptr_eq(s2),
             (Value::Vector(v1), Value::Vector(v2)) => v1 == v2,
             (Value::Set(s1), Value::Set(s2)) => s1 == s2,
             (Value::Map(m1), Value::Map(m2)) => m1 == m2,
+            (Value::Atom(a1), Value::Atom(a2)) => a1.ptr_eq(a2),
+            (Value::Symbol(s1), Value::Symbol(s2)) => s1 == s2,
             (Value::String(s1), Value::String(s2)) => s1 == s2,
             (Value::Number(n1), Value::Number(n2)) => n1 == n2,
             (Value::Nil, Value::Nil) => true,
@@ -205,34 +439,58 @@ impl Hash for Value {
             Value::Callable(c) => c.name().hash(state),
             Value::Lambda(ptr, _) => ptr.hash(state),
             Value::List(l) => l.hash(state),
+            Value::LazySeq(s) => s.as_ptr().hash(state),
             Value::Vector(v) => v.hash(state),
-            Value::Set(s) => {
-                let hash = s
-                    .iter()
-                    .map(|v| {
-                        let mut inner_state = DefaultHasher::new();
-                        v.hash(&mut inner_state);
-                        inner_state.finish()
-                    })
-                    .fold(0, u64::wrapping_add);
-
-                state.write_u64(hash);
-            }
-            Value::Map(m) => {
-                let hash = m
-                    .iter()
-                    .map(|kv| {
-                        let mut inner_state = DefaultHasher::new();
-                        kv.hash(&mut inner_state);
-                        inner_state.finish()
-                    })
-                    .fold(0, u64::wrapping_add);
-
-                state.write_u64(hash);
-            }
+            Value::Set(s) => s.hash(state),
+            Value::Map(m) => m.hash(state),
+            Value::Atom(a) => a.as_ptr().hash(state),
+            Value::Symbol(s) => s.hash(state),
             Value::String(s) => s.hash(state),
             Value::Number(n) => n.hash(state),
             Value::Nil => NilHash.hash(state),
         }
     }
 }
+
+/// A total order over values, used by `compare`, the comparison operations,
+/// `sort`, `min`, `max` and the sorted collections. Values of
+/// different types are ordered by their type: `nil`, numbers, strings,
+/// symbols, vectors, lists, lazy sequences, sets, maps, atoms and functions.
+/// Strings are compared by their characters, sequences element by element,
+/// and sets and maps by their sorted elements. Lazy sequences, atoms and
+/// lambdas are only equal to themselves, so they're ordered by their identity.
+/// The callables realize lazy sequences into lists before comparing them or
+/// using them as keys, as their identity doesn't change when they're realized
+impl Ord for Value {
+    fn cmp(&self, other: &Value) -> Ordering {
+        fn sorted<T: Ord, I: Iterator<Item = T>>(elements: I) -> Vec<T> {
+            let mut elements = elements.collect::<Vec<T>>();
+            elements.sort();
+            elements
+        }
+
+        match (self, other) {
+            (Value::Number(n1), Value::Number(n2)) => n1.cmp(n2),
+            (Value::String(s1), Value::String(s2)) => {
+                let unescaped = |s: &String| unescape(s).unwrap_or_else(|_| s.clone());
+                unescaped(s1).cmp(&unescaped(s2)).then_with(|| s1.cmp(s2))
+            }
+            (Value::Symbol(s1), Value::Symbol(s2)) => s1.cmp(s2),
+            (Value::Vector(v1), Value::Vector(v2)) => v1.cmp(v2),
+            (Value::List(l1), Value::List(l2)) => l1.iter().cmp(l2.iter()),
+            (Value::LazySeq(s1), Value::LazySeq(s2)) => s1.as_ptr().cmp(&s2.as_ptr()),
+            (Value::Set(s1), Value::Set(s2)) => sorted(s1.iter()).cmp(&sorted(s2.iter())),
+            (Value::Map(m1), Value::Map(m2)) => sorted(m1.iter()).cmp(&sorted(m2.iter())),
+            (Value::Atom(a1), Value::Atom(a2)) => a1.as_ptr().cmp(&a2.as_ptr()),
+            (Value::Callable(c1), Value::Callable(c2)) => c1.name().cmp(c2.name()),
+            (Value::Lambda(ptr1, _), Value::Lambda(ptr2, _)) => ptr1.cmp(ptr2),
+            _ => self.type_rank().cmp(&other.type_rank()),
+        }
+    }
+}
+
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Value) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
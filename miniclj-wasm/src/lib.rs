@@ -1,4 +1,8 @@
-use miniclj_lib::{CompilerState, SExprsParser, VMState};
+use std::{io, sync::atomic::Ordering};
+
+use miniclj_lib::{
+    BrowserIO, BufferIO, CompilerState, Engine, IOHandler, Limits, RunStatus, SExprsParser, VMState,
+};
 use wasm_bindgen::prelude::*;
 
 mod result;
@@ -52,33 +56,160 @@ pub fn compile(code: &str) -> JsValue {
 
 /// Executes miniclj code
 /// - read instructions are executed as window.prompt calls
-/// - print and println instructions append its output to
-///   `window.minicljoutput`
+/// - the text printed by the program is returned as the output
 #[wasm_bindgen]
 pub fn run(code: &str) -> JsValue {
+    run_with_limits(code, None, None, None)
+}
+
+/// Executes miniclj code like `run`, but the read instructions
+/// read the lines of `input`, and the end of the input after them
+#[wasm_bindgen]
+pub fn run_with_input(code: &str, input: &str) -> JsValue {
     set_panic_hook();
 
-    let tree = match SExprsParser::parse(code) {
-        Ok(tree) => tree,
-        Err(err) => return JSResult::error(format!("{:#?}", err)),
+    let (mut engine, entry_ptr, output) = match load(code) {
+        Ok(loaded) => loaded,
+        Err(err) => return err,
     };
+    set_input(engine.vm_state(), &output, input);
+    if let Err(err) = engine.execute_from(entry_ptr) {
+        return JSResult::error(err.to_string());
+    }
 
-    let mut compiler_state = CompilerState::default();
-    for expr in tree {
-        if let Err(err) = compiler_state.compile(expr) {
-            return JSResult::error(format!("Compilation error: {}", err));
+    JSResult::output(output.take_output())
+}
+
+/// Executes miniclj code like `run`, stopping it with an error if
+/// it exceeds any of the limits passed. The execution time can't
+/// be measured in the browser, the number of executed instructions
+/// should be limited instead to stop infinite loops
+#[wasm_bindgen]
+pub fn run_with_limits(
+    code: &str,
+    max_instructions: Option<u32>,
+    max_call_depth: Option<u32>,
+    max_collection_size: Option<u32>,
+) -> JsValue {
+    set_panic_hook();
+
+    let (mut engine, entry_ptr, output) = match load(code) {
+        Ok(loaded) => loaded,
+        Err(err) => return err,
+    };
+    engine.vm_state_mut().set_limits(Limits {
+        max_instructions: max_instructions.map(u64::from),
+        max_call_depth: max_call_depth.map(|depth| depth as usize),
+        max_collection_size: max_collection_size.map(|size| size as usize),
+        timeout: None,
+    });
+    if let Err(err) = engine.execute_from(entry_ptr) {
+        return JSResult::error(err.to_string());
+    }
+
+    JSResult::output(output.take_output())
+}
+
+/// A miniclj program executed in slices, so that the page
+/// can be updated between them while long programs run
+#[wasm_bindgen]
+pub struct Program {
+    engine: Engine,
+    output: BufferIO,
+}
+
+#[wasm_bindgen]
+impl Program {
+    /// Compiles miniclj code, throws an error result if it's invalid
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        code: &str,
+        max_call_depth: Option<u32>,
+        max_collection_size: Option<u32>,
+    ) -> Result<Program, JsValue> {
+        set_panic_hook();
+
+        let (mut engine, _, output) = load(code)?;
+        engine.vm_state_mut().set_limits(Limits {
+            max_call_depth: max_call_depth.map(|depth| depth as usize),
+            max_collection_size: max_collection_size.map(|size| size as usize),
+            ..Limits::default()
+        });
+        Ok(Program { engine, output })
+    }
+
+    /// Executes up to `instructions` instructions of the program,
+    /// the result has the status "yielded" if it didn't finish,
+    /// otherwise its output is the text printed by the program
+    pub fn run_for(&mut self, instructions: u32) -> JsValue {
+        match self.engine.run_for(u64::from(instructions)) {
+            Ok(RunStatus::Finished) => JSResult::output(self.output.take_output()),
+            Ok(RunStatus::Yielded) => JSResult::yielded(),
+            Err(err) => JSResult::error(err.to_string()),
         }
     }
 
-    let (constants_rev, instructions) = compiler_state.into_parts();
-    let constants = constants_rev
-        .into_iter()
-        .map(|(constant, address)| (address, constant))
-        .collect();
+    /// Makes the read instructions read the lines of `input`,
+    /// instead of calling `window.prompt`
+    pub fn set_input(&self, input: &str) {
+        set_input(self.engine.vm_state(), &self.output, input);
+    }
 
-    if let Err(err) = VMState::new(constants, instructions).execute() {
-        return JSResult::error(format!("Runtime error: {}", err));
+    /// Stops the program, the next slice returns an error
+    pub fn cancel(&self) {
+        self.engine
+            .vm_state()
+            .cancel_handle()
+            .store(true, Ordering::Relaxed);
     }
 
-    JSResult::output(String::from("Correct compilation"))
+    pub fn executed_instructions(&self) -> f64 {
+        self.engine.vm_state().executed_instructions() as f64
+    }
+}
+
+/// Keeps the text printed by a program to return it as its output,
+/// and reads its input from a prepared buffer, if any, or otherwise
+/// through `window.prompt`
+struct PageIO {
+    output: BufferIO,
+    input: Option<BufferIO>,
+}
+
+impl IOHandler for PageIO {
+    fn print(&mut self, text: &str) -> io::Result<()> {
+        self.output.print(text)
+    }
+
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        match &mut self.input {
+            Some(input) => input.read_line(),
+            None => BrowserIO.read_line(),
+        }
+    }
+}
+
+/// Replaces the input of a program with a prepared buffer
+fn set_input(vm_state: &VMState, output: &BufferIO, input: &str) {
+    vm_state.set_io(Box::new(PageIO {
+        output: output.clone(),
+        input: Some(BufferIO::new(input)),
+    }));
+}
+
+/// Compiles miniclj code into an engine ready to execute it, returning
+/// also its first instruction and the buffer where the program prints
+/// its output
+fn load(code: &str) -> Result<(Engine, usize, BufferIO), JsValue> {
+    let mut engine = Engine::new();
+    let entry_ptr = engine
+        .load_str(code)
+        .map_err(|err| JSResult::error(err.to_string()))?;
+
+    let output = BufferIO::default();
+    engine.vm_state().set_io(Box::new(PageIO {
+        output: output.clone(),
+        input: None,
+    }));
+    Ok((engine, entry_ptr, output))
 }
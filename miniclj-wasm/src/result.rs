@@ -19,6 +19,17 @@ impl JSResult {
         JsValue::from_serde(&result).unwrap()
     }
 
+    /// Returned by programs executed in slices that haven't finished
+    pub fn yielded() -> JsValue {
+        let result = JSResult {
+            status: "yielded",
+            output: None,
+            error: None,
+        };
+
+        JsValue::from_serde(&result).unwrap()
+    }
+
     pub fn error(error: String) -> JsValue {
         let result = JSResult {
             status: "error",
@@ -2,11 +2,15 @@ use std::{
     fs::{read_to_string, File},
     path::PathBuf,
     str::FromStr,
+    time::Duration,
 };
 
 use clap::{
     crate_authors, crate_description, crate_name, crate_version, App, AppSettings, Arg, ArgMatches,
 };
+use miniclj_lib::{
+    DebugInfo, EngineError, EnvPermissions, FsPermissions, Limits, Profiler, RuntimeError, Tracer,
+};
 
 pub fn args() -> App<'static> {
     App::new(crate_name!())
@@ -14,6 +18,8 @@ pub fn args() -> App<'static> {
         .author(crate_authors!())
         .about(crate_description!())
         .setting(AppSettings::SubcommandRequiredElseHelp)
+        .setting(AppSettings::AllowExternalSubcommands)
+        .after_help("Run a script, with its arguments in *command-line-args*:\n    miniclj FILE [ARGS]...")
         .subcommand(
             App::new("check")
                 .about("Check if a source code file can be correctly parsed")
@@ -52,14 +58,65 @@ pub fn args() -> App<'static> {
                 )
         )
         .subcommand(
+            App::new("dap")
+                .about("Start a Debug Adapter Protocol server that communicates through stdin and stdout"),
+        )
+        .subcommand(
+            App::new("debug")
+                .about("Run a source code file in an interactive debugger")
+                .arg(
+                    Arg::new("FILE")
+                        .about("File to debug")
+                        .required(true)
+                        .index(1),
+                ),
+        )
+        .subcommand(with_fs_args(with_limit_args(with_trace_args(
             App::new("exec").about("Execute a bytecode file").arg(
                 Arg::new("FILE")
                     .about("File to execute")
                     .required(true)
                     .index(1),
             ),
+        ))))
+        .subcommand(
+            App::new("fmt")
+                .about("Format source code files with a canonical layout")
+                .arg(
+                    Arg::new("FILE")
+                        .about("Files to format")
+                        .required(true)
+                        .multiple_values(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::new("check")
+                        .long("check")
+                        .about("Don't write the files, exit with an error if any of them isn't formatted"),
+                ),
+        )
+        .subcommand(
+            App::new("lsp")
+                .about("Start a language server that communicates through stdin and stdout"),
+        )
+        .subcommand(
+            App::new("nrepl")
+                .about("Start an nREPL server for editor integration")
+                .arg(
+                    Arg::new("port")
+                        .short('p')
+                        .long("port")
+                        .takes_value(true)
+                        .value_name("PORT")
+                        .default_value("0")
+                        .about("Port to listen on, a random one is used by default"),
+                ),
         )
         .subcommand(
+            App::new("repl")
+                .about("Start an interactive session that compiles and executes expressions"),
+        )
+        .subcommand(with_fs_args(with_limit_args(with_trace_args(
             App::new("run")
                 .about("Compile and execute a source code file")
                 .arg(
@@ -67,15 +124,231 @@ pub fn args() -> App<'static> {
                         .about("File to run")
                         .required(true)
                         .index(1),
+                )
+                .arg(
+                    Arg::new("profile")
+                        .long("profile")
+                        .about("Measure the functions executed, writing a report to FILE.profile and the folded stacks for flamegraph tools to FILE.folded"),
+                ),
+        ))))
+        .subcommand(
+            App::new("test")
+                .about("Run the tests defined in *_test.clj files")
+                .arg(
+                    Arg::new("PATH")
+                        .about("Files to test, or directories to search for test files")
+                        .multiple_values(true)
+                        .default_value(".")
+                        .index(1),
+                )
+                .arg(
+                    Arg::new("junit")
+                        .long("junit")
+                        .takes_value(true)
+                        .value_name("REPORT_FILE")
+                        .about("Also write the results to a JUnit XML report"),
                 ),
         )
 }
 
+/// Adds the options used to trace the execution of a program
+fn with_trace_args(app: App<'static>) -> App<'static> {
+    app.arg(
+        Arg::new("trace")
+            .long("trace")
+            .about("Print every executed instruction with the values it reads and writes to stderr"),
+    )
+    .arg(
+        Arg::new("trace-range")
+            .long("trace-range")
+            .takes_value(true)
+            .multiple_occurrences(true)
+            .value_name("START..END")
+            .about("Only trace the instructions in this range, implies --trace"),
+    )
+    .arg(
+        Arg::new("trace-fn")
+            .long("trace-fn")
+            .takes_value(true)
+            .multiple_occurrences(true)
+            .value_name("FUNCTION")
+            .about("Only trace the instructions executed inside this function, given by its name or its first instruction, implies --trace"),
+    )
+}
+
+/// Adds the options used to limit the resources used by a program
+fn with_limit_args(app: App<'static>) -> App<'static> {
+    app.arg(
+        Arg::new("max-instructions")
+            .long("max-instructions")
+            .takes_value(true)
+            .value_name("COUNT")
+            .about("Stop the program after executing this number of instructions"),
+    )
+    .arg(
+        Arg::new("max-call-depth")
+            .long("max-call-depth")
+            .takes_value(true)
+            .value_name("DEPTH")
+            .about(
+                "Stop the program if this number of functions are being executed at the same time",
+            ),
+    )
+    .arg(
+        Arg::new("max-collection-size")
+            .long("max-collection-size")
+            .takes_value(true)
+            .value_name("SIZE")
+            .about(
+                "Stop the program if a function returns a collection or a string larger than this",
+            ),
+    )
+    .arg(
+        Arg::new("timeout")
+            .long("timeout")
+            .takes_value(true)
+            .value_name("MILLISECONDS")
+            .about("Stop the program after running for this time"),
+    )
+}
+
+/// Adds the options used to allow a program to access
+/// files and environment variables
+fn with_fs_args(app: App<'static>) -> App<'static> {
+    app.arg(
+        Arg::new("allow-read")
+            .long("allow-read")
+            .takes_value(true)
+            .multiple_occurrences(true)
+            .value_name("DIR")
+            .about("Allow the program to read the files inside this directory"),
+    )
+    .arg(
+        Arg::new("allow-write")
+            .long("allow-write")
+            .takes_value(true)
+            .multiple_occurrences(true)
+            .value_name("DIR")
+            .about("Allow the program to create, write and delete the files inside this directory"),
+    )
+    .arg(
+        Arg::new("allow-env")
+            .long("allow-env")
+            .takes_value(true)
+            .multiple_occurrences(true)
+            .value_name("NAME")
+            .about("Allow the program to read this environment variable"),
+    )
+}
+
+/// Builds the file system permissions requested through the command-line options
+pub fn fs_permissions_from_opts(opts: &ArgMatches) -> FsPermissions {
+    let mut fs_permissions = FsPermissions::default();
+    for dir in opts.values_of("allow-read").into_iter().flatten() {
+        fs_permissions.allow_read(dir);
+    }
+    for dir in opts.values_of("allow-write").into_iter().flatten() {
+        fs_permissions.allow_write(dir);
+    }
+    fs_permissions
+}
+
+/// Builds the environment permissions requested through the command-line options
+pub fn env_permissions_from_opts(opts: &ArgMatches) -> EnvPermissions {
+    let mut env_permissions = EnvPermissions::default();
+    for name in opts.values_of("allow-env").into_iter().flatten() {
+        env_permissions.allow(name);
+    }
+    env_permissions
+}
+
+/// Builds the limits requested through the command-line options
+pub fn limits_from_opts(opts: &ArgMatches) -> Result<Limits, String> {
+    fn parse_opt<T: FromStr>(opts: &ArgMatches, name: &str) -> Result<Option<T>, String> {
+        opts.value_of(name)
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|_| format!("The value of --{} should be a number", name))
+            })
+            .transpose()
+    }
+
+    Ok(Limits {
+        max_instructions: parse_opt(opts, "max-instructions")?,
+        max_call_depth: parse_opt(opts, "max-call-depth")?,
+        max_collection_size: parse_opt(opts, "max-collection-size")?,
+        timeout: parse_opt(opts, "timeout")?.map(Duration::from_millis),
+    })
+}
+
+/// Builds the tracer requested through the command-line options,
+/// `function_entry` finds the first instruction of a function by its name
+pub fn tracer_from_opts<F: Fn(&str) -> Option<usize>>(
+    opts: &ArgMatches,
+    function_entry: F,
+) -> Result<Option<Tracer>, String> {
+    let ranges = opts.values_of("trace-range").into_iter().flatten();
+    let functions = opts.values_of("trace-fn").into_iter().flatten();
+    if !opts.is_present("trace") && ranges.clone().count() + functions.clone().count() == 0 {
+        return Ok(None);
+    }
+
+    let mut tracer = Tracer::new(Box::new(std::io::stderr()));
+    for range in ranges {
+        let parse_ptr = |ptr: &str| {
+            ptr.parse::<usize>()
+                .map_err(|_| format!("Invalid instruction range {}", range))
+        };
+        match range.split_once("..") {
+            Some((start, end)) => tracer.add_range(parse_ptr(start)?, parse_ptr(end)?),
+            None => {
+                let ptr = parse_ptr(range)?;
+                tracer.add_range(ptr, ptr + 1);
+            }
+        }
+    }
+    for function in functions {
+        let entry_ptr = function
+            .parse::<usize>()
+            .ok()
+            .or_else(|| function_entry(function))
+            .ok_or_else(|| format!("Function {} not found", function))?;
+        tracer.add_function(entry_ptr);
+    }
+    Ok(Some(tracer))
+}
+
+/// Formats the error of an execution, ending the process
+/// instead if the program called `exit`
+pub fn check_execution(result: Result<(), EngineError>) -> Result<(), String> {
+    match result {
+        Err(EngineError::RuntimeError(RuntimeError::Exit(status))) => std::process::exit(status),
+        result => result.map_err(|err| err.to_string()),
+    }
+}
+
 pub fn read_file_from_opts(opts: &ArgMatches) -> Result<String, String> {
     let filename = opts.value_of("FILE").unwrap();
     read_to_string(filename).map_err(|e| format!("Couldn't read file: {}", e))
 }
 
+/// Writes the report and the folded stacks measured by a profiler
+/// next to the file passed as an argument
+pub fn write_profile_from_opts(
+    opts: &ArgMatches,
+    profiler: &Profiler,
+    debug_info: &DebugInfo,
+) -> Result<(), String> {
+    let mut path = PathBuf::from_str(opts.value_of("FILE").unwrap()).unwrap();
+    path.set_extension("profile");
+    std::fs::write(&path, profiler.report(Some(debug_info)))
+        .map_err(|e| format!("Couldn't write profile: {}", e))?;
+    path.set_extension("folded");
+    std::fs::write(&path, profiler.folded_stacks(Some(debug_info)))
+        .map_err(|e| format!("Couldn't write folded stacks: {}", e))
+}
+
 pub fn output_file_from_opts(opts: &ArgMatches) -> Result<File, String> {
     let path = opts.value_of("output").map_or_else(
         || {
@@ -0,0 +1,214 @@
+use std::{
+    fs::read_to_string,
+    io::{self, BufRead, Write},
+};
+
+use miniclj_lib::{DebugCommand, DebugHandler, DebugInfo, Debugger, PauseReason, PausedState};
+
+use crate::debugger::{describe_value, frame_name, load};
+
+const HELP: &str = "Commands:
+  break <line> | break @<instruction>    Set a breakpoint (b)
+  delete <line> | delete @<instruction>  Remove a breakpoint (d)
+  breakpoints                            List the breakpoints
+  step                                   Execute one instruction (s)
+  next                                   Step over function calls (n)
+  out                                    Run until the current function returns (o)
+  continue                               Run until the next breakpoint (c)
+  locals                                 Show the variables and temporal values (l)
+  print <symbol>                         Show the value of a global (p)
+  backtrace                              Show the functions being executed (bt)
+  quit                                   Stop the program (q)";
+
+/// Reads the commands of the user from the standard input
+/// every time the execution is paused
+struct ConsoleHandler {
+    source_lines: Vec<String>,
+    debug_info: DebugInfo,
+}
+
+impl ConsoleHandler {
+    fn print_location(&self, state: &PausedState) {
+        let instruction_ptr = state.instruction_ptr();
+        let frame = state.frames().last().unwrap();
+        match self.debug_info.line(instruction_ptr) {
+            Some(line) => {
+                println!(
+                    "at instruction {}, line {} in {}",
+                    instruction_ptr,
+                    line,
+                    frame_name(&self.debug_info, frame)
+                );
+                if let Some(source_line) = self.source_lines.get(line - 1) {
+                    println!("{:>5} | {}", line, source_line);
+                }
+            }
+            None => println!(
+                "at instruction {} in {}",
+                instruction_ptr,
+                frame_name(&self.debug_info, frame)
+            ),
+        }
+        if let Some(instruction) = state.instruction(instruction_ptr) {
+            println!("   => {}", instruction);
+        }
+    }
+
+    /// Parses a breakpoint location, given as a line
+    /// or as an instruction prefixed with `@`
+    fn breakpoint_location(argument: Option<&str>) -> Result<(bool, usize), String> {
+        let argument =
+            argument.ok_or_else(|| String::from("Expected a line or an @instruction"))?;
+        let (is_instruction, number) = match argument.strip_prefix('@') {
+            Some(number) => (true, number),
+            None => (false, argument),
+        };
+        number
+            .parse()
+            .map(|number| (is_instruction, number))
+            .map_err(|_| format!("Invalid location {}", argument))
+    }
+
+    /// Executes a command, returns how to continue the execution
+    /// if the command resumes it
+    fn execute(
+        &self,
+        debugger: &Debugger,
+        state: &PausedState,
+        command: &str,
+        argument: Option<&str>,
+    ) -> Result<Option<DebugCommand>, String> {
+        match command {
+            "b" | "break" => match Self::breakpoint_location(argument)? {
+                (true, instruction_ptr) => {
+                    debugger.set_breakpoint(instruction_ptr);
+                    println!("Breakpoint set at instruction {}", instruction_ptr);
+                }
+                (false, line) => {
+                    let instruction_ptrs = debugger.set_line_breakpoint(line);
+                    if instruction_ptrs.is_empty() {
+                        return Err(format!("Line {} doesn't have instructions", line));
+                    }
+                    println!(
+                        "Breakpoint set at line {} (instructions {:?})",
+                        line, instruction_ptrs
+                    );
+                }
+            },
+            "d" | "delete" => match Self::breakpoint_location(argument)? {
+                (true, instruction_ptr) => debugger.remove_breakpoint(instruction_ptr),
+                (false, line) => debugger.remove_line_breakpoint(line),
+            },
+            "breakpoints" => {
+                for instruction_ptr in debugger.breakpoints() {
+                    match self.debug_info.line(instruction_ptr) {
+                        Some(line) => println!("instruction {}, line {}", instruction_ptr, line),
+                        None => println!("instruction {}", instruction_ptr),
+                    }
+                }
+            }
+            "s" | "step" => return Ok(Some(DebugCommand::Step)),
+            "n" | "next" => return Ok(Some(DebugCommand::StepOver)),
+            "o" | "out" => return Ok(Some(DebugCommand::StepOut)),
+            "c" | "continue" => return Ok(Some(DebugCommand::Continue)),
+            "l" | "locals" => {
+                for (idx, value) in state.vars() {
+                    println!("local[{}] = {}", idx, describe_value(&value));
+                }
+                for (idx, value) in state.temps() {
+                    println!("temp[{}] = {}", idx, describe_value(&value));
+                }
+            }
+            "p" | "print" => {
+                let name = argument.ok_or_else(|| String::from("Expected a symbol"))?;
+                let value = self
+                    .debug_info
+                    .global_address(name)
+                    .ok_or_else(|| format!("Global {} not defined", name))
+                    .map(|address| state.global(&address))?;
+                match value {
+                    Some(value) => println!("{} = {}", name, describe_value(&value)),
+                    None => println!("{} isn't assigned yet", name),
+                }
+            }
+            "bt" | "backtrace" => {
+                for frame in state.frames().iter().rev() {
+                    match self.debug_info.line(frame.instruction_ptr) {
+                        Some(line) => println!(
+                            "{} at instruction {}, line {}",
+                            frame_name(&self.debug_info, frame),
+                            frame.instruction_ptr,
+                            line
+                        ),
+                        None => println!(
+                            "{} at instruction {}",
+                            frame_name(&self.debug_info, frame),
+                            frame.instruction_ptr
+                        ),
+                    }
+                }
+            }
+            "h" | "help" => println!("{}", HELP),
+            "q" | "quit" => std::process::exit(0),
+            _ => return Err(format!("Unknown command {}, try help", command)),
+        }
+        Ok(None)
+    }
+}
+
+impl DebugHandler for ConsoleHandler {
+    fn paused(&mut self, debugger: &Debugger, state: &PausedState) -> DebugCommand {
+        let reason = match state.reason() {
+            PauseReason::Entry => "Paused on entry",
+            PauseReason::Breakpoint => "Paused in breakpoint",
+            PauseReason::Step => "Paused",
+            PauseReason::Interrupt => "Interrupted",
+        };
+        print!("{} ", reason);
+        self.print_location(state);
+
+        let stdin = io::stdin();
+        loop {
+            print!("(debug) ");
+            let _ = io::stdout().flush();
+
+            let mut input = String::new();
+            match stdin.lock().read_line(&mut input) {
+                Ok(0) | Err(_) => std::process::exit(0),
+                Ok(_) => {}
+            }
+            let mut words = input.split_whitespace();
+            let command = match words.next() {
+                Some(command) => command,
+                None => continue,
+            };
+            match self.execute(debugger, state, command, words.next()) {
+                Ok(Some(command)) => return command,
+                Ok(None) => {}
+                Err(err) => println!("{}", err),
+            }
+        }
+    }
+}
+
+/// Compiles a file and executes it, pausing before the first
+/// instruction and giving the control to the user
+pub fn start(filename: &str) -> Result<(), String> {
+    let source = read_to_string(filename).map_err(|e| format!("Couldn't read file: {}", e))?;
+    let (mut vm_state, debug_info) = load(&source)?;
+
+    let handler = ConsoleHandler {
+        source_lines: source.lines().map(String::from).collect(),
+        debug_info: debug_info.clone(),
+    };
+    let debugger = Debugger::new(Box::new(handler), Some(debug_info));
+    debugger.pause_on_entry();
+    vm_state.set_debugger(Some(debugger));
+
+    println!("Type help to see the available commands");
+    let result = vm_state
+        .execute()
+        .map_err(|err| format!("Runtime error: {}", err));
+    println!("Program finished");
+    result
+}
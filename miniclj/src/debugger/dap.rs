@@ -0,0 +1,571 @@
+use std::{
+    fs::read_to_string,
+    io::{self, BufRead, Write},
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use serde_json::{json, Value as Json};
+
+use miniclj_lib::{DebugCommand, DebugHandler, DebugInfo, Debugger, PauseReason, PausedState};
+
+use crate::debugger::{describe_value, frame_name, load};
+
+const THREAD_ID: i64 = 1;
+
+const LOCALS_REFERENCE: i64 = 1;
+const TEMPS_REFERENCE: i64 = 2;
+const GLOBALS_REFERENCE: i64 = 3;
+
+/// Writes the messages sent to the client, numbering them
+struct MessageWriter {
+    seq: i64,
+    output: Box<dyn Write + Send>,
+}
+
+impl MessageWriter {
+    fn send(&mut self, mut message: Json) {
+        self.seq += 1;
+        message["seq"] = json!(self.seq);
+        let content = message.to_string();
+        let _ = write!(
+            self.output,
+            "Content-Length: {}\r\n\r\n{}",
+            content.len(),
+            content
+        );
+        let _ = self.output.flush();
+    }
+
+    fn respond(&mut self, request: &Json, result: Result<Json, String>) {
+        let mut response = json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": result.is_ok(),
+        });
+        match result {
+            Ok(body) => response["body"] = body,
+            Err(message) => response["message"] = json!(message),
+        }
+        self.send(response);
+    }
+
+    fn event(&mut self, event: &str, body: Json) {
+        self.send(json!({ "type": "event", "event": event, "body": body }));
+    }
+}
+
+type SharedWriter = Arc<Mutex<MessageWriter>>;
+
+/// Reads a message with its `Content-Length` header,
+/// returns `None` when the input ends
+fn read_message<R: BufRead>(reader: &mut R) -> Option<Json> {
+    let mut content_length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).ok()? == 0 {
+            return None;
+        }
+        let header = header.trim();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(length) = header.strip_prefix("Content-Length:") {
+            content_length = length.trim().parse::<usize>().ok();
+        }
+    }
+
+    let mut content = vec![0; content_length?];
+    reader.read_exact(&mut content).ok()?;
+    serde_json::from_slice(&content).ok()
+}
+
+/// Sends the text printed by the program as `output` events
+struct OutputWriter(SharedWriter);
+
+impl Write for OutputWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let output = String::from_utf8_lossy(buf).into_owned();
+        self.0
+            .lock()
+            .unwrap()
+            .event("output", json!({ "category": "stdout", "output": output }));
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// State shared between the thread that reads the messages
+/// and the thread that executes the program
+#[derive(Clone)]
+struct Shared {
+    writer: SharedWriter,
+    /// Lines of the breakpoints set by the client that
+    /// haven't been applied to the debugger yet
+    pending_breakpoints: Arc<Mutex<Option<Vec<usize>>>>,
+    pause_requested: Arc<AtomicBool>,
+    interrupt: Arc<Mutex<Option<Arc<AtomicBool>>>>,
+}
+
+impl Shared {
+    fn interrupt(&self) {
+        if let Some(interrupt) = &*self.interrupt.lock().unwrap() {
+            interrupt.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Answers the requests that need the state of the program
+/// while it's paused, and sends the `stopped` events
+struct DapHandler {
+    shared: Shared,
+    requests: Receiver<Json>,
+    program: String,
+    debug_info: DebugInfo,
+    /// The line, the call depth and the command of the last step,
+    /// steps continue until they reach a different line
+    last_step: Option<(Option<usize>, usize, DebugCommand)>,
+}
+
+impl DapHandler {
+    fn apply_pending_breakpoints(&self, debugger: &Debugger) {
+        if let Some(lines) = self.shared.pending_breakpoints.lock().unwrap().take() {
+            debugger.clear_breakpoints();
+            for line in lines {
+                debugger.set_line_breakpoint(line);
+            }
+        }
+    }
+
+    fn stack_trace(&self, state: &PausedState) -> Json {
+        let name = Path::new(&self.program)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let frames = state
+            .frames()
+            .iter()
+            .rev()
+            .enumerate()
+            .map(|(id, frame)| {
+                json!({
+                    "id": id,
+                    "name": frame_name(&self.debug_info, frame),
+                    "line": self.debug_info.line(frame.instruction_ptr).unwrap_or(0),
+                    "column": 1,
+                    "source": { "name": name, "path": self.program },
+                    "instructionPointerReference": frame.instruction_ptr.to_string(),
+                })
+            })
+            .collect::<Vec<Json>>();
+        json!({ "stackFrames": frames, "totalFrames": frames.len() })
+    }
+
+    fn scopes(frame_id: i64) -> Json {
+        let globals = json!({ "name": "Globals", "variablesReference": GLOBALS_REFERENCE, "expensive": false });
+        // The values of the outer frames aren't available
+        let scopes = if frame_id == 0 {
+            vec![
+                json!({ "name": "Locals", "variablesReference": LOCALS_REFERENCE, "expensive": false }),
+                json!({ "name": "Temporaries", "variablesReference": TEMPS_REFERENCE, "expensive": false }),
+                globals,
+            ]
+        } else {
+            vec![globals]
+        };
+        json!({ "scopes": scopes })
+    }
+
+    fn variables(&self, state: &PausedState, reference: i64) -> Json {
+        let variable = |name: String, value: String| json!({ "name": name, "value": value, "variablesReference": 0 });
+        let variables = match reference {
+            LOCALS_REFERENCE => state
+                .vars()
+                .into_iter()
+                .map(|(idx, value)| variable(format!("local[{}]", idx), describe_value(&value)))
+                .collect(),
+            TEMPS_REFERENCE => state
+                .temps()
+                .into_iter()
+                .map(|(idx, value)| variable(format!("temp[{}]", idx), describe_value(&value)))
+                .collect(),
+            GLOBALS_REFERENCE => self
+                .debug_info
+                .globals()
+                .iter()
+                .filter_map(|(name, address)| {
+                    let value = state.global(address)?;
+                    Some(variable(name.to_string(), describe_value(&value)))
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        json!({ "variables": variables })
+    }
+
+    fn evaluate(&self, state: &PausedState, expression: &str) -> Result<Json, String> {
+        let address = self
+            .debug_info
+            .global_address(expression.trim())
+            .ok_or_else(|| String::from("Only global symbols can be evaluated"))?;
+        let value = state
+            .global(&address)
+            .ok_or_else(|| format!("{} isn't assigned yet", expression.trim()))?;
+        Ok(json!({ "result": describe_value(&value), "variablesReference": 0 }))
+    }
+}
+
+impl DebugHandler for DapHandler {
+    fn paused(&mut self, debugger: &Debugger, state: &PausedState) -> DebugCommand {
+        self.apply_pending_breakpoints(debugger);
+
+        let line = self.debug_info.line(state.instruction_ptr());
+        let depth = state.frames().len();
+        let reason = match state.reason() {
+            PauseReason::Entry => "entry",
+            PauseReason::Breakpoint => "breakpoint",
+            PauseReason::Step => match self.last_step {
+                // Editors step by lines, so the steps inside a line are skipped
+                Some((last_line, last_depth, command))
+                    if (line == last_line || line.is_none()) && depth == last_depth =>
+                {
+                    return command
+                }
+                _ => "step",
+            },
+            PauseReason::Interrupt => {
+                if !self.shared.pause_requested.swap(false, Ordering::Relaxed) {
+                    return DebugCommand::Continue;
+                }
+                "pause"
+            }
+        };
+        self.last_step = None;
+        self.shared.writer.lock().unwrap().event(
+            "stopped",
+            json!({ "reason": reason, "threadId": THREAD_ID, "allThreadsStopped": true }),
+        );
+
+        loop {
+            let request = match self.requests.recv() {
+                Ok(request) => request,
+                Err(_) => std::process::exit(0),
+            };
+            let arguments = &request["arguments"];
+            let (result, command) = match request["command"].as_str().unwrap_or_default() {
+                "stackTrace" => (Ok(self.stack_trace(state)), None),
+                "scopes" => (
+                    Ok(Self::scopes(arguments["frameId"].as_i64().unwrap_or(0))),
+                    None,
+                ),
+                "variables" => (
+                    Ok(self
+                        .variables(state, arguments["variablesReference"].as_i64().unwrap_or(0))),
+                    None,
+                ),
+                "evaluate" => (
+                    self.evaluate(state, arguments["expression"].as_str().unwrap_or_default()),
+                    None,
+                ),
+                "continue" => (
+                    Ok(json!({ "allThreadsContinued": true })),
+                    Some(DebugCommand::Continue),
+                ),
+                "next" => (Ok(json!({})), Some(DebugCommand::StepOver)),
+                "stepIn" => (Ok(json!({})), Some(DebugCommand::Step)),
+                "stepOut" => (Ok(json!({})), Some(DebugCommand::StepOut)),
+                "setBreakpoints" => {
+                    self.apply_pending_breakpoints(debugger);
+                    continue;
+                }
+                command => (Err(format!("Unsupported request {}", command)), None),
+            };
+            self.shared.writer.lock().unwrap().respond(&request, result);
+
+            if let Some(command) = command {
+                if command != DebugCommand::Continue {
+                    self.last_step = Some((line, depth, command));
+                }
+                return command;
+            }
+        }
+    }
+}
+
+/// Executes the program in a new thread, which receives
+/// the requests that need the state of the program
+fn launch(shared: Shared, program: String, stop_on_entry: bool) -> Sender<Json> {
+    let (sender, requests) = mpsc::channel();
+    thread::spawn(move || {
+        let source = read_to_string(&program).unwrap_or_default();
+        let (mut vm_state, debug_info) = match load(&source) {
+            Ok(loaded) => loaded,
+            Err(err) => {
+                let mut writer = shared.writer.lock().unwrap();
+                writer.event(
+                    "output",
+                    json!({ "category": "stderr", "output": err + "\n" }),
+                );
+                writer.event("terminated", json!({}));
+                return;
+            }
+        };
+
+        let handler = DapHandler {
+            shared: shared.clone(),
+            requests,
+            program,
+            debug_info: debug_info.clone(),
+            last_step: None,
+        };
+        let debugger = Debugger::new(Box::new(handler), Some(debug_info));
+        if stop_on_entry {
+            debugger.pause_on_entry();
+        }
+        if let Some(lines) = shared.pending_breakpoints.lock().unwrap().take() {
+            for line in lines {
+                debugger.set_line_breakpoint(line);
+            }
+        }
+        *shared.interrupt.lock().unwrap() = Some(debugger.interrupt_handle());
+        vm_state.set_debugger(Some(debugger));
+        vm_state.set_io(Box::new(OutputWriter(shared.writer.clone())));
+
+        let exit_code = match vm_state.execute() {
+            Ok(()) => 0,
+            Err(err) => {
+                shared.writer.lock().unwrap().event(
+                    "output",
+                    json!({ "category": "stderr", "output": format!("Runtime error: {}\n", err) }),
+                );
+                1
+            }
+        };
+        let mut writer = shared.writer.lock().unwrap();
+        writer.event("exited", json!({ "exitCode": exit_code }));
+        writer.event("terminated", json!({}));
+    });
+    sender
+}
+
+/// Starts the server, which communicates through stdin and stdout
+pub fn start() -> Result<(), String> {
+    let shared = Shared {
+        writer: Arc::new(Mutex::new(MessageWriter {
+            seq: 0,
+            output: Box::new(io::stdout()),
+        })),
+        pending_breakpoints: Arc::new(Mutex::new(None)),
+        pause_requested: Arc::new(AtomicBool::new(false)),
+        interrupt: Arc::new(Mutex::new(None)),
+    };
+    let mut program = None;
+    let mut stop_on_entry = false;
+    let mut debug_info = DebugInfo::default();
+    let mut program_requests: Option<Sender<Json>> = None;
+
+    let stdin = io::stdin();
+    let mut reader = stdin.lock();
+    while let Some(request) = read_message(&mut reader) {
+        let arguments = &request["arguments"];
+        let respond = |result: Result<Json, String>| {
+            shared.writer.lock().unwrap().respond(&request, result);
+        };
+        match request["command"].as_str().unwrap_or_default() {
+            "initialize" => {
+                respond(Ok(json!({
+                    "supportsConfigurationDoneRequest": true,
+                    "supportsTerminateRequest": true,
+                })));
+                shared
+                    .writer
+                    .lock()
+                    .unwrap()
+                    .event("initialized", json!({}));
+            }
+            "launch" => {
+                let path = arguments["program"].as_str().unwrap_or_default();
+                let result = read_to_string(path)
+                    .map_err(|e| format!("Couldn't read file {}: {}", path, e))
+                    .and_then(|source| load(&source).map(|(_, info)| info));
+                match result {
+                    Ok(info) => {
+                        debug_info = info;
+                        program = Some(path.to_string());
+                        stop_on_entry = arguments["stopOnEntry"].as_bool().unwrap_or(false);
+                        respond(Ok(json!({})));
+                    }
+                    Err(err) => respond(Err(err)),
+                }
+            }
+            "setBreakpoints" => {
+                let lines = arguments["breakpoints"]
+                    .as_array()
+                    .map(|breakpoints| {
+                        breakpoints
+                            .iter()
+                            .filter_map(|breakpoint| breakpoint["line"].as_u64())
+                            .map(|line| line as usize)
+                            .collect::<Vec<usize>>()
+                    })
+                    .unwrap_or_default();
+                let breakpoints = lines
+                    .iter()
+                    .map(|line| {
+                        let verified = !debug_info.line_instructions(*line).is_empty();
+                        json!({ "verified": verified, "line": line })
+                    })
+                    .collect::<Vec<Json>>();
+                *shared.pending_breakpoints.lock().unwrap() = Some(lines);
+                respond(Ok(json!({ "breakpoints": breakpoints })));
+
+                // The breakpoints are applied by the program thread
+                // the next time it pauses, which is forced here
+                if let Some(sender) = &program_requests {
+                    let _ = sender.send(request.clone());
+                    shared.interrupt();
+                }
+            }
+            "configurationDone" => {
+                respond(Ok(json!({})));
+                if let (Some(program), None) = (&program, &program_requests) {
+                    program_requests = Some(launch(shared.clone(), program.clone(), stop_on_entry));
+                }
+            }
+            "threads" => respond(Ok(json!({
+                "threads": [{ "id": THREAD_ID, "name": "main" }]
+            }))),
+            "pause" => {
+                respond(Ok(json!({})));
+                shared.pause_requested.store(true, Ordering::Relaxed);
+                shared.interrupt();
+            }
+            "disconnect" | "terminate" => {
+                respond(Ok(json!({})));
+                std::process::exit(0);
+            }
+            "stackTrace" | "scopes" | "variables" | "evaluate" | "continue" | "next" | "stepIn"
+            | "stepOut" => match &program_requests {
+                Some(sender) => {
+                    let _ = sender.send(request.clone());
+                }
+                None => respond(Err(String::from("The program isn't running"))),
+            },
+            command => respond(Err(format!("Unsupported request {}", command))),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    /// Output of the messages that can be read by the test
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn request(seq: i64, command: &str) -> Json {
+        json!({ "seq": seq, "type": "request", "command": command, "arguments": {} })
+    }
+
+    #[test]
+    fn test_read_message() {
+        let input = "Content-Length: 10\r\nOther: header\r\n\r\n{\"seq\": 1}\r\n";
+        let mut reader = Cursor::new(input);
+        assert_eq!(read_message(&mut reader), Some(json!({ "seq": 1 })));
+        assert_eq!(read_message(&mut reader), None);
+        assert_eq!(read_message(&mut Cursor::new("\r\n{}")), None);
+    }
+
+    #[test]
+    fn test_steps() {
+        let source = "(defn f [x]\n  (* x 2))\n(def y (f 3))\n(+ y 1)";
+        let (mut vm_state, debug_info) = load(source).unwrap();
+        let output = SharedBuffer::default();
+        let shared = Shared {
+            writer: Arc::new(Mutex::new(MessageWriter {
+                seq: 0,
+                output: Box::new(output.clone()),
+            })),
+            pending_breakpoints: Arc::new(Mutex::new(None)),
+            pause_requested: Arc::new(AtomicBool::new(false)),
+            interrupt: Arc::new(Mutex::new(None)),
+        };
+
+        let (sender, requests) = mpsc::channel();
+        let commands = [
+            "next",
+            "stepIn",
+            "stackTrace",
+            "stepOut",
+            "next",
+            "continue",
+        ];
+        for (seq, command) in commands.iter().enumerate() {
+            sender.send(request(seq as i64, command)).unwrap();
+        }
+        let handler = DapHandler {
+            shared,
+            requests,
+            program: String::from("/tmp/program.clj"),
+            debug_info: debug_info.clone(),
+            last_step: None,
+        };
+        let debugger = Debugger::new(Box::new(handler), Some(debug_info));
+        debugger.pause_on_entry();
+        vm_state.set_debugger(Some(debugger));
+        vm_state.execute().unwrap();
+
+        let output = output.0.lock().unwrap().clone();
+        let mut reader = Cursor::new(output);
+        let mut stops = Vec::new();
+        let mut stack_trace = None;
+        while let Some(message) = read_message(&mut reader) {
+            if message["event"] == "stopped" {
+                stops.push(message["body"]["reason"].as_str().unwrap().to_string());
+            } else if message["command"] == "stackTrace" {
+                stack_trace = Some(message["body"]["stackFrames"].clone());
+            }
+        }
+        // The steps skip the instructions in the same line
+        assert_eq!(stops, vec!["entry", "step", "step", "step", "step"]);
+        let stack_trace = stack_trace.unwrap();
+        let frames = stack_trace
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|frame| {
+                (
+                    frame["name"].as_str().unwrap(),
+                    frame["line"].as_u64().unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(frames, vec![("f", 2), ("<top level>", 3)]);
+        // The handler exits the process if the requests are closed while paused
+        drop(sender);
+    }
+}
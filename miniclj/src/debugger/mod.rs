@@ -0,0 +1,44 @@
+use std::collections::HashMap;
+
+use miniclj_lib::{CompilerState, DebugInfo, Frame, SExprsParser, VMState, Value};
+
+/// Implements the interactive debugger used from the terminal
+pub mod console;
+/// Implements a server for the Debug Adapter Protocol,
+/// used by editors to debug programs
+pub mod dap;
+
+/// Compiles a source file, returning the virtual machine ready to
+/// execute it and the debug information of its instructions
+fn load(source: &str) -> Result<(VMState, DebugInfo), String> {
+    let tree = SExprsParser::parse(source).map_err(|e| format!("{}", e))?;
+
+    let mut compiler_state = CompilerState::default();
+    for expr in tree {
+        compiler_state
+            .compile(expr)
+            .map_err(|err| format!("Compilation error: {}", err))?;
+    }
+
+    let debug_info = compiler_state.debug_info(source);
+    let (constants_rev, instructions) = compiler_state.into_parts();
+    let constants = constants_rev
+        .into_iter()
+        .map(|(constant, address)| (address, constant))
+        .collect::<HashMap<_, _>>();
+    Ok((VMState::new(constants, instructions), debug_info))
+}
+
+/// Returns the name of the function being executed in a frame
+fn frame_name(debug_info: &DebugInfo, frame: &Frame) -> String {
+    match frame.entry_ptr {
+        Some(entry_ptr) => debug_info
+            .function_name(entry_ptr)
+            .map_or_else(|| format!("fn@{}", entry_ptr), String::from),
+        None => String::from("<top level>"),
+    }
+}
+
+fn describe_value(value: &Value) -> String {
+    format!("{}", value.readable())
+}
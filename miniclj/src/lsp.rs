@@ -0,0 +1,506 @@
+use std::collections::HashMap;
+
+use lalrpop_util::ParseError;
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::{
+    notification::{
+        DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument,
+        Notification as LspNotification, PublishDiagnostics,
+    },
+    request::{Completion, GotoDefinition, HoverRequest, Request as LspRequest},
+    CompletionItem, CompletionItemKind, CompletionOptions, CompletionParams, CompletionResponse,
+    Diagnostic, DiagnosticSeverity, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverContents,
+    HoverParams, HoverProviderCapability, Location, MarkupContent, MarkupKind, OneOf, Position,
+    PublishDiagnosticsParams, Range, ServerCapabilities, TextDocumentSyncCapability,
+    TextDocumentSyncKind, Uri,
+};
+use miniclj_lib::{CompilerState, Literal, SExpr, SExprsParser};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// A global symbol defined with `def` or `defn`
+struct Definition {
+    /// Range of the symbol's name in the `def` or `defn` expression
+    range: Range,
+    /// First line of the expression that defined the symbol
+    header: String,
+}
+
+/// Stores the contents of an open file and the information
+/// extracted from compiling it
+struct Document {
+    text: String,
+    compiler_state: CompilerState,
+    diagnostics: Vec<Diagnostic>,
+    definitions: HashMap<String, Definition>,
+}
+
+impl Document {
+    fn new(text: String) -> Document {
+        let mut document = Document {
+            text,
+            compiler_state: CompilerState::default(),
+            diagnostics: Vec::new(),
+            definitions: HashMap::new(),
+        };
+        if !document.text.trim().is_empty() {
+            document.analyze();
+        }
+        document
+    }
+
+    /// Compiles every top-level expression of the file, saving the
+    /// errors found and the symbols defined in the global scope
+    fn analyze(&mut self) {
+        let exprs = match SExprsParser::parse_spanned(&self.text) {
+            Ok(exprs) => exprs,
+            Err(err) => {
+                let (start, end) = match &err {
+                    ParseError::InvalidToken { location }
+                    | ParseError::UnrecognizedEOF { location, .. } => (*location, *location),
+                    ParseError::UnrecognizedToken {
+                        token: (start, _, end),
+                        ..
+                    }
+                    | ParseError::ExtraToken {
+                        token: (start, _, end),
+                    } => (*start, *end),
+                    ParseError::User { error } => (error.start, error.end),
+                };
+                let range = Range::new(self.position(start), self.position(end));
+                self.diagnostics
+                    .push(new_diagnostic(range, err.to_string()));
+                return;
+            }
+        };
+
+        for (start, expr, end) in exprs {
+            let defined_symbol = defined_symbol(&expr);
+            let checkpoint = self.compiler_state.checkpoint();
+            if let Err(err) = self.compiler_state.compile(expr) {
+                self.compiler_state.rollback(checkpoint);
+                let range = Range::new(self.position(start), self.position(end));
+                self.diagnostics
+                    .push(new_diagnostic(range, err.to_string()));
+                continue;
+            }
+
+            if let Some((keyword, symbol)) = defined_symbol {
+                if self.compiler_state.get_symbol(&symbol).is_none() {
+                    continue;
+                }
+                let expr_text = &self.text[start..end];
+                let after_keyword = expr_text.find(keyword).map_or(0, |idx| idx + keyword.len());
+                let symbol_start = expr_text[after_keyword..]
+                    .find(&symbol)
+                    .map_or(start, |idx| start + after_keyword + idx);
+                let range = Range::new(
+                    self.position(symbol_start),
+                    self.position(symbol_start + symbol.len()),
+                );
+                let header = expr_text.lines().next().unwrap_or_default().to_string();
+                self.definitions
+                    .insert(symbol, Definition { range, header });
+            }
+        }
+    }
+
+    /// Converts a byte offset into a position with a
+    /// line and a character counted in UTF-16 units
+    fn position(&self, offset: usize) -> Position {
+        let mut line = 0;
+        let mut character = 0;
+        for (idx, c) in self.text.char_indices() {
+            if idx >= offset {
+                break;
+            }
+            if c == '\n' {
+                line += 1;
+                character = 0;
+            } else {
+                character += c.len_utf16() as u32;
+            }
+        }
+        Position::new(line, character)
+    }
+
+    /// Converts a position into a byte offset
+    fn offset(&self, position: Position) -> usize {
+        let mut line = 0;
+        let mut character = 0;
+        for (idx, c) in self.text.char_indices() {
+            if line == position.line && (character >= position.character || c == '\n') {
+                return idx;
+            }
+            if c == '\n' {
+                line += 1;
+                character = 0;
+            } else {
+                character += c.len_utf16() as u32;
+            }
+        }
+        self.text.len()
+    }
+
+    /// Returns the byte offsets of the symbol around a position
+    fn symbol_at(&self, position: Position) -> (usize, usize) {
+        let offset = self.offset(position);
+        let start = self.text[..offset]
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| is_symbol_char(*c))
+            .last()
+            .map_or(offset, |(idx, _)| idx);
+        let end = self.text[offset..]
+            .char_indices()
+            .find(|(_, c)| !is_symbol_char(*c))
+            .map_or(self.text.len(), |(idx, _)| offset + idx);
+        (start, end)
+    }
+}
+
+fn is_symbol_char(c: char) -> bool {
+    !c.is_whitespace() && !"()[]{}\"#@'`,;".contains(c)
+}
+
+/// Returns the keyword and the symbol if the expression
+/// is a call to `def` or `defn`
+fn defined_symbol(expr: &SExpr) -> Option<(&'static str, String)> {
+    if let SExpr::Expr(exprs, _) = expr {
+        if let [SExpr::Literal(Literal::Symbol(callable)), SExpr::Literal(Literal::Symbol(symbol)), ..] =
+            exprs.as_slice()
+        {
+            match callable.as_str() {
+                "def" => return Some(("def", symbol.to_string())),
+                "defn" => return Some(("defn", symbol.to_string())),
+                _ => {}
+            }
+        }
+    }
+    None
+}
+
+fn new_diagnostic(range: Range, message: String) -> Diagnostic {
+    Diagnostic {
+        range,
+        severity: Some(DiagnosticSeverity::ERROR),
+        source: Some(String::from("miniclj")),
+        message,
+        ..Diagnostic::default()
+    }
+}
+
+struct Server {
+    connection: Connection,
+    documents: HashMap<Uri, Document>,
+}
+
+impl Server {
+    fn main_loop(&mut self) -> Result<(), String> {
+        while let Ok(message) = self.connection.receiver.recv() {
+            match message {
+                Message::Request(request) => {
+                    let is_shutdown = self
+                        .connection
+                        .handle_shutdown(&request)
+                        .map_err(|err| format!("Language server error: {}", err))?;
+                    if is_shutdown {
+                        return Ok(());
+                    }
+                    self.handle_request(request)?;
+                }
+                Message::Notification(notification) => self.handle_notification(notification)?,
+                Message::Response(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn send(&self, message: Message) -> Result<(), String> {
+        self.connection
+            .sender
+            .send(message)
+            .map_err(|err| format!("Language server error: {}", err))
+    }
+
+    fn handle_request(&self, request: Request) -> Result<(), String> {
+        let Request { id, method, params } = request;
+        let response = match method.as_str() {
+            Completion::METHOD => respond(id, params, |params| self.completion(params)),
+            HoverRequest::METHOD => respond(id, params, |params| self.hover(params)),
+            GotoDefinition::METHOD => respond(id, params, |params| self.definition(params)),
+            _ => Response::new_err(
+                id,
+                ErrorCode::MethodNotFound as i32,
+                format!("Unsupported method {}", method),
+            ),
+        };
+        self.send(response.into())
+    }
+
+    fn handle_notification(&mut self, notification: Notification) -> Result<(), String> {
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                if let Ok(params) =
+                    serde_json::from_value::<DidOpenTextDocumentParams>(notification.params)
+                {
+                    let document = Document::new(params.text_document.text);
+                    self.update_document(params.text_document.uri, document)?;
+                }
+            }
+            DidChangeTextDocument::METHOD => {
+                if let Ok(params) =
+                    serde_json::from_value::<DidChangeTextDocumentParams>(notification.params)
+                {
+                    // The whole file is sent on every change
+                    if let Some(change) = params.content_changes.into_iter().last() {
+                        let document = Document::new(change.text);
+                        self.update_document(params.text_document.uri, document)?;
+                    }
+                }
+            }
+            DidCloseTextDocument::METHOD => {
+                if let Ok(params) =
+                    serde_json::from_value::<DidCloseTextDocumentParams>(notification.params)
+                {
+                    self.documents.remove(&params.text_document.uri);
+                    self.publish_diagnostics(params.text_document.uri, Vec::new())?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn update_document(&mut self, uri: Uri, document: Document) -> Result<(), String> {
+        let diagnostics = document.diagnostics.clone();
+        self.documents.insert(uri.clone(), document);
+        self.publish_diagnostics(uri, diagnostics)
+    }
+
+    fn publish_diagnostics(&self, uri: Uri, diagnostics: Vec<Diagnostic>) -> Result<(), String> {
+        let params = PublishDiagnosticsParams::new(uri, diagnostics, None);
+        let notification = Notification::new(PublishDiagnostics::METHOD.to_string(), params);
+        self.send(notification.into())
+    }
+
+    fn completion(&self, params: CompletionParams) -> Option<CompletionResponse> {
+        let position = params.text_document_position;
+        let document = self.documents.get(&position.text_document.uri)?;
+        let (start, _) = document.symbol_at(position.position);
+        let prefix = &document.text[start..document.offset(position.position)];
+
+        let callables = document
+            .compiler_state
+            .callable_names()
+            .into_iter()
+            .filter(|name| name.starts_with(prefix))
+            .map(|name| CompletionItem {
+                label: name.to_string(),
+                kind: Some(CompletionItemKind::FUNCTION),
+                detail: document.compiler_state.callable_signature(name),
+                ..CompletionItem::default()
+            });
+        let definitions = document
+            .definitions
+            .iter()
+            .filter(|(name, _)| name.starts_with(prefix))
+            .map(|(name, definition)| CompletionItem {
+                label: name.clone(),
+                kind: Some(CompletionItemKind::VARIABLE),
+                detail: Some(definition.header.clone()),
+                ..CompletionItem::default()
+            });
+        Some(CompletionResponse::Array(
+            definitions.chain(callables).collect(),
+        ))
+    }
+
+    fn hover(&self, params: HoverParams) -> Option<Hover> {
+        let position = params.text_document_position_params;
+        let document = self.documents.get(&position.text_document.uri)?;
+        let (start, end) = document.symbol_at(position.position);
+        let symbol = &document.text[start..end];
+
+        // User definitions take priority over the language callables
+        let code = match document.definitions.get(symbol) {
+            Some(definition) => definition.header.clone(),
+            None => document.compiler_state.callable_signature(symbol)?,
+        };
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: format!("```clojure\n{}\n```", code),
+            }),
+            range: Some(Range::new(document.position(start), document.position(end))),
+        })
+    }
+
+    fn definition(&self, params: GotoDefinitionParams) -> Option<GotoDefinitionResponse> {
+        let position = params.text_document_position_params;
+        let uri = position.text_document.uri;
+        let document = self.documents.get(&uri)?;
+        let (start, end) = document.symbol_at(position.position);
+        let definition = document.definitions.get(&document.text[start..end])?;
+        Some(GotoDefinitionResponse::Scalar(Location::new(
+            uri.clone(),
+            definition.range,
+        )))
+    }
+}
+
+/// Deserializes the parameters of a request, and responds
+/// with the result of calling `handler` with them
+fn respond<P, R, F>(id: lsp_server::RequestId, params: serde_json::Value, handler: F) -> Response
+where
+    P: DeserializeOwned,
+    R: Serialize,
+    F: FnOnce(P) -> R,
+{
+    match serde_json::from_value::<P>(params) {
+        Ok(params) => Response::new_ok(id, handler(params)),
+        Err(err) => Response::new_err(id, ErrorCode::InvalidParams as i32, err.to_string()),
+    }
+}
+
+/// Starts a language server that communicates with
+/// the editor through the standard input and output
+pub fn start() -> Result<(), String> {
+    let (connection, io_threads) = Connection::stdio();
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        completion_provider: Some(CompletionOptions::default()),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        definition_provider: Some(OneOf::Left(true)),
+        ..ServerCapabilities::default()
+    };
+    let capabilities = serde_json::to_value(capabilities)
+        .map_err(|err| format!("Language server error: {}", err))?;
+    connection
+        .initialize(capabilities)
+        .map_err(|err| format!("Language server error: {}", err))?;
+
+    let mut server = Server {
+        connection,
+        documents: HashMap::new(),
+    };
+    server.main_loop()?;
+    drop(server);
+    io_threads
+        .join()
+        .map_err(|err| format!("Language server error: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::{TextDocumentIdentifier, TextDocumentItem, TextDocumentPositionParams};
+
+    use super::*;
+
+    fn open(text: &str) -> (Server, Connection, Uri) {
+        let (connection, client) = Connection::memory();
+        let mut server = Server {
+            connection,
+            documents: HashMap::new(),
+        };
+        let uri = "file:///test.clj".parse::<Uri>().unwrap();
+        let params = DidOpenTextDocumentParams {
+            text_document: TextDocumentItem::new(
+                uri.clone(),
+                String::from("clojure"),
+                1,
+                String::from(text),
+            ),
+        };
+        let notification = Notification::new(DidOpenTextDocument::METHOD.to_string(), params);
+        server.handle_notification(notification).unwrap();
+        (server, client, uri)
+    }
+
+    fn published_diagnostics(client: &Connection) -> Vec<Diagnostic> {
+        match client.receiver.try_recv() {
+            Ok(Message::Notification(notification)) => {
+                serde_json::from_value::<PublishDiagnosticsParams>(notification.params)
+                    .unwrap()
+                    .diagnostics
+            }
+            message => panic!("Unexpected message {:?}", message),
+        }
+    }
+
+    fn position_params(uri: &Uri, line: u32, character: u32) -> TextDocumentPositionParams {
+        TextDocumentPositionParams::new(
+            TextDocumentIdentifier::new(uri.clone()),
+            Position::new(line, character),
+        )
+    }
+
+    fn range(start: (u32, u32), end: (u32, u32)) -> Range {
+        Range::new(Position::new(start.0, start.1), Position::new(end.0, end.1))
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let (_server, client, _) = open("(def x 1)\n(+ x y)");
+        let diagnostics = published_diagnostics(&client);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range, range((1, 0), (1, 7)));
+
+        let (_server, client, _) = open("(def x\n  3/0)");
+        let diagnostics = published_diagnostics(&client);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range, range((1, 2), (1, 5)));
+        assert_eq!(diagnostics[0].message, "Fraction with a zero denominator");
+
+        let (_server, client, _) = open("(def x 1)\n(def y (+ x 1))");
+        assert!(published_diagnostics(&client).is_empty());
+    }
+
+    #[test]
+    fn test_hover_and_definition() {
+        let (server, _client, uri) = open("(defn add [a b]\n  (+ a b))\n(add 1 2)");
+
+        let hover = server
+            .hover(HoverParams {
+                text_document_position_params: position_params(&uri, 2, 2),
+                work_done_progress_params: Default::default(),
+            })
+            .unwrap();
+        assert_eq!(hover.range, Some(range((2, 1), (2, 4))));
+        match hover.contents {
+            HoverContents::Markup(markup) => {
+                assert_eq!(markup.value, "```clojure\n(defn add [a b]\n```")
+            }
+            contents => panic!("Unexpected contents {:?}", contents),
+        }
+        let hover = server.hover(HoverParams {
+            text_document_position_params: position_params(&uri, 1, 3),
+            work_done_progress_params: Default::default(),
+        });
+        assert!(hover.is_some());
+        let hover = server.hover(HoverParams {
+            text_document_position_params: position_params(&uri, 2, 6),
+            work_done_progress_params: Default::default(),
+        });
+        assert!(hover.is_none());
+
+        let definition = server.definition(GotoDefinitionParams {
+            text_document_position_params: position_params(&uri, 2, 3),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        });
+        assert_eq!(
+            definition,
+            Some(GotoDefinitionResponse::Scalar(Location::new(
+                uri.clone(),
+                range((0, 6), (0, 9))
+            )))
+        );
+        let definition = server.definition(GotoDefinitionParams {
+            text_document_position_params: position_params(&uri, 1, 3),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        });
+        assert_eq!(definition, None);
+    }
+}
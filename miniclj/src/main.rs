@@ -1,11 +1,40 @@
-use miniclj_lib::{BytecodeParser, CompilerState, SExprsParser, VMState};
+use std::fs::{read_to_string, write};
+
+use miniclj_lib::{
+    BytecodeParser, CodeFormatter, CompilerState, Engine, EngineError, Profiler, SExprsParser,
+    VMState,
+};
 
 /// This module exposes the `clap` `App` used to parse arguments
 /// passed through the command-line interface, and shared
 /// functionality between multiple subcommands
 mod cli;
+/// This module implements the step debugger, both as an interactive
+/// console and as a server for the Debug Adapter Protocol
+mod debugger;
+/// This module implements a server for the Language Server Protocol,
+/// used by editors to show errors and information about the code
+mod lsp;
+/// This module implements a server for the nREPL protocol,
+/// used by editors to evaluate code
+mod nrepl;
+/// This module implements the interactive REPL, which compiles and
+/// executes expressions as they are written
+mod repl;
+/// This module implements the script mode, which runs a file
+/// passed directly as the first argument
+mod script;
+/// This module exposes the session shared by the REPL and the
+/// nREPL server, which compiles and executes code incrementally
+mod session;
+/// This module implements the test runner, which executes the
+/// tests defined with `deftest` and reports their results
+mod testrunner;
 
-use crate::cli::{args, output_file_from_opts, read_file_from_opts};
+use crate::cli::{
+    args, check_execution, env_permissions_from_opts, fs_permissions_from_opts, limits_from_opts,
+    output_file_from_opts, read_file_from_opts, tracer_from_opts, write_profile_from_opts,
+};
 
 /// The entry point for the command-line interface
 fn main() -> Result<(), String> {
@@ -41,37 +70,89 @@ fn main() -> Result<(), String> {
                 .write_to(&mut output_file)
                 .map_err(|err| format!("File error: {}", err))?;
         }
+        ("fmt", opts) => {
+            let check = opts.is_present("check");
+            let mut unformatted_files = Vec::new();
+            for filename in opts.values_of("FILE").unwrap() {
+                let input = read_to_string(filename)
+                    .map_err(|e| format!("Couldn't read file {}: {}", filename, e))?;
+                let output = CodeFormatter::format(&input)
+                    .map_err(|e| format!("Couldn't format file {}: {}", filename, e))?;
+                if input == output {
+                    continue;
+                }
+                if check {
+                    println!("{} isn't formatted", filename);
+                    unformatted_files.push(filename);
+                } else {
+                    write(filename, output)
+                        .map_err(|e| format!("Couldn't write file {}: {}", filename, e))?;
+                }
+            }
+            if !unformatted_files.is_empty() {
+                return Err(format!(
+                    "{} file(s) aren't formatted",
+                    unformatted_files.len()
+                ));
+            }
+        }
         ("exec", opts) => {
             let input = read_file_from_opts(opts)?;
             let (constants, instructions) =
                 BytecodeParser::parse(&input).map_err(|e| format!("Bytecode error: {}", e))?;
 
-            VMState::new(constants, instructions)
-                .execute()
-                .map_err(|err| format!("Runtime error: {}", err))?;
+            // Bytecode files don't keep the names of the functions
+            let tracer = tracer_from_opts(opts, |_| None)?;
+            let mut vm_state = VMState::new(constants, instructions);
+            vm_state.set_tracer(tracer);
+            vm_state.set_limits(limits_from_opts(opts)?);
+            vm_state.set_fs_permissions(fs_permissions_from_opts(opts));
+            vm_state.set_env_permissions(env_permissions_from_opts(opts));
+            check_execution(vm_state.execute().map_err(EngineError::from))?;
         }
         ("run", opts) => {
             let input = read_file_from_opts(opts)?;
-            let tree = SExprsParser::parse(&input).map_err(|e| format!("{}", e))?;
+            let mut engine = Engine::new();
+            let entry_ptr = engine.load_str(&input).map_err(|err| err.to_string())?;
 
-            let mut compiler_state = CompilerState::default();
-            for expr in tree {
-                compiler_state
-                    .compile(expr)
-                    .map_err(|err| format!("Compilation error: {}", err))?;
+            let compiler_state = engine.compiler_state();
+            let tracer = tracer_from_opts(opts, |name| compiler_state.function_entry(name))?;
+            let debug_info = compiler_state.debug_info(&input);
+            let vm_state = engine.vm_state_mut();
+            vm_state.set_tracer(tracer);
+            vm_state.set_limits(limits_from_opts(opts)?);
+            vm_state.set_fs_permissions(fs_permissions_from_opts(opts));
+            vm_state.set_env_permissions(env_permissions_from_opts(opts));
+            if opts.is_present("profile") {
+                vm_state.set_profiler(Some(Profiler::default()));
             }
-
-            let (constants_rev, instructions) = compiler_state.into_parts();
-            let constants = constants_rev
-                .into_iter()
-                .map(|(constant, address)| (address, constant))
-                .collect();
-
-            VMState::new(constants, instructions)
-                .execute()
-                .map_err(|err| format!("Runtime error: {}", err))?;
+            let result = engine.execute_from(entry_ptr);
+            if let Some(profiler) = engine.vm_state().profiler() {
+                profiler.finish();
+                write_profile_from_opts(opts, profiler, &debug_info)?;
+            }
+            check_execution(result)?;
+        }
+        ("dap", _) => return debugger::dap::start(),
+        ("debug", opts) => return debugger::console::start(opts.value_of("FILE").unwrap()),
+        ("lsp", _) => return lsp::start(),
+        ("nrepl", opts) => {
+            let port = opts
+                .value_of("port")
+                .unwrap()
+                .parse()
+                .map_err(|_| String::from("The port should be a number between 0 and 65535"))?;
+            return nrepl::start(port);
+        }
+        ("repl", _) => return repl::start(),
+        ("test", opts) => {
+            let paths = opts.values_of("PATH").unwrap().collect();
+            return testrunner::run(paths, opts.value_of("junit"));
+        }
+        (filename, opts) => {
+            let args = opts.values_of("").into_iter().flatten().collect();
+            std::process::exit(script::run(filename, args));
         }
-        (_, _) => unreachable!(),
     }
 
     let execution_time = start_time.elapsed();
@@ -0,0 +1,227 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, BufRead, Error, ErrorKind, Write},
+};
+
+/// The longest string accepted from a peer, so that a message can't
+/// make the server allocate an arbitrary amount of memory
+const MAX_BYTES_LEN: usize = 16 * 1024 * 1024;
+
+/// The deepest nesting of lists and dictionaries accepted from a peer,
+/// so that a message can't overflow the stack of the server
+const MAX_DEPTH: usize = 64;
+
+/// A value encoded with bencode, the format used by the messages of nREPL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Bencode {
+    Integer(i64),
+    Bytes(Vec<u8>),
+    List(Vec<Bencode>),
+    Dict(BTreeMap<Vec<u8>, Bencode>),
+}
+
+impl Bencode {
+    /// Reads a value from the reader, returning `None` if the
+    /// reader ended before the start of the value
+    pub fn read_from<R: BufRead>(reader: &mut R) -> io::Result<Option<Bencode>> {
+        match peek_byte(reader)? {
+            Some(_) => read_value(reader, 0).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        match self {
+            Bencode::Integer(n) => write!(writer, "i{}e", n),
+            Bencode::Bytes(bytes) => {
+                write!(writer, "{}:", bytes.len())?;
+                writer.write_all(bytes)
+            }
+            Bencode::List(values) => {
+                writer.write_all(b"l")?;
+                for value in values {
+                    value.write_to(writer)?;
+                }
+                writer.write_all(b"e")
+            }
+            Bencode::Dict(entries) => {
+                writer.write_all(b"d")?;
+                for (key, value) in entries {
+                    write!(writer, "{}:", key.len())?;
+                    writer.write_all(key)?;
+                    value.write_to(writer)?;
+                }
+                writer.write_all(b"e")
+            }
+        }
+    }
+
+    /// Returns the value associated to a key, if this value is a dictionary
+    pub fn get(&self, key: &str) -> Option<&Bencode> {
+        match self {
+            Bencode::Dict(entries) => entries.get(key.as_bytes()),
+            _ => None,
+        }
+    }
+
+    /// Returns the value associated to a key as a string, if this
+    /// value is a dictionary and the value is a valid UTF-8 string
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        match self.get(key) {
+            Some(Bencode::Bytes(bytes)) => std::str::from_utf8(bytes).ok(),
+            _ => None,
+        }
+    }
+}
+
+impl From<&str> for Bencode {
+    fn from(string: &str) -> Bencode {
+        Bencode::Bytes(string.as_bytes().to_vec())
+    }
+}
+
+impl From<String> for Bencode {
+    fn from(string: String) -> Bencode {
+        Bencode::Bytes(string.into_bytes())
+    }
+}
+
+impl<T: Into<Bencode>> From<Vec<T>> for Bencode {
+    fn from(values: Vec<T>) -> Bencode {
+        Bencode::List(values.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<K: Into<String>, V: Into<Bencode>> FromIterator<(K, V)> for Bencode {
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Bencode {
+        Bencode::Dict(
+            iter.into_iter()
+                .map(|(key, value)| (key.into().into_bytes(), value.into()))
+                .collect(),
+        )
+    }
+}
+
+fn invalid_data(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+fn peek_byte<R: BufRead>(reader: &mut R) -> io::Result<Option<u8>> {
+    Ok(reader.fill_buf()?.first().copied())
+}
+
+fn next_byte<R: BufRead>(reader: &mut R) -> io::Result<u8> {
+    let byte = peek_byte(reader)?.ok_or_else(|| Error::from(ErrorKind::UnexpectedEof))?;
+    reader.consume(1);
+    Ok(byte)
+}
+
+/// Reads the digits of a number until the terminator byte
+fn read_number<R: BufRead>(reader: &mut R, terminator: u8) -> io::Result<i64> {
+    let mut digits = String::new();
+    loop {
+        match next_byte(reader)? {
+            byte if byte == terminator => break,
+            // No valid number has more digits than `i64::MIN`
+            _ if digits.len() == 20 => return Err(invalid_data("Bencode number too long")),
+            byte @ (b'0'..=b'9' | b'-') => digits.push(byte as char),
+            _ => return Err(invalid_data("Invalid digit in a bencode number")),
+        }
+    }
+    digits
+        .parse()
+        .map_err(|_| invalid_data("Invalid bencode number"))
+}
+
+fn read_bytes<R: BufRead>(reader: &mut R) -> io::Result<Vec<u8>> {
+    let len = read_number(reader, b':')?;
+    let len = usize::try_from(len).map_err(|_| invalid_data("Negative bencode string length"))?;
+    if len > MAX_BYTES_LEN {
+        return Err(invalid_data("Bencode string too long"));
+    }
+    let mut bytes = vec![0; len];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn read_value<R: BufRead>(reader: &mut R, depth: usize) -> io::Result<Bencode> {
+    if depth > MAX_DEPTH {
+        return Err(invalid_data("Bencode value nested too deeply"));
+    }
+    match peek_byte(reader)?.ok_or_else(|| Error::from(ErrorKind::UnexpectedEof))? {
+        b'i' => {
+            reader.consume(1);
+            read_number(reader, b'e').map(Bencode::Integer)
+        }
+        b'l' => {
+            reader.consume(1);
+            let mut values = Vec::new();
+            while peek_byte(reader)? != Some(b'e') {
+                values.push(read_value(reader, depth + 1)?);
+            }
+            reader.consume(1);
+            Ok(Bencode::List(values))
+        }
+        b'd' => {
+            reader.consume(1);
+            let mut entries = BTreeMap::new();
+            while peek_byte(reader)? != Some(b'e') {
+                let key = read_bytes(reader)?;
+                entries.insert(key, read_value(reader, depth + 1)?);
+            }
+            reader.consume(1);
+            Ok(Bencode::Dict(entries))
+        }
+        b'0'..=b'9' => read_bytes(reader).map(Bencode::Bytes),
+        _ => Err(invalid_data("Invalid bencode value")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(input: &[u8]) -> io::Result<Option<Bencode>> {
+        Bencode::read_from(&mut &input[..])
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let value = [
+            ("op", Bencode::from("eval")),
+            ("id", Bencode::Integer(-42)),
+            ("list", Bencode::from(vec!["a", ""])),
+        ]
+        .into_iter()
+        .collect::<Bencode>();
+        let mut encoded = Vec::new();
+        value.write_to(&mut encoded).unwrap();
+        assert_eq!(encoded, b"d2:idi-42e4:listl1:a0:e2:op4:evale".to_vec());
+        assert_eq!(read(&encoded).unwrap(), Some(value.clone()));
+        assert_eq!(value.get_str("op"), Some("eval"));
+        assert_eq!(read(b"").unwrap(), None);
+    }
+
+    #[test]
+    fn test_malformed() {
+        let inputs: [&[u8]; 9] = [
+            b"x",
+            b"i12",
+            b"i1x2e",
+            b"i123456789012345678901234e",
+            b"d-1:ai1ee",
+            b"5:ab",
+            b"99999999999:a",
+            b"l1:a",
+            b"di1ei2ee",
+        ];
+        for input in inputs {
+            assert!(read(input).is_err(), "{:?}", String::from_utf8_lossy(input));
+        }
+
+        let nested = [b"l".repeat(MAX_DEPTH + 2), b"e".repeat(MAX_DEPTH + 2)].concat();
+        assert_eq!(read(&nested).unwrap_err().kind(), ErrorKind::InvalidData);
+        let nested = [b"l".repeat(MAX_DEPTH), b"e".repeat(MAX_DEPTH)].concat();
+        assert!(read(&nested).is_ok());
+    }
+}
@@ -0,0 +1,300 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    thread,
+};
+
+use miniclj_lib::IOHandler;
+
+use crate::session::Session;
+
+/// Implements the bencode format used by the nREPL messages
+mod bencode;
+
+use bencode::Bencode;
+
+const OPS: [&str; 7] = [
+    "clone",
+    "close",
+    "completions",
+    "describe",
+    "eval",
+    "load-file",
+    "ls-sessions",
+];
+
+/// Sends a response to a request, copying its id and session
+fn send<W: Write>(
+    writer: &mut W,
+    request: &Bencode,
+    entries: Vec<(&str, Bencode)>,
+) -> io::Result<()> {
+    let mut response = entries
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect::<HashMap<String, Bencode>>();
+    for key in ["id", "session"] {
+        if let Some(value) = request.get(key) {
+            response.insert(key.to_string(), value.clone());
+        }
+    }
+    response.into_iter().collect::<Bencode>().write_to(writer)?;
+    writer.flush()
+}
+
+fn status(values: &[&str]) -> Bencode {
+    Bencode::from(values.to_vec())
+}
+
+/// Sends the text printed during an evaluation as `out` messages.
+/// The clients can't send input to the evaluations, so reading from
+/// it returns the end of the input, instead of reading the input of
+/// the server
+struct OutputWriter {
+    stream: TcpStream,
+    request: Bencode,
+}
+
+impl IOHandler for OutputWriter {
+    fn print(&mut self, text: &str) -> io::Result<()> {
+        send(&mut self.stream, &self.request, vec![("out", text.into())])
+    }
+
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        Ok(None)
+    }
+}
+
+/// Stores the sessions created through a connection. Sessions can't
+/// be shared between threads, so each connection has its own ones
+struct Connection {
+    stream: TcpStream,
+    sessions: HashMap<String, Session>,
+    default_session: Session,
+    session_counter: usize,
+}
+
+impl Connection {
+    fn new(stream: TcpStream) -> Connection {
+        Connection {
+            stream,
+            sessions: HashMap::new(),
+            default_session: Session::default(),
+            session_counter: 0,
+        }
+    }
+
+    fn handle(&mut self, request: &Bencode) -> io::Result<()> {
+        match request.get_str("op").unwrap_or_default() {
+            "clone" => {
+                self.session_counter += 1;
+                let id = format!("miniclj-session-{}", self.session_counter);
+                self.sessions.insert(id.clone(), Session::default());
+                let entries = vec![("new-session", id.into()), ("status", status(&["done"]))];
+                send(&mut self.stream, request, entries)
+            }
+            "close" => {
+                if let Some(id) = request.get_str("session") {
+                    self.sessions.remove(id);
+                }
+                let entries = vec![("status", status(&["done", "session-closed"]))];
+                send(&mut self.stream, request, entries)
+            }
+            "ls-sessions" => {
+                let sessions = self.sessions.keys().cloned().collect::<Vec<String>>();
+                let entries = vec![("sessions", sessions.into()), ("status", status(&["done"]))];
+                send(&mut self.stream, request, entries)
+            }
+            "describe" => {
+                let ops = OPS
+                    .iter()
+                    .map(|op| (*op, Bencode::Dict(Default::default())))
+                    .collect::<Bencode>();
+                let version = [("version-string", env!("CARGO_PKG_VERSION"))]
+                    .into_iter()
+                    .collect::<Bencode>();
+                let versions = [("miniclj", version)].into_iter().collect::<Bencode>();
+                let entries = vec![
+                    ("ops", ops),
+                    ("versions", versions),
+                    ("status", status(&["done"])),
+                ];
+                send(&mut self.stream, request, entries)
+            }
+            "eval" => {
+                let code = request.get_str("code").unwrap_or_default().to_string();
+                self.eval(request, &code, false)
+            }
+            "load-file" => {
+                let code = request.get_str("file").unwrap_or_default().to_string();
+                self.eval(request, &code, true)
+            }
+            "completions" => {
+                let prefix = request
+                    .get_str("prefix")
+                    .or_else(|| request.get_str("symbol"))
+                    .unwrap_or_default();
+                let completions = match self.session(request) {
+                    Some(session) => session
+                        .symbols()
+                        .into_iter()
+                        .filter(|(symbol, _)| symbol.starts_with(prefix))
+                        .map(|(symbol, is_callable)| {
+                            let symbol_type = if is_callable { "function" } else { "var" };
+                            [("candidate", symbol), ("type", symbol_type.to_string())]
+                                .into_iter()
+                                .collect::<Bencode>()
+                        })
+                        .collect::<Vec<Bencode>>(),
+                    None => return self.unknown_session(request),
+                };
+                let entries = vec![
+                    ("completions", completions.into()),
+                    ("status", status(&["done"])),
+                ];
+                send(&mut self.stream, request, entries)
+            }
+            _ => send(
+                &mut self.stream,
+                request,
+                vec![("status", status(&["error", "unknown-op", "done"]))],
+            ),
+        }
+    }
+
+    /// Returns the session used by a request, which is the
+    /// default one of the connection if it doesn't specify one
+    fn session(&mut self, request: &Bencode) -> Option<&mut Session> {
+        match request.get_str("session") {
+            Some(id) => self.sessions.get_mut(id),
+            None => Some(&mut self.default_session),
+        }
+    }
+
+    fn unknown_session(&mut self, request: &Bencode) -> io::Result<()> {
+        send(
+            &mut self.stream,
+            request,
+            vec![("status", status(&["error", "unknown-session", "done"]))],
+        )
+    }
+
+    /// Evaluates the code in the session of the request, sending the
+    /// value of each expression (or only the last one, if `only_last`
+    /// is true) and the text printed while executing it
+    fn eval(&mut self, request: &Bencode, code: &str, only_last: bool) -> io::Result<()> {
+        let output = OutputWriter {
+            stream: self.stream.try_clone()?,
+            request: request.clone(),
+        };
+        let mut stream = self.stream.try_clone()?;
+        let session = match self.session(request) {
+            Some(session) => session,
+            None => return self.unknown_session(request),
+        };
+
+        let mut last_value = None;
+        let send_value = |stream: &mut TcpStream, value: String| {
+            let entries = vec![("value", value.into()), ("ns", "user".into())];
+            send(stream, request, entries)
+        };
+        let previous_io = session.set_io(Box::new(output));
+        let result = session.eval(code, |value| {
+            if only_last {
+                last_value = Some(value);
+            } else {
+                // Errors sending the value will show up
+                // while reading the next request
+                let _ = send_value(&mut stream, value);
+            }
+        });
+        session.set_io(previous_io);
+
+        if let Some(value) = last_value {
+            send_value(&mut stream, value)?;
+        }
+        if let Err(err) = result {
+            send(
+                &mut stream,
+                request,
+                vec![("err", format!("{}\n", err).into())],
+            )?;
+            let entries = vec![("ex", err.into()), ("status", status(&["eval-error"]))];
+            send(&mut stream, request, entries)?;
+        }
+        send(&mut stream, request, vec![("status", status(&["done"]))])
+    }
+}
+
+fn handle_connection(stream: TcpStream) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut connection = Connection::new(stream);
+    while let Some(request) = Bencode::read_from(&mut reader)? {
+        connection.handle(&request)?;
+    }
+    Ok(())
+}
+
+/// Starts a server that accepts nREPL connections on localhost,
+/// and writes the port to a `.nrepl-port` file so that
+/// editors can find it
+pub fn start(port: u16) -> Result<(), String> {
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .map_err(|err| format!("Couldn't start the nREPL server: {}", err))?;
+    let port = listener
+        .local_addr()
+        .map_err(|err| format!("Couldn't start the nREPL server: {}", err))?
+        .port();
+    println!(
+        "nREPL server started on port {} on host 127.0.0.1 - nrepl://127.0.0.1:{}",
+        port, port
+    );
+    if let Err(err) = fs::write(".nrepl-port", port.to_string()) {
+        eprintln!("Couldn't write the .nrepl-port file: {}", err);
+    }
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                thread::spawn(move || {
+                    if let Err(err) = handle_connection(stream) {
+                        eprintln!("nREPL connection error: {}", err);
+                    }
+                });
+            }
+            Err(err) => eprintln!("Couldn't accept an nREPL connection: {}", err),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eval_reads_end_of_input() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _) = listener.accept().unwrap();
+        let mut connection = Connection::new(server);
+        let request = [("op", "eval"), ("code", "(print \"a\") (read-line)")]
+            .into_iter()
+            .collect::<Bencode>();
+        connection.handle(&request).unwrap();
+        drop(connection);
+
+        let mut reader = BufReader::new(client);
+        let mut out = Vec::new();
+        let mut values = Vec::new();
+        while let Some(response) = Bencode::read_from(&mut reader).unwrap() {
+            out.extend(response.get_str("out").map(String::from));
+            values.extend(response.get_str("value").map(String::from));
+        }
+        assert_eq!(out, vec!["a"]);
+        assert_eq!(values, vec!["nil", "nil"]);
+    }
+}
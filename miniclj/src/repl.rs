@@ -0,0 +1,72 @@
+use rustyline::{error::ReadlineError, DefaultEditor};
+
+use crate::session::Session;
+
+/// Checks if every parenthesis, bracket and brace opened in
+/// the input has been closed, ignoring the ones inside of strings
+/// and comments
+fn is_balanced(input: &str) -> bool {
+    let mut depth = 0;
+    let mut chars = input.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            ';' => {
+                chars.by_ref().find(|c| *c == '\n');
+            }
+            '"' => loop {
+                match chars.next() {
+                    Some('\\') => {
+                        chars.next();
+                    }
+                    Some('"') => break,
+                    Some(_) => {}
+                    None => return false,
+                }
+            },
+            _ => {}
+        }
+    }
+    depth <= 0
+}
+
+/// Starts an interactive session that reads expressions from the
+/// standard input, until the user sends an EOF (Ctrl-D)
+pub fn start() -> Result<(), String> {
+    let mut editor =
+        DefaultEditor::new().map_err(|err| format!("Couldn't start the REPL: {}", err))?;
+    let mut session = Session::default();
+    let mut input = String::new();
+
+    loop {
+        let prompt = if input.is_empty() {
+            "user=> "
+        } else {
+            "  #_=> "
+        };
+        match editor.readline(prompt) {
+            Ok(line) => {
+                input.push_str(&line);
+                input.push('\n');
+                if input.trim().is_empty() {
+                    input.clear();
+                    continue;
+                }
+                if !is_balanced(&input) {
+                    continue;
+                }
+
+                let _ = editor.add_history_entry(input.trim_end());
+                if let Err(err) = session.eval(&input, |value| println!("{}", value)) {
+                    println!("{}", err);
+                }
+                input.clear();
+            }
+            // Ctrl-C discards the expression being written
+            Err(ReadlineError::Interrupted) => input.clear(),
+            Err(ReadlineError::Eof) => return Ok(()),
+            Err(err) => return Err(format!("Couldn't read input: {}", err)),
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use std::fs::read_to_string;
+
+use miniclj_lib::{Engine, EngineError, EnvPermissions, FsPermissions, IntoValue, RuntimeError};
+
+/// Runs a source code file as a script, with `*command-line-args*`
+/// bound to the rest of the arguments. Only the program writes to
+/// stdout, errors are written to stderr and returned as the exit
+/// status, so scripts can be used in shell pipelines
+pub fn run(filename: &str, args: Vec<&str>) -> i32 {
+    let source = match read_to_string(filename) {
+        Ok(source) => source,
+        Err(err) => {
+            eprintln!("Couldn't read file {}: {}", filename, err);
+            return 1;
+        }
+    };
+
+    let mut engine = Engine::new();
+    // Scripts are written and executed by the user, like in any other
+    // scripting language, so they can access every file and variable
+    engine
+        .vm_state_mut()
+        .set_fs_permissions(FsPermissions::unrestricted());
+    engine
+        .vm_state_mut()
+        .set_env_permissions(EnvPermissions::unrestricted());
+    let command_line_args = args.into_iter().map(String::from).collect::<Vec<_>>();
+    if let Err(err) = engine.set_global("*command-line-args*", command_line_args.into_value()) {
+        eprintln!("{}", err);
+        return 1;
+    }
+
+    match engine.eval_str(&source) {
+        Ok(_) => 0,
+        Err(EngineError::RuntimeError(RuntimeError::Exit(status))) => status,
+        Err(err) => {
+            eprintln!("{}", err);
+            1
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use miniclj_lib::{Engine, IOHandler, SExprsParser};
+
+/// Keeps the compiler and the virtual machine alive between inputs,
+/// so that definitions persist until the session is closed
+#[derive(Default)]
+pub struct Session {
+    engine: Engine,
+}
+
+impl Session {
+    /// Compiles and executes every expression in the input, calling
+    /// `on_value` with the readable representation of the value
+    /// returned by each one of them
+    pub fn eval<F: FnMut(String)>(&mut self, input: &str, mut on_value: F) -> Result<(), String> {
+        let tree = SExprsParser::parse(input).map_err(|e| format!("{}", e))?;
+
+        for expr in tree {
+            let value = self
+                .engine
+                .eval_expr(expr)
+                .map_err(|err| format!("{}", err))?;
+            on_value(format!("{}", value.readable()));
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the handler of the input and output of the code
+    /// executed in this session, returning the previous one
+    pub fn set_io(&self, io: Box<dyn IOHandler>) -> Box<dyn IOHandler> {
+        self.engine.vm_state().set_io(io)
+    }
+
+    /// Returns the symbols that can be used in this session, and
+    /// whether each one of them is a language callable
+    pub fn symbols(&self) -> Vec<(String, bool)> {
+        let compiler_state = self.engine.compiler_state();
+        let mut symbols = compiler_state
+            .global_symbols()
+            .into_iter()
+            .map(|symbol| (symbol.to_string(), false))
+            .chain(
+                compiler_state
+                    .callable_names()
+                    .into_iter()
+                    .map(|name| (name.to_string(), true)),
+            )
+            .collect::<Vec<_>>();
+        symbols.sort_unstable();
+        symbols
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(session: &mut Session, input: &str) -> Result<Vec<String>, String> {
+        let mut values = Vec::new();
+        session.eval(input, |value| values.push(value))?;
+        Ok(values)
+    }
+
+    #[test]
+    fn test_failed_definitions() {
+        let mut session = Session::default();
+        assert_eq!(eval(&mut session, "(def x 1)").unwrap(), vec!["1"]);
+
+        assert!(eval(&mut session, "(defn f [a] (g a))").is_err());
+        assert!(eval(&mut session, "(def y (do (def z 2) (g)))").is_err());
+        assert!(eval(&mut session, "(deftest t (is (g)))").is_err());
+        let symbols = session.symbols();
+        for name in ["f", "y", "z", "t"] {
+            assert!(!symbols.contains(&(name.to_string(), false)));
+        }
+        assert!(session.engine.compiler_state().tests().is_empty());
+        assert_eq!(
+            eval(&mut session, "(f 1)").unwrap_err(),
+            "Compilation error: Callable \"f\" not defined in the current scope"
+        );
+
+        assert!(eval(&mut session, "(def x (g))").is_err());
+        assert_eq!(
+            eval(&mut session, "(def w 3) [x w]").unwrap(),
+            vec!["3", "[1 3]"]
+        );
+    }
+}
@@ -0,0 +1,353 @@
+use std::{
+    collections::HashMap,
+    fs::{read_dir, read_to_string, write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use miniclj_lib::{Assertion, CompilerState, SExprsParser, VMState};
+
+/// Result of running a single test, or of loading
+/// a file that couldn't be compiled
+struct TestResult {
+    name: String,
+    assertions: Vec<Assertion>,
+    error: Option<String>,
+    duration: Duration,
+}
+
+impl TestResult {
+    fn failures(&self) -> impl Iterator<Item = &Assertion> {
+        self.assertions.iter().filter(|assertion| !assertion.passed)
+    }
+}
+
+struct FileResult {
+    path: String,
+    tests: Vec<TestResult>,
+}
+
+/// Runs the tests in the `*_test.clj` files found in `paths`,
+/// printing the failures and a summary of the results, and
+/// optionally writing them to a JUnit XML report
+pub fn run(paths: Vec<&str>, junit_path: Option<&str>) -> Result<(), String> {
+    let mut files = Vec::new();
+    for path in paths {
+        let path = Path::new(path);
+        if path.is_dir() {
+            find_test_files(path, &mut files)
+                .map_err(|e| format!("Couldn't read directory {}: {}", path.display(), e))?;
+        } else {
+            files.push(path.to_path_buf());
+        }
+    }
+
+    let results = files
+        .iter()
+        .map(|path| run_file(path))
+        .collect::<Result<Vec<FileResult>, String>>()?;
+
+    let mut num_tests = 0;
+    let mut num_assertions = 0;
+    let mut num_failures = 0;
+    let mut num_errors = 0;
+    for file in &results {
+        println!("Testing {}", file.path);
+        for test in &file.tests {
+            num_tests += 1;
+            num_assertions += test.assertions.len();
+            for assertion in test.failures() {
+                num_failures += 1;
+                println!("\nFAIL in ({}) ({})", test.name, file.path);
+                print_assertion(assertion);
+            }
+            if let Some(error) = &test.error {
+                num_errors += 1;
+                println!("\nERROR in ({}) ({})", test.name, file.path);
+                println!("{}", error);
+            }
+        }
+        println!();
+    }
+    println!(
+        "Ran {} tests containing {} assertions.",
+        num_tests, num_assertions
+    );
+    println!("{} failures, {} errors.", num_failures, num_errors);
+
+    if let Some(junit_path) = junit_path {
+        write(junit_path, junit_report(&results))
+            .map_err(|e| format!("Couldn't write file {}: {}", junit_path, e))?;
+    }
+
+    if num_failures + num_errors > 0 {
+        Err(format!(
+            "Tests failed with {} failure(s) and {} error(s)",
+            num_failures, num_errors
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+fn find_test_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    let mut entries = read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<PathBuf>>>()?;
+    entries.sort();
+
+    for path in entries {
+        let filename = path.file_name().unwrap().to_string_lossy();
+        if filename.starts_with('.') {
+            continue;
+        }
+        if path.is_dir() {
+            find_test_files(&path, files)?;
+        } else if filename.ends_with("_test.clj") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Compiles a file and runs each one of its tests in a new virtual
+/// machine, so that the state changed by a test isn't seen by the others
+fn run_file(path: &Path) -> Result<FileResult, String> {
+    let display_path = path.display().to_string();
+    let input =
+        read_to_string(path).map_err(|e| format!("Couldn't read file {}: {}", display_path, e))?;
+
+    let start_time = Instant::now();
+    let compile = || {
+        let tree = SExprsParser::parse(&input).map_err(|e| format!("{}", e))?;
+        let mut compiler_state = CompilerState::default();
+        for expr in tree {
+            compiler_state
+                .compile(expr)
+                .map_err(|err| format!("Compilation error: {}", err))?;
+        }
+        Ok(compiler_state)
+    };
+    let compiler_state = match compile() {
+        Ok(compiler_state) => compiler_state,
+        Err(error) => {
+            let test = TestResult {
+                name: display_path.clone(),
+                assertions: Vec::new(),
+                error: Some(error),
+                duration: start_time.elapsed(),
+            };
+            return Ok(FileResult {
+                path: display_path,
+                tests: vec![test],
+            });
+        }
+    };
+
+    let tests = compiler_state.tests().to_vec();
+    let (constants_rev, instructions) = compiler_state.into_parts();
+    let constants = constants_rev
+        .into_iter()
+        .map(|(constant, address)| (address, constant))
+        .collect::<HashMap<_, _>>();
+
+    let tests = tests
+        .into_iter()
+        .map(|(name, address)| {
+            let start_time = Instant::now();
+            let vm_state = VMState::new(constants.clone(), instructions.clone());
+            let error = vm_state
+                .execute()
+                .and_then(|_| vm_state.get_global(&address))
+                .and_then(|test| vm_state.execute_callable(&test, Vec::new()))
+                .err()
+                .map(|err| format!("Runtime error: {}", err));
+            TestResult {
+                name: name.to_string(),
+                assertions: vm_state.test_report().take_assertions(),
+                error,
+                duration: start_time.elapsed(),
+            }
+        })
+        .collect();
+
+    Ok(FileResult {
+        path: display_path,
+        tests,
+    })
+}
+
+fn print_assertion(assertion: &Assertion) {
+    if !assertion.contexts.is_empty() {
+        println!("{}", assertion.contexts.join(" "));
+    }
+    if let Some(message) = &assertion.message {
+        println!("{}", message);
+    }
+    if assertion.expr != assertion.expected {
+        println!("    expr: {}", assertion.expr);
+    }
+    println!("expected: {}", assertion.expected);
+    println!("  actual: {}", assertion.actual);
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn junit_report(results: &[FileResult]) -> String {
+    let count = |file: &FileResult| {
+        let failures = file
+            .tests
+            .iter()
+            .filter(|test| test.error.is_none() && test.failures().next().is_some())
+            .count();
+        let errors = file
+            .tests
+            .iter()
+            .filter(|test| test.error.is_some())
+            .count();
+        (failures, errors)
+    };
+
+    let (total_failures, total_errors) = results
+        .iter()
+        .map(count)
+        .fold((0, 0), |(f, e), (file_f, file_e)| (f + file_f, e + file_e));
+    let total_tests = results.iter().map(|file| file.tests.len()).sum::<usize>();
+
+    let mut report = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    report.push_str(&format!(
+        "<testsuites tests=\"{}\" failures=\"{}\" errors=\"{}\">\n",
+        total_tests, total_failures, total_errors
+    ));
+    for file in results {
+        let (failures, errors) = count(file);
+        let time = file
+            .tests
+            .iter()
+            .map(|test| test.duration)
+            .sum::<Duration>();
+        report.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{:.3}\">\n",
+            escape_xml(&file.path),
+            file.tests.len(),
+            failures,
+            errors,
+            time.as_secs_f64()
+        ));
+        for test in &file.tests {
+            report.push_str(&format!(
+                "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+                escape_xml(&test.name),
+                escape_xml(&file.path),
+                test.duration.as_secs_f64()
+            ));
+            let failures = test.failures().collect::<Vec<&Assertion>>();
+            if test.error.is_none() && failures.is_empty() {
+                report.push_str("/>\n");
+                continue;
+            }
+            report.push_str(">\n");
+            if let Some(error) = &test.error {
+                report.push_str(&format!(
+                    "      <error message=\"{}\"/>\n",
+                    escape_xml(error)
+                ));
+            } else {
+                let details = failures
+                    .iter()
+                    .map(|assertion| {
+                        format!(
+                            "{}expected: {}\n  actual: {}",
+                            assertion
+                                .message
+                                .as_ref()
+                                .map_or_else(String::new, |message| format!("{}\n", message)),
+                            assertion.expected,
+                            assertion.actual
+                        )
+                    })
+                    .collect::<Vec<String>>()
+                    .join("\n\n");
+                report.push_str(&format!(
+                    "      <failure message=\"{} assertion(s) failed\">{}</failure>\n",
+                    failures.len(),
+                    escape_xml(&details)
+                ));
+            }
+            report.push_str("    </testcase>\n");
+        }
+        report.push_str("  </testsuite>\n");
+    }
+    report.push_str("</testsuites>\n");
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        env::temp_dir,
+        fs::{create_dir_all, remove_file},
+        process,
+    };
+
+    use super::*;
+
+    /// Runs a test file with the given source, replacing its path
+    /// and the durations so that the report is always the same
+    fn run_source(filename: &str, source: &str) -> FileResult {
+        let dir = temp_dir().join(format!("miniclj_testrunner_{}", process::id()));
+        create_dir_all(&dir).unwrap();
+        let path = dir.join(filename);
+        write(&path, source).unwrap();
+        let mut file = run_file(&path).unwrap();
+        remove_file(&path).unwrap();
+        for test in &mut file.tests {
+            // Files that don't compile are reported as a test named like the file
+            if test.name == file.path {
+                test.name = filename.to_string();
+            }
+            test.duration = Duration::ZERO;
+        }
+        file.path = filename.to_string();
+        file
+    }
+
+    #[test]
+    fn test_junit_report() {
+        let math = run_source(
+            "math_test.clj",
+            "(deftest passing (is (= 2 (+ 1 1))))
+            (deftest failing (testing \"math\" (is (= 3 (+ 1 1)) \"sum <wrong>\")))
+            (deftest erroring (is (= 1 (/ 1 0))))",
+        );
+        let broken = run_source("broken_test.clj", "(deftest broken (is (undefined)))");
+        assert_eq!(
+            junit_report(&[math, broken]),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites tests="4" failures="1" errors="2">
+  <testsuite name="math_test.clj" tests="3" failures="1" errors="1" time="0.000">
+    <testcase name="passing" classname="math_test.clj" time="0.000"/>
+    <testcase name="failing" classname="math_test.clj" time="0.000">
+      <failure message="1 assertion(s) failed">sum &lt;wrong&gt;
+expected: 3
+  actual: 2</failure>
+    </testcase>
+    <testcase name="erroring" classname="math_test.clj" time="0.000">
+      <error message="Runtime error: Division by zero"/>
+    </testcase>
+  </testsuite>
+  <testsuite name="broken_test.clj" tests="1" failures="0" errors="1" time="0.000">
+    <testcase name="broken_test.clj" classname="broken_test.clj" time="0.000">
+      <error message="Compilation error: Callable &quot;undefined&quot; not defined in the current scope"/>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#
+        );
+    }
+}
@@ -1,10 +1,11 @@
-import React, { useEffect, useState } from 'react';
+import React, { useEffect, useRef, useState } from 'react';
 import {
   Alert,
   AlertDescription,
   AlertIcon,
   AlertTitle,
   Box,
+  Button,
   Tab,
   TabList,
   TabPanel,
@@ -38,6 +39,11 @@ const ResultTab = (props: ResultTabProps): JSX.Element => (
   </>
 );
 
+// Programs are executed in slices of instructions, updating
+// the page between them so that long programs don't freeze it
+const SLICE_INSTRUCTIONS = 100_000;
+const MAX_CALL_DEPTH = 1_000;
+
 interface MinicljOutputState {
   ast: ResultTabProps;
   compile: ResultTabProps;
@@ -54,20 +60,53 @@ const MinicljOutput = dynamic(
         compile: { status: 'ok', output: '' },
         run: { status: 'ok', output: '' },
       });
+      const [executedInstructions, setExecutedInstructions] = useState<
+        number | null
+      >(null);
+      const programRef = useRef<any>(null);
 
       useEffect(() => {
-        (window as any).minicljoutput = '';
-
-        const newState = {
+        setState({
           ast: miniclj.ast(code),
           compile: miniclj.compile(code),
-          run: miniclj.run(code),
-        };
-        if (newState.run.status === 'ok') {
-          newState.run.output = (window as any).minicljoutput as string;
+          run: { status: 'ok', output: '' },
+        });
+
+        let program: any;
+        try {
+          program = new miniclj.Program(code, MAX_CALL_DEPTH);
+        } catch (error) {
+          setState((state) => ({ ...state, run: error as ResultTabProps }));
+          return;
         }
+        programRef.current = program;
 
-        setState(newState);
+        let timeout: ReturnType<typeof setTimeout>;
+        const finish = () => {
+          clearTimeout(timeout);
+          if (programRef.current === program) {
+            programRef.current = null;
+            setExecutedInstructions(null);
+          }
+          program.free();
+        };
+        const runSlice = () => {
+          const result = program.run_for(SLICE_INSTRUCTIONS);
+          if (result.status === 'yielded') {
+            setExecutedInstructions(program.executed_instructions());
+            timeout = setTimeout(runSlice, 0);
+            return;
+          }
+          setState((state) => ({ ...state, run: result }));
+          finish();
+        };
+        timeout = setTimeout(runSlice, 0);
+
+        return () => {
+          if (programRef.current === program) {
+            finish();
+          }
+        };
       }, [code]);
 
       return (
@@ -85,6 +124,18 @@ const MinicljOutput = dynamic(
               <ResultTab {...state.compile} />
             </TabPanel>
             <TabPanel>
+              {executedInstructions !== null && (
+                <Box mb="1em">
+                  Running, {executedInstructions} instructions executed{' '}
+                  <Button
+                    size="sm"
+                    ml="1em"
+                    onClick={() => programRef.current?.cancel()}
+                  >
+                    Stop
+                  </Button>
+                </Box>
+              )}
               <ResultTab {...state.run} />
             </TabPanel>
           </TabPanels>